@@ -61,6 +61,45 @@ pub static ACCENT_LIGHT_BLUE: LazyLock<Color> =
 // #fff0
 pub static TRANSPARENT: Color = Color::linear_rgba(1.0, 1.0, 1.0, 0.0);
 
+// #fcf1bd
+pub static PALE_YELLOW: LazyLock<Color> =
+    LazyLock::new(|| Color::Srgba(Srgba::hex("fcf1bd").unwrap()));
+// #7d6500
+pub static DARK_YELLOW: LazyLock<Color> =
+    LazyLock::new(|| Color::Srgba(Srgba::hex("7d6500").unwrap()));
+// #ff4b56
+pub static CONFLICT_RED: LazyLock<Color> =
+    LazyLock::new(|| Color::Srgba(Srgba::hex("ff4b56").unwrap()));
+
+// 色盲友好的 Okabe-Ito 配色
+// #e69f00
+pub static OKABE_ORANGE: LazyLock<Color> =
+    LazyLock::new(|| Color::Srgba(Srgba::hex("e69f00").unwrap()));
+// #56b4e9
+pub static OKABE_SKY_BLUE: LazyLock<Color> =
+    LazyLock::new(|| Color::Srgba(Srgba::hex("56b4e9").unwrap()));
+// #009e73
+pub static OKABE_GREEN: LazyLock<Color> =
+    LazyLock::new(|| Color::Srgba(Srgba::hex("009e73").unwrap()));
+// #f0e442
+pub static OKABE_YELLOW: LazyLock<Color> =
+    LazyLock::new(|| Color::Srgba(Srgba::hex("f0e442").unwrap()));
+// #0072b2
+pub static OKABE_BLUE: LazyLock<Color> =
+    LazyLock::new(|| Color::Srgba(Srgba::hex("0072b2").unwrap()));
+// #d55e00
+pub static OKABE_VERMILLION: LazyLock<Color> =
+    LazyLock::new(|| Color::Srgba(Srgba::hex("d55e00").unwrap()));
+// #cc79a7
+pub static OKABE_PURPLE: LazyLock<Color> =
+    LazyLock::new(|| Color::Srgba(Srgba::hex("cc79a7").unwrap()));
+// #d6ecf9
+pub static PALE_SKY_BLUE: LazyLock<Color> =
+    LazyLock::new(|| Color::Srgba(Srgba::hex("d6ecf9").unwrap()));
+// #f5dbe9
+pub static PALE_PURPLE: LazyLock<Color> =
+    LazyLock::new(|| Color::Srgba(Srgba::hex("f5dbe9").unwrap()));
+
 /// #f8cd05
 pub static STRANDS_YELLOW: LazyLock<Color> =
     LazyLock::new(|| Color::Srgba(Srgba::hex("f8cd05").unwrap()));
@@ -2,19 +2,42 @@ use crate::{
     GameState,
     color::*,
     game::{
+        auto_candidates::CandidateElimination,
         board::{ConflictContainer, PreviewCandidate, play_board},
         cell_state::{
             AutoCandidates, CandidatesValue, CellMode, CellValueBundle, ConflictCell,
-            CorrectionCell, DigitValueCell, FixedCell, ManualCandidates, RevealedCell,
-            SelectedCell,
+            CornerCandidates, CorrectionCell, DigitValueCell, FixedCell, ManualCandidates,
+            RevealedCell, SelectedCell,
         },
         control_tab::control_board,
-        dialog::{Opened, PauseGame, ShowCongrats, ShowHint, ShowSettings, dialog_container},
-        input::{keyboard_input, keyboard_move_cell},
+        coop::{CoopLink, PingPartner, coop_status},
+        dialog::{
+            Confirm, Opened, PauseGame, ShowHint, ShowSettings, dialog_container, no_dialog_open,
+        },
+        generator::{
+            GeneratingPuzzle, Puzzle, PuzzleGenerated, PuzzlePool, request_puzzle, technique_label,
+        },
+        guided_solve::{GuidedSolve, StartGuidedSolve},
+        input::{
+            board_input_allowed, keyboard_input, keyboard_move_cell, press_feedback,
+            touch_swipe_tab,
+        },
+        layout::{BoardBody, ToolbarSide},
+        lessons::ActiveLesson,
+        mistakes::{Mistakes, mistakes_counter},
+        pencil_marks::{FillCandidates, RemoveImpossibleCandidates},
         position::CellPosition,
+        progress_check::CheckProgress,
+        replay::WatchReplay,
+        scoring::{PuzzleSolved, difficulty_label},
+        sharing::{PasteCode, PendingShare, SharePuzzle, ShareResult},
+        trial::{TrialControl, trial_bar},
+        undo::Undo,
     },
+    i18n::{Language, Localized},
     loading::{AudioAssets, FontAssets, TextureAssets},
     share::title_bar,
+    theme::{ColorBlindPalette, ThemeRole, ThemedBackground, ThemedBorder, ThemedText},
 };
 use bevy::{platform::collections::HashSet, prelude::*, time::Stopwatch};
 use bevy_kira_audio::{Audio, AudioControl};
@@ -25,12 +48,37 @@ use sudoku::{
     strategy::StrategySolver,
 };
 
+mod accessibility;
+mod auto_candidates;
 mod board;
 mod cell_state;
 mod control_tab;
+mod coop;
 mod dialog;
+mod editor;
+mod generator;
+mod guided_solve;
 mod input;
+mod layout;
+mod lessons;
+mod mistakes;
+mod pause;
+mod pencil_marks;
 mod position;
+mod progress_check;
+mod race;
+mod replay;
+mod scoring;
+mod screen_reader;
+mod sharing;
+mod stats;
+mod trial;
+mod undo;
+
+pub(crate) use generator::{
+    CustomPuzzle, next_clue_range, next_hardest_other, next_technique, technique_name,
+};
+pub(crate) use scoring::Difficulty;
 
 pub struct SudokuPlugin;
 
@@ -41,6 +89,26 @@ impl Plugin for SudokuPlugin {
         control_tab::plugin(app);
         board::plugin(app);
         dialog::plugin(app);
+        editor::plugin(app);
+        generator::plugin(app);
+        layout::plugin(app);
+        accessibility::plugin(app);
+        auto_candidates::plugin(app);
+        guided_solve::plugin(app);
+        lessons::plugin(app);
+        mistakes::plugin(app);
+        pause::plugin(app);
+        pencil_marks::plugin(app);
+        progress_check::plugin(app);
+        stats::plugin(app);
+        scoring::plugin(app);
+        replay::plugin(app);
+        race::plugin(app);
+        coop::plugin(app);
+        sharing::plugin(app);
+        screen_reader::plugin(app);
+        trial::plugin(app);
+        undo::plugin(app);
         app.init_resource::<AutoCandidateMode>()
             .init_resource::<Settings>()
             .add_event::<MoveSelectCell>()
@@ -49,24 +117,30 @@ impl Plugin for SudokuPlugin {
             .add_systems(
                 Update,
                 (
-                    keyboard_input,
+                    keyboard_input.run_if(board_input_allowed),
                     update_game_time,
-                    keyboard_move_cell,
+                    keyboard_move_cell.run_if(no_dialog_open),
+                    touch_swipe_tab.run_if(board_input_allowed),
+                    press_feedback,
                     show_conflict,
-                    kick_candidates,
-                    check_solver,
+                    kick_candidates.after(keyboard_input),
+                    // 讲解和课程里填上的数字不算解出
+                    check_solver.run_if(
+                        not(resource_exists::<GuidedSolve>)
+                            .and(not(resource_exists::<ActiveLesson>)),
+                    ),
                 )
                     .run_if(in_state(GameState::Playing)),
             )
             .add_observer(on_new_digit)
             .add_observer(on_new_candidate)
+            .add_observer(on_new_corner_candidate)
             .add_observer(check_conflict)
             .add_observer(init_puzzle)
+            .add_observer(on_puzzle_generated)
             .add_observer(find_hint)
             .add_observer(on_clean_cell)
-            .add_observer(on_select_cell)
             .add_observer(remove_conflict)
-            .add_observer(on_unselect_cell)
             .add_observer(on_reset_puzzle)
             .add_observer(on_reveal_cell)
             .add_observer(on_reveal_puzzle)
@@ -75,7 +149,7 @@ impl Plugin for SudokuPlugin {
     }
 }
 
-#[derive(Resource, Debug)]
+#[derive(Resource, Debug, Clone)]
 pub struct SudokuManager {
     pub solution: Sudoku,
     pub solver: StrategySolver,
@@ -124,7 +198,7 @@ fn setup_ui(
                         flex_direction: FlexDirection::Column,
                         ..default()
                     },
-                    BackgroundColor(WHITE_COLOR),
+                    ThemedBackground(ThemeRole::Surface),
                 ))
                 .with_children(|builder| {
                     // 工具栏
@@ -144,13 +218,16 @@ fn setup_ui(
                             dialog_container(&font_assets, builder);
 
                             builder
-                                .spawn(Node {
-                                    display: Display::Flex,
-                                    align_items: AlignItems::Stretch,
-                                    justify_content: JustifyContent::Center,
-                                    margin: UiRect::axes(Val::Auto, Val::Px(20.0)),
-                                    ..default()
-                                })
+                                .spawn((
+                                    Node {
+                                        display: Display::Flex,
+                                        align_items: AlignItems::Stretch,
+                                        justify_content: JustifyContent::Center,
+                                        margin: UiRect::axes(Val::Auto, Val::Px(20.0)),
+                                        ..default()
+                                    },
+                                    BoardBody,
+                                ))
                                 .with_children(|builder| {
                                     // 格子布局容器
                                     play_board(&font_assets, &texture_assets, builder);
@@ -177,7 +254,7 @@ fn toolbars(
                 border: UiRect::vertical(Val::Px(1.0)),
                 ..default()
             },
-            BorderColor(*EXTRA_LIGHT_GRAY),
+            ThemedBorder(ThemeRole::Border),
         ))
         .with_children(|builder| {
             builder
@@ -230,6 +307,7 @@ fn right_bar(
                 ..default()
             },
             // BackgroundColor(Color::linear_rgba(0.1, 0.95, 0.95, 0.5)),
+            ToolbarSide,
         ))
         .with_children(|builder| {
             builder
@@ -316,19 +394,11 @@ fn center_bar(
             },
         ))
         .with_children(|builder| {
-            builder.spawn((
-                Text::new("Easy"),
-                TextFont {
-                    font_size: 18.0,
-                    font: font_assets.franklin_500.clone(),
-                    ..default()
-                },
-                TextColor::BLACK,
-                Node {
-                    margin: UiRect::horizontal(Val::Px(16.0)),
-                    ..default()
-                },
-            ));
+            coop_status(font_assets, builder);
+            mistakes_counter(font_assets, builder);
+            difficulty_label(font_assets, builder);
+            technique_label(font_assets, builder);
+            trial_bar(font_assets, builder);
             builder.spawn((
                 Text::new("1:02:34"),
                 TextFont {
@@ -336,7 +406,7 @@ fn center_bar(
                     font: font_assets.franklin_500.clone(),
                     ..default()
                 },
-                TextColor(*DARK_BLACK),
+                ThemedText(ThemeRole::Text),
                 TimerText,
             ));
 
@@ -385,6 +455,7 @@ fn left_bar(
                 justify_content: JustifyContent::FlexStart,
                 ..default()
             },
+            ToolbarSide,
         ))
         .with_children(|builder| {
             builder
@@ -413,13 +484,13 @@ fn left_bar(
                     ));
 
                     builder.spawn((
-                        Text::new("Back"),
+                        Localized::new("Back"),
                         TextFont {
                             font_size: 16.0,
                             font: font_assets.franklin_500.clone(),
                             ..default()
                         },
-                        TextColor(*DARK_BLACK),
+                        ThemedText(ThemeRole::Text),
                     ));
                 })
                 .observe(
@@ -454,25 +525,122 @@ pub struct AutoCandidatesContainer;
 #[derive(Event)]
 pub struct InitPuzzle;
 
+/// 新题目已经放到棋盘上, 这一局从这里开始
+#[derive(Event)]
+pub struct PuzzleStarted;
+
+#[allow(clippy::too_many_arguments)]
 fn init_puzzle(
     _ev: Trigger<InitPuzzle>,
     mut commands: Commands,
     cell_background: Query<(Entity, &CellPosition)>,
     settings: Res<Settings>,
-    mut auto: ResMut<AutoCandidateMode>,
+    auto: ResMut<AutoCandidateMode>,
+    mut pending: ResMut<PendingShare>,
+    mut pool: ResMut<PuzzlePool>,
+    custom: Res<CustomPuzzle>,
 ) {
-    let (sudoku, solution) = loop {
-        let sudoku = Sudoku::generate();
-        if let Some(solution) = sudoku.solution() {
-            break (sudoku, solution);
-        }
-    };
+    // 分享的题目优先, 带着分享者填好的格子
+    let shared = pending
+        .0
+        .take()
+        .and_then(|shared| Some((shared.puzzle.solution()?, shared)));
+    if let Some((solution, shared)) = shared {
+        commands.remove_resource::<GeneratingPuzzle>();
+        start_puzzle(
+            &mut commands,
+            &cell_background,
+            &settings,
+            auto,
+            (shared.puzzle, solution),
+            shared.progress,
+            shared.marks,
+        );
+    } else if let Some(puzzle) = request_puzzle(&mut commands, &mut pool, &settings, &custom) {
+        start_puzzle(
+            &mut commands,
+            &cell_background,
+            &settings,
+            auto,
+            puzzle,
+            None,
+            None,
+        );
+    }
+}
+
+/// 后台生成的题目准备好后开始这一局
+fn on_puzzle_generated(
+    trigger: Trigger<PuzzleGenerated>,
+    mut commands: Commands,
+    cell_background: Query<(Entity, &CellPosition)>,
+    settings: Res<Settings>,
+    auto: ResMut<AutoCandidateMode>,
+) {
+    start_puzzle(
+        &mut commands,
+        &cell_background,
+        &settings,
+        auto,
+        trigger.event().0,
+        None,
+        None,
+    );
+}
 
+fn start_puzzle(
+    commands: &mut Commands,
+    cell_background: &Query<(Entity, &CellPosition)>,
+    settings: &Settings,
+    mut auto: ResMut<AutoCandidateMode>,
+    (sudoku, solution): Puzzle,
+    progress: Option<[u8; 81]>,
+    marks: Option<[(u16, u16); 81]>,
+) {
     info!("sudoku: {:?}", sudoku);
     if settings.start_in_automatic_mode {
         *auto = AutoCandidateMode(true);
     }
+    // 从题目出现时开始计时
+    commands.insert_resource(GameTimer(Stopwatch::new()));
+
+    load_sudoku(commands, cell_background, sudoku, solution);
+    commands.trigger(PuzzleStarted);
+
+    if let Some(progress) = progress {
+        for (entity, cell_position) in cell_background.iter() {
+            let digit = progress[cell_position.0 as usize];
+            if digit != 0 {
+                commands
+                    .entity(entity)
+                    .insert((DigitValueCell(Some(Digit::new(digit))), CellMode::Digit));
+            }
+        }
+    }
+    if let Some(marks) = marks {
+        for (entity, cell_position) in cell_background.iter() {
+            let (centre, corner) = marks[cell_position.0 as usize];
+            if (centre, corner) == (0, 0) {
+                continue;
+            }
+            commands.entity(entity).insert((
+                ManualCandidates(Set::from_bits(centre)),
+                CornerCandidates(Set::from_bits(corner)),
+            ));
+            if centre != 0 {
+                commands.entity(entity).insert(CellMode::ManualCandidates);
+            }
+        }
+    }
+}
 
+/// 把题目放到棋盘上, 题目给出的数字成为固定格子
+pub(crate) fn load_sudoku(
+    commands: &mut Commands,
+    cell_background: &Query<(Entity, &CellPosition)>,
+    sudoku: Sudoku,
+    solution: Sudoku,
+) {
     let solver = StrategySolver::from_sudoku(sudoku);
 
     commands.insert_resource(SudokuManager {
@@ -488,13 +656,9 @@ fn init_puzzle(
             if cell_position.0 == index as u8 {
                 // 如果一开始就是数字，那么这个格子是固定颜色
                 if bundle.cell_mode == CellMode::Digit {
-                    commands
-                        .entity(entity)
-                        .insert(bundle)
-                        .insert(FixedCell)
-                        .insert(BackgroundColor(*EXTRA_LIGHT_GRAY));
+                    commands.entity(entity).insert(bundle).insert(FixedCell);
                 } else {
-                    commands.entity(entity).insert(bundle);
+                    commands.entity(entity).insert(bundle).remove::<FixedCell>();
                 }
 
                 // 如果是第一个格子，那么选中
@@ -513,27 +677,7 @@ fn init_puzzle(
     }
 }
 
-fn on_select_cell(trigger: Trigger<OnInsert, SelectedCell>, mut cell: Query<&mut BackgroundColor>) {
-    let entity = trigger.target();
-    if let Ok(mut background) = cell.get_mut(entity) {
-        background.0 = *STRANDS_YELLOW;
-    }
-}
-
-fn on_unselect_cell(
-    trigger: Trigger<OnRemove, SelectedCell>,
-    mut cell: Query<(&mut BackgroundColor, Option<&FixedCell>)>,
-) {
-    let entity = trigger.target();
-    if let Ok((mut background, opt_fixed)) = cell.get_mut(entity) {
-        if opt_fixed.is_some() {
-            background.0 = *EXTRA_LIGHT_GRAY;
-        } else {
-            background.0 = WHITE_COLOR;
-        }
-    }
-}
-
+#[allow(clippy::collapsible_if)]
 fn on_new_digit(
     trigger: Trigger<NewDigit>,
     mut q_cell: Query<
@@ -558,7 +702,7 @@ fn on_new_digit(
         commands.trigger(CheckDigitConflict);
 
         if settings.check_guesses_when_entered {
-            commands.trigger_targets(CheckCell, vec![entity]);
+            commands.trigger_targets(CheckCell { automatic: true }, vec![entity]);
         }
     }
 }
@@ -572,19 +716,15 @@ fn on_new_candidate(
             &mut AutoCandidates,
             &mut CellMode,
         ),
-        (
-            With<SelectedCell>,
-            Without<FixedCell>,
-            Without<RevealedCell>,
-        ),
+        (Without<FixedCell>, Without<RevealedCell>),
     >,
     auto_mode: Res<AutoCandidateMode>,
     mut commands: Commands,
 ) {
     let new_candidate = trigger.event().0;
 
-    for (mut digit_value, mut manual_candidates, mut auto_candidates, mut cell_mode) in
-        q_cell.iter_mut()
+    if let Ok((mut digit_value, mut manual_candidates, mut auto_candidates, mut cell_mode)) =
+        q_cell.get_mut(trigger.target())
     {
         debug!("new candidate: {:?}", new_candidate);
         match cell_mode.as_ref() {
@@ -613,6 +753,33 @@ fn on_new_candidate(
     }
 }
 
+fn on_new_corner_candidate(
+    trigger: Trigger<NewCornerCandidate>,
+    mut q_cell: Query<
+        (&mut DigitValueCell, &mut CornerCandidates, &mut CellMode),
+        (Without<FixedCell>, Without<RevealedCell>),
+    >,
+    auto_mode: Res<AutoCandidateMode>,
+    mut commands: Commands,
+) {
+    if let Ok((mut digit_value, mut corner_candidates, mut cell_mode)) =
+        q_cell.get_mut(trigger.target())
+    {
+        if *cell_mode == CellMode::Digit {
+            if let Some(digit) = digit_value.0 {
+                commands.trigger(RemoveDigit(digit));
+            }
+            digit_value.0 = None;
+            if **auto_mode {
+                *cell_mode = CellMode::AutoCandidates;
+            } else {
+                *cell_mode = CellMode::ManualCandidates;
+            }
+        }
+        corner_candidates.insert(trigger.event().0);
+    }
+}
+
 fn on_clean_cell(
     trigger: Trigger<CleanCell>,
     mut q_cell: Query<
@@ -620,6 +787,7 @@ fn on_clean_cell(
             Entity,
             &mut DigitValueCell,
             &mut ManualCandidates,
+            &mut CornerCandidates,
             &mut CellMode,
         ),
         (Without<FixedCell>, Without<RevealedCell>),
@@ -629,8 +797,13 @@ fn on_clean_cell(
     q_preview: Query<&PreviewCandidate>,
     mut commands: Commands,
 ) {
-    if let Ok((entity, mut digit_value, mut manual_candidates, mut cell_mode)) =
-        q_cell.get_mut(trigger.target())
+    if let Ok((
+        entity,
+        mut digit_value,
+        mut manual_candidates,
+        mut corner_candidates,
+        mut cell_mode,
+    )) = q_cell.get_mut(trigger.target())
     {
         match *cell_mode {
             CellMode::Digit => {
@@ -638,14 +811,18 @@ fn on_clean_cell(
                     commands.trigger(RemoveDigit(digit));
                 }
                 digit_value.0 = None;
+                corner_candidates.0 = Set::NONE;
                 if **auto_mode {
                     *cell_mode = CellMode::AutoCandidates;
                 } else {
                     *cell_mode = CellMode::ManualCandidates;
                 }
             }
-            CellMode::AutoCandidates => {}
-            CellMode::ManualCandidates => manual_candidates.0 = Set::NONE,
+            CellMode::AutoCandidates => corner_candidates.0 = Set::NONE,
+            CellMode::ManualCandidates => {
+                manual_candidates.0 = Set::NONE;
+                corner_candidates.0 = Set::NONE;
+            }
         }
         commands
             .entity(entity)
@@ -672,34 +849,36 @@ fn check_solver(
         return;
     }
 
-    let mut solved_count = 0;
-    for (cell_value, cell_position) in cell_query
-        .iter()
-        .sort_by::<&CellPosition>(|t1, t2| t1.0.cmp(&t2.0))
-    {
-        if let Some(digit) = cell_value.0 {
-            for (index, num) in sudoku_manager.solution.iter().enumerate() {
-                if cell_position.0 == index as u8 && Some(num.unwrap()) == Some(digit.get()) {
-                    solved_count += 1;
-                }
-            }
-        }
+    if correct_cells(&sudoku_manager.solution, cell_query.iter()) == 81 {
+        sudoku_manager.is_solved = true;
 
-        if solved_count == 81 {
-            sudoku_manager.is_solved = true;
-
-            if settings.play_sound_on_solve {
-                audio
-                    .play(audio_assets.congrats.clone())
-                    // .with_volume(0.3)
-                    .handle();
-            }
-
-            commands.trigger(ShowCongrats(true));
+        if settings.play_sound_on_solve {
+            audio
+                .play(audio_assets.congrats.clone())
+                // .with_volume(0.3)
+                .handle();
         }
+
+        commands.trigger(PuzzleSolved);
     }
 }
 
+/// 和答案一致的格子数, 81 格全对就算解出
+pub(crate) fn correct_cells<'a>(
+    solution: &Sudoku,
+    cells: impl IntoIterator<Item = (&'a DigitValueCell, &'a CellPosition)>,
+) -> usize {
+    let solution = solution.to_bytes();
+    cells
+        .into_iter()
+        .filter(|(cell_value, cell_position)| {
+            cell_value
+                .0
+                .is_some_and(|digit| solution[cell_position.0 as usize] == digit.get())
+        })
+        .count()
+}
+
 #[derive(Event)]
 pub struct CleanCell;
 
@@ -712,6 +891,16 @@ impl NewCandidate {
     }
 }
 
+/// 切换角标, 和 `NewCandidate` 的中心候选数互不影响
+#[derive(Event)]
+pub struct NewCornerCandidate(pub Digit);
+
+impl NewCornerCandidate {
+    pub fn new(digit: u8) -> NewCornerCandidate {
+        NewCornerCandidate(Digit::new(digit))
+    }
+}
+
 #[derive(Event)]
 pub struct NewDigit(pub Digit);
 
@@ -732,24 +921,23 @@ fn kick_candidates(
         (&DigitValueCell, &CellPosition),
         (Changed<DigitValueCell>, With<SelectedCell>),
     >,
-    mut q_manual: Query<(&mut ManualCandidates, &CellPosition), Without<SelectedCell>>,
-    mut q_auto: Query<(&mut AutoCandidates, &CellPosition), Without<SelectedCell>>,
+    mut q_manual: Query<
+        (&mut ManualCandidates, &mut CornerCandidates, &CellPosition),
+        Without<SelectedCell>,
+    >,
     auto_mode: Res<AutoCandidateMode>,
 ) {
     for (cell_state, kicker_position) in changed_cell.iter() {
         if let Some(digit) = cell_state.0 {
             debug!("kick_candidates: {:?} {} ", digit, kicker_position);
-            if **auto_mode {
-                for (mut auto_candidates, cell_position) in q_auto.iter_mut() {
-                    if kicker_position.in_range(cell_position) {
-                        auto_candidates.0.remove(digit.as_set());
-                    }
-                }
-            } else {
-                for (mut manual_candidates, cell_position) in q_manual.iter_mut() {
-                    if kicker_position.in_range(cell_position) {
+            for (mut manual_candidates, mut corner_candidates, cell_position) in q_manual.iter_mut()
+            {
+                if kicker_position.in_range(cell_position) {
+                    // 自动候选数由 auto_candidates 模块根据整个盘面计算, 角标总是手动的
+                    if !**auto_mode {
                         manual_candidates.0.remove(digit.as_set());
                     }
+                    corner_candidates.0.remove(digit.as_set());
                 }
             }
         }
@@ -757,6 +945,7 @@ fn kick_candidates(
 }
 
 /// 检查格子冲突
+#[allow(clippy::collapsible_if)]
 fn check_conflict(
     _check_digit: Trigger<CheckDigitConflict>,
     update_cell: Query<
@@ -829,6 +1018,7 @@ fn show_conflict(
     }
 }
 
+#[allow(clippy::collapsible_if)]
 fn remove_conflict(
     remove_digit: Trigger<RemoveDigit>,
     q_cell: Query<(Entity, &CellPosition), With<SelectedCell>>,
@@ -863,6 +1053,7 @@ fn cleanup_game(mut commands: Commands, menu: Query<Entity, With<Game>>) {
     }
 }
 
+/// 实际解题时间, 暂停、失去焦点和闲置的时间不算在内
 #[derive(Resource, Default, Deref, DerefMut, Debug, Clone)]
 pub struct GameTimer(pub Stopwatch);
 
@@ -886,8 +1077,9 @@ fn update_game_time(
     mut pause_button: Single<&mut Visibility, (With<PauseButton>, Without<TimerText>)>,
     settings: Res<Settings>,
     sudoku_manager: Res<SudokuManager>,
+    mistakes: Res<Mistakes>,
 ) {
-    if !sudoku_manager.is_solved {
+    if !sudoku_manager.is_solved && !mistakes.game_over {
         game_timer.tick(time.delta());
     }
 
@@ -906,6 +1098,7 @@ fn spawn_show_more(
     font_assets: &Res<FontAssets>,
     builder: &mut ChildSpawnerCommands<'_>,
     is_solved: bool,
+    coop: bool,
 ) {
     builder
         .spawn((
@@ -920,8 +1113,8 @@ fn spawn_show_more(
                 flex_direction: FlexDirection::Column,
                 ..default()
             },
-            BorderColor(Color::Srgba(Srgba::hex("c4c4c4").unwrap())),
-            BackgroundColor(WHITE_COLOR),
+            ThemedBorder(ThemeRole::Border),
+            ThemedBackground(ThemeRole::Surface),
             GlobalZIndex(99),
         ))
         .with_children(|builder| {
@@ -934,6 +1127,22 @@ fn spawn_show_more(
                         commands.trigger(InitPuzzle);
                     },
                 );
+                more_item(
+                    font_assets,
+                    builder,
+                    "Watch Replay",
+                    |_: Trigger<Pointer<Click>>, mut commands, _q_selected| {
+                        commands.trigger(WatchReplay);
+                    },
+                );
+                more_item(
+                    font_assets,
+                    builder,
+                    "Share Result",
+                    |_: Trigger<Pointer<Click>>, mut commands, _q_selected| {
+                        commands.trigger(ShareResult);
+                    },
+                );
             } else {
                 more_item(
                     font_assets,
@@ -948,7 +1157,7 @@ fn spawn_show_more(
                     builder,
                     "Check Cell",
                     |_: Trigger<Pointer<Click>>, mut commands, q_selected| {
-                        commands.trigger_targets(CheckCell, vec![*q_selected]);
+                        commands.trigger_targets(CheckCell::default(), vec![*q_selected]);
                     },
                 );
                 more_item(
@@ -959,12 +1168,60 @@ fn spawn_show_more(
                         commands.trigger(CheckPuzzle);
                     },
                 );
+                more_item(
+                    font_assets,
+                    builder,
+                    "Am I on Track?",
+                    |_: Trigger<Pointer<Click>>, mut commands, _q_selected| {
+                        commands.trigger(CheckProgress);
+                    },
+                );
                 more_item(
                     font_assets,
                     builder,
                     "Reveal Cell",
                     |_: Trigger<Pointer<Click>>, mut commands, q_selected| {
-                        commands.trigger_targets(RevealCell, vec![*q_selected]);
+                        commands.trigger_targets(RevealCell::default(), vec![*q_selected]);
+                    },
+                );
+                more_item(
+                    font_assets,
+                    builder,
+                    "Fill Candidates",
+                    |_: Trigger<Pointer<Click>>, mut commands, _q_selected| {
+                        commands.trigger(FillCandidates);
+                    },
+                );
+                more_item(
+                    font_assets,
+                    builder,
+                    "Remove Impossible",
+                    |_: Trigger<Pointer<Click>>, mut commands, _q_selected| {
+                        commands.trigger(RemoveImpossibleCandidates);
+                    },
+                );
+                more_item(
+                    font_assets,
+                    builder,
+                    "Undo",
+                    |_: Trigger<Pointer<Click>>, mut commands, _q_selected| {
+                        commands.trigger(Undo);
+                    },
+                );
+                more_item(
+                    font_assets,
+                    builder,
+                    "Try a Guess",
+                    |_: Trigger<Pointer<Click>>, mut commands, _q_selected| {
+                        commands.trigger(TrialControl::Start);
+                    },
+                );
+                more_item(
+                    font_assets,
+                    builder,
+                    "Guided Solve",
+                    |_: Trigger<Pointer<Click>>, mut commands, _q_selected| {
+                        commands.trigger(StartGuidedSolve);
                     },
                 );
                 more_item(
@@ -972,7 +1229,12 @@ fn spawn_show_more(
                     builder,
                     "Reveal Puzzle",
                     |_: Trigger<Pointer<Click>>, mut commands, _q_selected| {
-                        commands.trigger(RevealPuzzle);
+                        commands.trigger(Confirm {
+                            title: "Reveal puzzle?",
+                            message: "This fills in every remaining cell and ends the game.",
+                            confirm: "Reveal",
+                            action: |mut commands| commands.trigger(RevealPuzzle),
+                        });
                     },
                 );
                 more_item(
@@ -980,9 +1242,42 @@ fn spawn_show_more(
                     builder,
                     "Reset Puzzle",
                     |_: Trigger<Pointer<Click>>, mut commands, _q_selected| {
-                        commands.trigger(ResetPuzzle);
+                        commands.trigger(Confirm {
+                            title: "Reset puzzle?",
+                            message: "This clears all your progress on this puzzle.",
+                            confirm: "Reset",
+                            action: |mut commands| commands.trigger(ResetPuzzle),
+                        });
                     },
                 );
+                more_item(
+                    font_assets,
+                    builder,
+                    "Share",
+                    |_: Trigger<Pointer<Click>>, mut commands, _q_selected| {
+                        commands.trigger(SharePuzzle);
+                    },
+                );
+                if cfg!(not(target_arch = "wasm32")) {
+                    more_item(
+                        font_assets,
+                        builder,
+                        "Paste Code",
+                        |_: Trigger<Pointer<Click>>, mut commands, _q_selected| {
+                            commands.trigger(PasteCode);
+                        },
+                    );
+                }
+                if coop {
+                    more_item(
+                        font_assets,
+                        builder,
+                        "Ping Partner",
+                        |_: Trigger<Pointer<Click>>, mut commands, _q_selected| {
+                            commands.trigger(PingPartner);
+                        },
+                    );
+                }
             }
         });
 }
@@ -990,7 +1285,7 @@ fn spawn_show_more(
 fn more_item(
     font_assets: &Res<FontAssets>,
     builder: &mut ChildSpawnerCommands<'_>,
-    text: &str,
+    text: &'static str,
     trigger: fn(Trigger<Pointer<Click>>, Commands, Single<Entity, With<SelectedCell>>),
 ) {
     builder
@@ -1015,34 +1310,34 @@ fn more_item(
                 },
                 ..default()
             },
-            BackgroundColor(WHITE_COLOR),
-            BorderColor(*GRAY),
+            ThemedBackground(ThemeRole::Surface),
+            ThemedBorder(ThemeRole::Border),
             GlobalZIndex(999),
         ))
         .with_children(|builder| {
             builder.spawn((
-                Text::new(text),
+                Localized::new(text),
                 TextFont {
                     font_size: 18.0,
                     font: font_assets.franklin_500.clone(),
                     ..default()
                 },
-                TextColor(*DARK_BLACK),
+                ThemedText(ThemeRole::Text),
             ));
         })
         .observe(
-            |trigger: Trigger<Pointer<Over>>, mut item: Query<&mut BackgroundColor>| {
+            |trigger: Trigger<Pointer<Over>>, mut item: Query<&mut ThemedBackground>| {
                 let entity = trigger.target();
                 if let Ok(mut item) = item.get_mut(entity) {
-                    item.0 = *EXTRA_LIGHT_GRAY;
+                    item.0 = ThemeRole::SurfaceHover;
                 }
             },
         )
         .observe(
-            |trigger: Trigger<Pointer<Out>>, mut item: Query<&mut BackgroundColor>| {
+            |trigger: Trigger<Pointer<Out>>, mut item: Query<&mut ThemedBackground>| {
                 let entity = trigger.target();
                 if let Ok(mut item) = item.get_mut(entity) {
-                    item.0 = WHITE_COLOR;
+                    item.0 = ThemeRole::Surface;
                 }
             },
         )
@@ -1059,6 +1354,7 @@ fn on_show_more(
     font_assets: Res<FontAssets>,
     mut commands: Commands,
     sudoku_manager: Res<SudokuManager>,
+    coop: Option<Res<CoopLink>>,
 ) {
     let parent = trigger.target();
 
@@ -1070,7 +1366,12 @@ fn on_show_more(
     } else {
         opened.0 = true;
         commands.entity(parent).with_children(|builder| {
-            spawn_show_more(&font_assets, builder, sudoku_manager.is_solved);
+            spawn_show_more(
+                &font_assets,
+                builder,
+                sudoku_manager.is_solved,
+                coop.is_some(),
+            );
         });
     }
 }
@@ -1091,9 +1392,10 @@ fn on_reset_puzzle(
     )>,
     mut commands: Commands,
     mut auto_mode: ResMut<AutoCandidateMode>,
+    settings: Res<Settings>,
 ) {
     commands.insert_resource(GameTimer(Stopwatch::new()));
-    auto_mode.0 = false;
+    auto_mode.0 = settings.start_in_automatic_mode;
     let mut entities = vec![];
     for (entity, _, _, _, _, _) in q_cell.iter() {
         commands
@@ -1128,7 +1430,11 @@ fn on_reset_puzzle(
                         auto_candidates.0 = Set::NONE;
                     }
                     CellState::Candidates(cands) => {
-                        *cell_mode = CellMode::ManualCandidates;
+                        *cell_mode = if auto_mode.0 {
+                            CellMode::AutoCandidates
+                        } else {
+                            CellMode::ManualCandidates
+                        };
                         digit_value.0 = None;
                         manual_candidates.0 = Set::NONE;
                         auto_candidates.0 = cands;
@@ -1141,8 +1447,11 @@ fn on_reset_puzzle(
     }
 }
 
-#[derive(Event)]
-struct RevealCell;
+#[derive(Event, Default)]
+struct RevealCell {
+    /// 揭晓整题时逐格揭晓, 不再逐格计入辅助
+    whole_puzzle: bool,
+}
 
 fn on_reveal_cell(
     trigger: Trigger<RevealCell>,
@@ -1182,12 +1491,16 @@ fn on_reveal_puzzle(
     }
 
     let entities = q_cell.iter().collect::<Vec<_>>();
-    commands.trigger_targets(RevealCell, entities);
+    commands.trigger_targets(RevealCell { whole_puzzle: true }, entities);
 }
 
-#[derive(Event)]
-pub struct CheckCell;
+#[derive(Event, Default)]
+pub struct CheckCell {
+    /// 填数时按设置自动检查, 不算使用了辅助
+    pub automatic: bool,
+}
 
+#[allow(clippy::collapsible_if)]
 fn on_check_cell(
     trigger: Trigger<CheckCell>,
     q_cell: Query<(&DigitValueCell, &CellPosition), Without<FixedCell>>,
@@ -1242,26 +1555,27 @@ pub struct FindHint;
 fn find_hint(
     _trigger: Trigger<FindHint>,
     q_selected: Query<Entity, With<SelectedCell>>,
-    q_cell: Query<(Entity, &AutoCandidates), Without<FixedCell>>,
+    q_cell: Query<(Entity, &AutoCandidates, &DigitValueCell), Without<FixedCell>>,
     mut commands: Commands,
 ) {
     for entity in q_selected.iter() {
         commands.entity(entity).remove::<SelectedCell>();
     }
-    if let Some((entity, _)) = q_cell
-        .iter()
-        .sort_by::<(Entity, &AutoCandidates)>(|t1, t2| {
-            let candidate_1 = t1.1;
-            let candidate_2 = t2.1;
-
-            candidate_1.0.len().cmp(&candidate_2.0.len())
-        })
-        .next()
-    {
+    if let Some(entity) = hint_cell(q_cell.iter()) {
         commands.entity(entity).insert(SelectedCell);
     }
 }
 
+/// 提示要选中的格子
+pub(crate) fn hint_cell<'a>(
+    cells: impl Iterator<Item = (Entity, &'a AutoCandidates, &'a DigitValueCell)>,
+) -> Option<Entity> {
+    cells
+        .filter(|(_, _, digit)| digit.0.is_none())
+        .min_by_key(|(_, candidates, _)| candidates.0.len())
+        .map(|(entity, _, _)| entity)
+}
+
 #[derive(Resource)]
 pub struct Settings {
     pub check_guesses_when_entered: bool,
@@ -1269,6 +1583,26 @@ pub struct Settings {
     pub highlight_conflicts: bool,
     pub play_sound_on_solve: bool,
     pub show_clock: bool,
+    pub dark_mode: bool,
+    pub high_contrast: bool,
+    /// 给冲突、提示、选中加上形状标记
+    pub accessibility_cues: bool,
+    pub palette: ColorBlindPalette,
+    /// 棋盘和键盘数字的缩放
+    pub font_scale: f32,
+    /// 自动候选数的排除深度
+    pub candidate_elimination: CandidateElimination,
+    /// 在工具栏显示填错的次数
+    pub count_mistakes: bool,
+    /// 错误次数用完后游戏结束
+    pub strict_mode: bool,
+    pub max_mistakes: u32,
+    /// 闲置多少秒后自动暂停, `None` 表示不自动暂停
+    pub auto_pause_after: Option<u32>,
+    /// 界面语言
+    pub language: Language,
+    /// 新题目的难度, `None` 表示不限难度
+    pub puzzle_difficulty: Option<Difficulty>,
 }
 
 impl Default for Settings {
@@ -1279,6 +1613,18 @@ impl Default for Settings {
             highlight_conflicts: true,
             play_sound_on_solve: true,
             show_clock: true,
+            dark_mode: false,
+            high_contrast: false,
+            accessibility_cues: false,
+            palette: ColorBlindPalette::Standard,
+            font_scale: 1.0,
+            candidate_elimination: CandidateElimination::Singles,
+            count_mistakes: false,
+            strict_mode: false,
+            max_mistakes: 3,
+            auto_pause_after: Some(120),
+            language: Language::English,
+            puzzle_difficulty: None,
         }
     }
 }
@@ -0,0 +1,124 @@
+use crate::{
+    GameState,
+    game::{
+        Settings,
+        cell_state::{CellMode, ConflictCell, CorrectionCell, RevealedCell, SelectedCell},
+        position::CellPosition,
+    },
+    theme::{ThemeRole, ThemedBackground, ThemedBorder},
+};
+use bevy::prelude::*;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (update_shape_cues, update_selected_border).run_if(in_state(GameState::Playing)),
+    );
+}
+
+/// 可选的字体缩放
+pub const FONT_SCALES: [f32; 4] = [0.85, 1.0, 1.25, 1.5];
+
+/// 切换到下一档字体缩放
+pub fn next_font_scale(current: f32) -> f32 {
+    FONT_SCALES
+        .iter()
+        .copied()
+        .find(|scale| *scale > current + f32::EPSILON)
+        .unwrap_or(FONT_SCALES[0])
+}
+
+/// 不依赖颜色的形状标记
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShapeCue {
+    /// 错误的数字画删除线
+    Strikethrough,
+    /// 提示出来的数字加下划线
+    Underline,
+}
+
+/// 在格子里生成隐藏的形状标记
+pub(crate) fn spawn_shape_cues(builder: &mut ChildSpawnerCommands<'_>) {
+    builder.spawn((
+        ShapeCue::Strikethrough,
+        Visibility::Hidden,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(18.0),
+            top: Val::Percent(48.0),
+            width: Val::Percent(64.0),
+            height: Val::Px(3.0),
+            ..default()
+        },
+        ThemedBackground(ThemeRole::Conflict),
+        Pickable::IGNORE,
+    ));
+    builder.spawn((
+        ShapeCue::Underline,
+        Visibility::Hidden,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(30.0),
+            bottom: Val::Percent(10.0),
+            width: Val::Percent(40.0),
+            height: Val::Px(3.0),
+            ..default()
+        },
+        ThemedBackground(ThemeRole::RevealedDigit),
+        Pickable::IGNORE,
+    ));
+}
+
+fn update_shape_cues(
+    settings: Res<Settings>,
+    q_cell: Query<(
+        &CellMode,
+        &Children,
+        Has<ConflictCell>,
+        Has<CorrectionCell>,
+        Has<RevealedCell>,
+    )>,
+    mut q_cue: Query<(&ShapeCue, &mut Visibility)>,
+) {
+    for (cell_mode, children, is_conflict, is_correction, is_revealed) in q_cell.iter() {
+        let show_digit = settings.accessibility_cues && *cell_mode == CellMode::Digit;
+        for child in children.iter() {
+            if let Ok((cue, mut visibility)) = q_cue.get_mut(child) {
+                let show = match cue {
+                    ShapeCue::Strikethrough => show_digit && (is_conflict || is_correction),
+                    ShapeCue::Underline => show_digit && is_revealed,
+                };
+                visibility.set_if_neq(if show {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                });
+            }
+        }
+    }
+}
+
+/// 选中的格子加粗边框
+fn update_selected_border(
+    settings: Res<Settings>,
+    mut q_cell: Query<(&mut Node, &mut ThemedBorder, Has<SelectedCell>), With<CellPosition>>,
+) {
+    for (mut node, mut themed, is_selected) in q_cell.iter_mut() {
+        let (border, role) = if settings.accessibility_cues && is_selected {
+            (Val::Px(3.0), ThemeRole::Text)
+        } else {
+            (Val::Px(0.5), ThemeRole::CellBorder)
+        };
+        if node.border.left != border {
+            node.border = UiRect::all(border);
+        }
+        themed.set_if_neq(ThemedBorder(role));
+    }
+}
+
+#[test]
+fn test_next_font_scale() {
+    assert_eq!(next_font_scale(1.0), 1.25);
+    assert_eq!(next_font_scale(1.5), 0.85);
+    assert_eq!(next_font_scale(0.85), 1.0);
+}
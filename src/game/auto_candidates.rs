@@ -0,0 +1,264 @@
+use crate::{
+    GameState,
+    game::{
+        Settings,
+        cell_state::{AutoCandidates, DigitValueCell},
+        guided_solve::GuidedSolve,
+        lessons::ActiveLesson,
+        position::CellPosition,
+    },
+};
+use bevy::prelude::*;
+use sudoku::{bitset::Set, board::Digit};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CandidateGrid>().add_systems(
+        Update,
+        // 讲解模式和课程模式自己维护候选数
+        sync_auto_candidates.run_if(
+            in_state(GameState::Playing)
+                .and(not(resource_exists::<GuidedSolve>))
+                .and(not(resource_exists::<ActiveLesson>)),
+        ),
+    );
+}
+
+/// 自动候选数的排除深度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CandidateElimination {
+    /// 只排除同行、同列、同宫已经填入的数字
+    #[default]
+    Singles,
+    /// 再排除区块
+    LockedCandidates,
+    /// 再排除数对
+    Pairs,
+}
+
+impl CandidateElimination {
+    pub fn next(self) -> Self {
+        match self {
+            CandidateElimination::Singles => CandidateElimination::LockedCandidates,
+            CandidateElimination::LockedCandidates => CandidateElimination::Pairs,
+            CandidateElimination::Pairs => CandidateElimination::Singles,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CandidateElimination::Singles => "Singles",
+            CandidateElimination::LockedCandidates => "Locked candidates",
+            CandidateElimination::Pairs => "Pairs",
+        }
+    }
+}
+
+/// 当前盘面和只按行列宫排除后的候选数
+///
+/// 每次只重新计算改动格子和它的同行、同列、同宫格子
+#[derive(Resource, Debug, Clone)]
+pub struct CandidateGrid {
+    digits: [Option<Digit>; 81],
+    basic: [Set<Digit>; 81],
+}
+
+impl Default for CandidateGrid {
+    fn default() -> Self {
+        Self {
+            digits: [None; 81],
+            basic: [Set::ALL; 81],
+        }
+    }
+}
+
+impl CandidateGrid {
+    /// 更新一个格子的数字, 数字没有变化时返回 false
+    pub fn set_digit(&mut self, index: usize, digit: Option<Digit>) -> bool {
+        if self.digits[index] == digit {
+            return false;
+        }
+        self.digits[index] = digit;
+
+        let position = CellPosition::new(index as u8);
+        for peer in 0..81 {
+            if position.in_range(&CellPosition::new(peer as u8)) {
+                self.basic[peer] = self.basic_candidates(peer);
+            }
+        }
+        true
+    }
+
+    fn basic_candidates(&self, index: usize) -> Set<Digit> {
+        if self.digits[index].is_some() {
+            return Set::NONE;
+        }
+
+        let position = CellPosition::new(index as u8);
+        let mut candidates = Set::ALL;
+        for (peer, digit) in self.digits.iter().enumerate() {
+            if let Some(digit) = digit
+                && position.in_range(&CellPosition::new(peer as u8))
+            {
+                candidates.remove(digit.as_set());
+            }
+        }
+        candidates
+    }
+
+    pub fn candidates(&self, level: CandidateElimination) -> [Set<Digit>; 81] {
+        let mut candidates = self.basic;
+        if level == CandidateElimination::Singles {
+            return candidates;
+        }
+
+        loop {
+            let mut changed = eliminate_locked_candidates(&mut candidates);
+            if level == CandidateElimination::Pairs {
+                changed |= eliminate_naked_pairs(&mut candidates);
+            }
+            if !changed {
+                break;
+            }
+        }
+        candidates
+    }
+}
+
+fn row_cells(row: u8) -> [usize; 9] {
+    std::array::from_fn(|col| CellPosition::from_row_col(row, col as u8).0 as usize)
+}
+
+fn col_cells(col: u8) -> [usize; 9] {
+    std::array::from_fn(|row| CellPosition::from_row_col(row as u8, col).0 as usize)
+}
+
+fn block_cells(block: u8) -> [usize; 9] {
+    std::array::from_fn(|i| CellPosition::from_block_row_col(block, i as u8).0 as usize)
+}
+
+/// 行、列、宫一共 27 个区域
+fn houses() -> impl Iterator<Item = [usize; 9]> {
+    (0..9)
+        .map(row_cells)
+        .chain((0..9).map(col_cells))
+        .chain((0..9).map(block_cells))
+}
+
+fn remove_candidate(
+    candidates: &mut [Set<Digit>; 81],
+    cells: impl Iterator<Item = usize>,
+    digit: Set<Digit>,
+) -> bool {
+    let mut changed = false;
+    for cell in cells {
+        if candidates[cell].overlaps(digit) {
+            candidates[cell].remove(digit);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// 区块排除: 某个数字在一个区域里只出现在另一个区域的交集里
+fn eliminate_locked_candidates(candidates: &mut [Set<Digit>; 81]) -> bool {
+    let mut changed = false;
+    for house in houses() {
+        for digit in Digit::all() {
+            let digit = digit.as_set();
+            let positions = house
+                .iter()
+                .map(|cell| CellPosition::new(*cell as u8))
+                .filter(|position| candidates[position.0 as usize].overlaps(digit))
+                .collect::<Vec<_>>();
+            let Some(first) = positions.first().copied() else {
+                continue;
+            };
+
+            // 找出和当前区域重叠并包含所有位置的其他区域
+            let others = [
+                row_cells(first.row()),
+                col_cells(first.col()),
+                block_cells(first.block()),
+            ];
+            for other in others {
+                if other == house || !positions.iter().all(|p| other.contains(&(p.0 as usize))) {
+                    continue;
+                }
+                let outside = other.into_iter().filter(|cell| !house.contains(cell));
+                changed |= remove_candidate(candidates, outside, digit);
+            }
+        }
+    }
+    changed
+}
+
+/// 数对排除: 区域里两个格子的候选数是同样的两个数字
+fn eliminate_naked_pairs(candidates: &mut [Set<Digit>; 81]) -> bool {
+    let mut changed = false;
+    for house in houses() {
+        for (i, a) in house.iter().enumerate() {
+            let pair = candidates[*a];
+            if pair.len() != 2 {
+                continue;
+            }
+            for b in house.iter().skip(i + 1) {
+                if candidates[*b] != pair {
+                    continue;
+                }
+                let others = house.into_iter().filter(|cell| cell != a && cell != b);
+                changed |= remove_candidate(candidates, others, pair);
+            }
+        }
+    }
+    changed
+}
+
+/// 盘面上任何格子的数字变化后, 重新计算所有格子的自动候选数
+fn sync_auto_candidates(
+    q_changed: Query<(&DigitValueCell, &CellPosition), Changed<DigitValueCell>>,
+    mut q_auto: Query<(&mut AutoCandidates, &CellPosition)>,
+    mut grid: ResMut<CandidateGrid>,
+    settings: Res<Settings>,
+) {
+    let mut changed = settings.is_changed();
+    for (digit_value, position) in q_changed.iter() {
+        changed |= grid.set_digit(position.0 as usize, digit_value.0);
+    }
+    if !changed {
+        return;
+    }
+
+    let candidates = grid.candidates(settings.candidate_elimination);
+    for (mut auto_candidates, position) in q_auto.iter_mut() {
+        let new_candidates = candidates[position.0 as usize];
+        if auto_candidates.0 != new_candidates {
+            auto_candidates.0 = new_candidates;
+        }
+    }
+}
+
+#[test]
+fn test_candidates_restore_after_remove() {
+    let mut grid = CandidateGrid::default();
+    grid.set_digit(0, Some(Digit::new(5)));
+    assert!(!grid.candidates(CandidateElimination::Singles)[8].contains(Digit::new(5)));
+
+    grid.set_digit(0, None);
+    assert!(grid.candidates(CandidateElimination::Singles)[8].contains(Digit::new(5)));
+}
+
+#[test]
+fn test_locked_candidates() {
+    let mut grid = CandidateGrid::default();
+    // 第一宫的第二、三行填满后, 1 只能放在第一行
+    for (i, (row, col)) in [(1, 0), (1, 1), (1, 2), (2, 0), (2, 1), (2, 2)]
+        .into_iter()
+        .enumerate()
+    {
+        let index = CellPosition::from_row_col(row, col).0 as usize;
+        grid.set_digit(index, Some(Digit::new(i as u8 + 2)));
+    }
+    let one = Digit::new(1);
+    assert!(grid.candidates(CandidateElimination::Singles)[4].contains(one));
+    assert!(!grid.candidates(CandidateElimination::LockedCandidates)[4].contains(one));
+}
@@ -4,16 +4,26 @@ use crate::{
     game::{
         AutoCandidateMode, AutoCandidatesContainer, DigitCellContainer, ManualCandidatesContainer,
         MoveSelectCell, SelectedCell, Settings,
+        accessibility::spawn_shape_cues,
         cell_state::{
             AutoCandidateCellMarker, AutoCandidates, CandidateMarker, CandidatesValue, CellMode,
-            ConflictCell, CorrectionCell, DigitValueCell, ManualCandidateCellMarker,
-            ManualCandidates, RevealedCell,
+            ConflictCell, CornerCandidateSlot, CornerCandidates, CorrectionCell, DigitValueCell,
+            FixedCell, ManualCandidateCellMarker, ManualCandidates, RevealedCell,
+            SqueezedCandidate, TrialCell,
         },
+        generator::generating_cover,
+        input::{InputBlockers, on_touch_press},
+        layout::BoardRoot,
+        pause::board_cover,
         position::CellPosition,
+        race::Player,
+        replay::ReplayPlayer,
     },
     loading::{FontAssets, TextureAssets},
+    theme::{Theme, ThemeRole, ThemedBackground, ThemedBorder, ThemedImage},
 };
-use bevy::prelude::*;
+use accesskit::{Node as Accessible, Role};
+use bevy::{a11y::AccessibilityNode, prelude::*};
 use sudoku::board::Digit;
 
 pub(crate) fn plugin(app: &mut App) {
@@ -23,10 +33,17 @@ pub(crate) fn plugin(app: &mut App) {
             show_digit_cell,
             show_candidates::<AutoCandidates, AutoCandidateCellMarker>,
             show_candidates::<ManualCandidates, ManualCandidateCellMarker>,
+            show_corner_candidates,
+            fit_centre_candidates,
             show_preview_number,
             change_cell_vis,
+            update_cell_background,
         )
-            .run_if(in_state(GameState::Playing)),
+            .run_if(
+                in_state(GameState::Playing)
+                    .or(in_state(GameState::Race))
+                    .or(in_state(GameState::Editor)),
+            ),
     )
     .add_systems(
         Update,
@@ -48,12 +65,14 @@ pub(crate) fn play_board(
     builder
         .spawn((
             Node {
-                width: Val::Vh(80.0),
+                width: Val::VMin(80.0),
                 // min_width: Val::Px(500.0),
                 // max_width: Val::Px(800.0),
                 ..default()
             },
-            BackgroundColor(*DARK_BLACK),
+            ThemedBackground(ThemeRole::BoardFrame),
+            BoardRoot,
+            board_accessible(),
         ))
         .with_children(|builder| {
             // 生成9宫格布局
@@ -71,7 +90,7 @@ pub(crate) fn play_board(
                         ..default()
                     },
                     // BorderColor(Color::BLACK),
-                    BackgroundColor(*GRAY),
+                    ThemedBackground(ThemeRole::BoardFrame),
                     // CellsLayout,
                 ))
                 .with_children(|builder| {
@@ -90,7 +109,7 @@ pub(crate) fn play_board(
                                     // border: UiRect::all(Val::Px(1.)),
                                     ..default()
                                 },
-                                BackgroundColor(*GRAY),
+                                ThemedBackground(ThemeRole::BoardFrame),
                             ))
                             .with_children(|builder| {
                                 // 生成宫格里的9个格子
@@ -108,10 +127,11 @@ pub(crate) fn play_board(
                                                 ..default()
                                             },
                                             CellPosition::from_block_row_col(block_index, bi),
-                                            BorderColor(*LIGHT_GRAY),
+                                            ThemedBorder(ThemeRole::CellBorder),
                                             BackgroundColor(Color::WHITE),
                                         ))
                                         .observe(on_click_cell)
+                                        .observe(on_touch_press)
                                         .with_children(|builder| {
                                             // 数字格子
                                             builder.spawn((
@@ -132,6 +152,8 @@ pub(crate) fn play_board(
                                                 },
                                                 DigitCellContainer,
                                             ));
+                                            // 无障碍模式下的形状标记
+                                            spawn_shape_cues(builder);
 
                                             // 自动候选格子容器
                                             builder
@@ -288,24 +310,91 @@ pub(crate) fn play_board(
                                                             );
                                                     }
                                                 });
+
+                                            // 角标容器
+                                            spawn_corner_candidates(font_assets, builder);
                                         });
                                 }
                             });
                     }
                 });
+
+            builder.spawn(board_cover());
+            generating_cover(font_assets, builder);
+        });
+}
+
+/// 角标依次占用的位置 (行, 列): 先四个角, 再四条边的中点, 第九个和第八个挤在一起
+const CORNER_SLOTS: [(i16, i16); 8] = [
+    (1, 1),
+    (1, 3),
+    (3, 1),
+    (3, 3),
+    (1, 2),
+    (3, 2),
+    (2, 1),
+    (2, 3),
+];
+
+fn spawn_corner_candidates(font_assets: &Res<FontAssets>, builder: &mut ChildSpawnerCommands<'_>) {
+    builder
+        .spawn(Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            display: Display::Grid,
+            position_type: PositionType::Absolute,
+            grid_template_columns: RepeatedGridTrack::flex(3, 1.0),
+            grid_template_rows: RepeatedGridTrack::flex(3, 1.0),
+            padding: UiRect::axes(Val::Px(4.0), Val::Px(1.0)),
+            ..default()
+        })
+        .with_children(|builder| {
+            for (slot, (row, column)) in CORNER_SLOTS.into_iter().enumerate() {
+                builder.spawn((
+                    Text::default(),
+                    TextFont {
+                        font: font_assets.franklin_700.clone(),
+                        font_size: 12.0,
+                        ..default()
+                    },
+                    TextColor(TRANSPARENT),
+                    Node {
+                        grid_row: GridPlacement::start(row),
+                        grid_column: GridPlacement::start(column),
+                        align_self: match row {
+                            1 => AlignSelf::Start,
+                            3 => AlignSelf::End,
+                            _ => AlignSelf::Center,
+                        },
+                        justify_self: match column {
+                            1 => JustifySelf::Start,
+                            3 => JustifySelf::End,
+                            _ => JustifySelf::Center,
+                        },
+                        ..default()
+                    },
+                    CornerCandidateSlot(slot as u8),
+                ));
+            }
         });
 }
 
+/// 棋盘对读屏软件是一个 9x9 的表格
+fn board_accessible() -> AccessibilityNode {
+    let mut accessible = Accessible::new(Role::Grid);
+    accessible.set_label("Sudoku board");
+    accessible.set_row_count(9);
+    accessible.set_column_count(9);
+    AccessibilityNode(accessible)
+}
+
 fn spawn_conflict_container(
     texture_assets: &Res<TextureAssets>,
     builder: &mut ChildSpawnerCommands<'_>,
 ) {
     builder.spawn((
-        ImageNode {
-            image: texture_assets.circle.clone(),
-            color: Color::srgb_u8(255, 75, 86),
-            ..default()
-        },
+        ImageNode::new(texture_assets.circle.clone()),
+        ThemedImage(ThemeRole::Conflict),
         // Visibility::Hidden,
         Node {
             position_type: PositionType::Absolute,
@@ -346,21 +435,68 @@ impl PreviewCandidate {
 fn on_click_cell(
     trigger: Trigger<Pointer<Click>>,
     mut commands: Commands,
-    exist: Query<Entity, With<SelectedCell>>,
+    q_player: Query<Option<&Player>>,
+    exist: Query<(Entity, Option<&Player>), With<SelectedCell>>,
+    replay_player: Option<Res<ReplayPlayer>>,
 ) {
-    for entity in exist.iter() {
-        commands.entity(entity).remove::<SelectedCell>();
+    // 回放时由回放选中格子
+    if replay_player.is_some() {
+        return;
+    }
+    // 对战时每块棋盘各有一个选中格子
+    let player = q_player.get(trigger.target()).ok().flatten();
+    for (entity, other) in exist.iter() {
+        if other == player {
+            commands.entity(entity).remove::<SelectedCell>();
+        }
     }
 
     commands.entity(trigger.target()).insert(SelectedCell);
 }
 
+/// 格子背景: 选中 > 题目给出 > 同行同列同宫 > 普通
+fn update_cell_background(
+    theme: Res<Theme>,
+    q_selected: Query<(&CellPosition, Option<&Player>), With<SelectedCell>>,
+    mut q_cell: Query<(
+        &CellPosition,
+        &mut BackgroundColor,
+        Has<FixedCell>,
+        Has<SelectedCell>,
+        Option<&Player>,
+    )>,
+) {
+    for (cell_position, mut background, is_fixed, is_selected, player) in q_cell.iter_mut() {
+        let selected = q_selected
+            .iter()
+            .find(|(_, other)| *other == player)
+            .map(|(position, _)| *position);
+        let role = if is_selected {
+            ThemeRole::SelectedCell
+        } else if is_fixed {
+            ThemeRole::GivenCell
+        } else if selected.is_some_and(|selected| selected.in_range(cell_position)) {
+            ThemeRole::PeerHighlight
+        } else {
+            ThemeRole::CellBackground
+        };
+        background.set_if_neq(BackgroundColor(theme.color(role)));
+    }
+}
+
 fn show_digit_cell(
-    q_cell: Query<(Entity, &DigitValueCell, &CellMode, Option<&RevealedCell>)>,
+    theme: Res<Theme>,
+    q_cell: Query<(
+        Entity,
+        &DigitValueCell,
+        &CellMode,
+        Option<&RevealedCell>,
+        Has<TrialCell>,
+    )>,
     children: Query<&Children>,
     mut digit_cell: Query<(&mut Text, &mut Visibility, &mut TextColor), With<DigitCellContainer>>,
 ) {
-    for (entity, digit_value, cell_mode, opt_revealed) in q_cell.iter() {
+    for (entity, digit_value, cell_mode, opt_revealed, is_trial) in q_cell.iter() {
         for child in children.iter_descendants(entity) {
             if let Ok((mut text, mut visibility, mut text_color)) = digit_cell.get_mut(child) {
                 if let CellMode::Digit = cell_mode {
@@ -369,9 +505,11 @@ fn show_digit_cell(
                     }
                     *visibility = Visibility::Visible;
                     if opt_revealed.is_some() {
-                        text_color.0 = *ACCENT_BLUE;
+                        text_color.0 = theme.color(ThemeRole::RevealedDigit);
+                    } else if is_trial {
+                        text_color.0 = theme.color(ThemeRole::TrialDigit);
                     } else {
-                        text_color.0 = *DARK_BLACK;
+                        text_color.0 = theme.color(ThemeRole::Digit);
                     }
                 } else {
                     *visibility = Visibility::Hidden;
@@ -382,11 +520,17 @@ fn show_digit_cell(
 }
 
 fn show_candidates<C: CandidatesValue, M: CandidateMarker>(
-    q_cell: Query<(Entity, &C)>,
+    theme: Res<Theme>,
+    q_cell: Query<(Entity, &C, Has<TrialCell>)>,
     children: Query<&Children>,
     mut candidate_cell: Query<(&mut TextColor, &mut M)>,
 ) {
-    for (entity, manual_candidates) in q_cell.iter() {
+    for (entity, manual_candidates, is_trial) in q_cell.iter() {
+        let color = if is_trial {
+            theme.color(ThemeRole::TrialDigit)
+        } else {
+            theme.color(ThemeRole::CandidateText)
+        };
         for child in children.iter_descendants(entity) {
             if let Ok((mut text_color, mut cell_marker)) = candidate_cell.get_mut(child) {
                 if manual_candidates
@@ -394,7 +538,7 @@ fn show_candidates<C: CandidatesValue, M: CandidateMarker>(
                     .contains(Digit::new(cell_marker.index()).as_set())
                 {
                     cell_marker.set_selected(true);
-                    *text_color = TextColor(*GRAY2);
+                    *text_color = TextColor(color);
                 } else {
                     cell_marker.set_selected(false);
                     *text_color = TextColor(TRANSPARENT);
@@ -404,17 +548,83 @@ fn show_candidates<C: CandidatesValue, M: CandidateMarker>(
     }
 }
 
+/// 角标按从小到大的顺序填进角上, 填数字后隐藏
+fn show_corner_candidates(
+    theme: Res<Theme>,
+    q_cell: Query<(Entity, &CornerCandidates, &CellMode, Has<TrialCell>)>,
+    children: Query<&Children>,
+    mut q_slot: Query<(&mut Text, &mut TextColor, &CornerCandidateSlot)>,
+) {
+    for (entity, corner_candidates, cell_mode, is_trial) in q_cell.iter() {
+        let color = if is_trial {
+            theme.color(ThemeRole::TrialDigit)
+        } else {
+            theme.color(ThemeRole::CandidateText)
+        };
+        let digits = corner_candidates.0.into_iter().collect::<Vec<_>>();
+        for child in children.iter_descendants(entity) {
+            if let Ok((mut text, mut text_color, slot)) = q_slot.get_mut(child) {
+                // 位置不够时最后一个位置放下剩余的所有角标
+                let slot = slot.0 as usize;
+                let shown = if slot + 1 == CORNER_SLOTS.len() {
+                    digits.get(slot..).unwrap_or_default()
+                } else {
+                    digits.get(slot..=slot).unwrap_or_default()
+                };
+                if shown.is_empty() || *cell_mode == CellMode::Digit {
+                    text_color.0 = TRANSPARENT;
+                } else {
+                    text.0 = shown.iter().map(|digit| digit.get().to_string()).collect();
+                    text_color.0 = color;
+                }
+            }
+        }
+    }
+}
+
+/// 有角标时把中心的候选数缩小, 给四周的角标让出位置
+///
+/// 字号由布局按 [`SqueezedCandidate`] 计算, 这里只在角标有无变化时切换
+fn fit_centre_candidates(
+    q_cell: Query<(Entity, &CornerCandidates), Changed<CornerCandidates>>,
+    children: Query<&Children>,
+    mut q_container: Query<
+        &mut Node,
+        Or<(
+            With<ManualCandidatesContainer>,
+            With<AutoCandidatesContainer>,
+        )>,
+    >,
+    mut q_marker: Query<&mut SqueezedCandidate>,
+) {
+    for (entity, corner_candidates) in q_cell.iter() {
+        let has_corner = !corner_candidates.0.is_empty();
+        let height = Val::Percent(if has_corner { 60.0 } else { 100.0 });
+        for child in children.iter_descendants(entity) {
+            if let Ok(mut node) = q_container.get_mut(child)
+                && node.height != height
+            {
+                node.height = height;
+            }
+            if let Ok(mut squeezed) = q_marker.get_mut(child) {
+                squeezed.set_if_neq(SqueezedCandidate(has_corner));
+            }
+        }
+    }
+}
+
 fn show_preview_number(
+    theme: Res<Theme>,
     mut candidate_cell: Query<(Entity, &mut TextColor, &mut PreviewCandidate)>,
     time: Res<Time>,
     mut commands: Commands,
 ) {
     for (entity, mut text_color, mut preview) in candidate_cell.iter_mut() {
         if preview.hold {
-            *text_color = TextColor(*GRAY);
+            *text_color = TextColor(theme.color(ThemeRole::CandidateText));
             continue;
         }
-        *text_color = TextColor(*LIGHTER_GRAY);
+        *text_color = TextColor(theme.color(ThemeRole::PreviewCandidate));
 
         preview.timer.tick(time.delta());
         let alpha = 1.5 - preview.timer.elapsed_secs();
@@ -433,7 +643,11 @@ fn candidate_cell_click<C: CandidatesValue, M: CandidateMarker>(
     parent_query: Query<&ChildOf>,
     mut q_select: Query<&mut C, With<SelectedCell>>,
     mut commands: Commands,
+    blockers: InputBlockers,
 ) {
+    if blockers.blocked() {
+        return;
+    }
     let candidate_cell = cell.get(click.target()).unwrap();
     for ancestor in parent_query.iter_ancestors(click.target()) {
         if let Ok(mut cell_value) = q_select.get_mut(ancestor) {
@@ -444,6 +658,7 @@ fn candidate_cell_click<C: CandidatesValue, M: CandidateMarker>(
     }
 }
 
+#[allow(clippy::collapsible_if)]
 fn candidate_cell_move<C: CandidatesValue, M: CandidateMarker>(
     trigger: Trigger<Pointer<Over>>,
     cell: Query<&M>,
@@ -451,6 +666,10 @@ fn candidate_cell_move<C: CandidatesValue, M: CandidateMarker>(
     q_select: Query<&mut C, With<SelectedCell>>,
     mut commands: Commands,
 ) {
+    // 触摸屏没有悬停, 按下时的预览会一直残留
+    if trigger.pointer_id.is_touch() {
+        return;
+    }
     if let Ok(manual_marker) = cell.get(trigger.target()) {
         for ancestor in parent_query.iter_ancestors(trigger.target()) {
             if let Ok(_cell_value) = q_select.get(ancestor) {
@@ -464,6 +683,7 @@ fn candidate_cell_move<C: CandidatesValue, M: CandidateMarker>(
     }
 }
 
+#[allow(clippy::collapsible_if)]
 fn candidate_cell_out<M: CandidateMarker>(
     trigger: Trigger<Pointer<Out>>,
     cell: Query<&M>,
@@ -471,6 +691,9 @@ fn candidate_cell_out<M: CandidateMarker>(
     parent_query: Query<&ChildOf>,
     q_select: Query<&CellMode, With<SelectedCell>>,
 ) {
+    if trigger.pointer_id.is_touch() {
+        return;
+    }
     if let Ok(manual_marker) = cell.get(trigger.target()) {
         for ancestor in parent_query.iter_ancestors(trigger.target()) {
             if let Ok(cell_mode) = q_select.get(ancestor) {
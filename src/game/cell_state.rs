@@ -10,6 +10,7 @@ pub struct CellValueBundle {
     pub digit_value: DigitValueCell,
     pub auto_candidates: AutoCandidates,
     pub manual_candidates: ManualCandidates,
+    pub corner_candidates: CornerCandidates,
     pub cell_mode: CellMode,
 }
 
@@ -39,6 +40,7 @@ impl CellValueBundle {
             digit_value,
             auto_candidates,
             manual_candidates,
+            corner_candidates: CornerCandidates(Set::NONE),
             cell_mode,
         }
     }
@@ -71,6 +73,20 @@ impl CandidatesValue for ManualCandidates {
     }
 }
 
+/// 角标: 和中心的手动候选数分开保存, 候选模式下同时显示
+#[derive(Component, Debug)]
+pub struct CornerCandidates(pub Set<Digit>);
+
+impl CandidatesValue for CornerCandidates {
+    fn candidates_mut(&mut self) -> &mut Set<Digit> {
+        &mut self.0
+    }
+
+    fn candidates(&self) -> &Set<Digit> {
+        &self.0
+    }
+}
+
 pub trait CandidatesValue: Component<Mutability = Mutable> {
     fn insert(&mut self, digit: Digit) {
         self.candidates_mut().bitxor_assign(digit);
@@ -80,7 +96,7 @@ pub trait CandidatesValue: Component<Mutability = Mutable> {
     fn candidates(&self) -> &Set<Digit>;
 }
 
-#[derive(Component, Debug, PartialEq, Eq)]
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CellMode {
     Digit,
     AutoCandidates,
@@ -93,6 +109,7 @@ pub struct FixedCell;
 
 /// 手动候选数字
 #[derive(Component, Debug)]
+#[require(SqueezedCandidate)]
 pub struct ManualCandidateCellMarker {
     pub index: u8,
     pub selected: bool,
@@ -114,6 +131,7 @@ impl CandidateMarker for ManualCandidateCellMarker {
 
 /// 自动候选数字
 #[derive(Component, Debug)]
+#[require(SqueezedCandidate)]
 pub struct AutoCandidateCellMarker {
     pub index: u8,
     pub selected: bool,
@@ -133,6 +151,14 @@ impl CandidateMarker for AutoCandidateCellMarker {
     }
 }
 
+/// 格子里有角标时中心的候选数缩小, 给四周的角标让出位置
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SqueezedCandidate(pub bool);
+
+/// 角标位置, 数字从小到大依次填进第 0..8 个位置
+#[derive(Component, Debug)]
+pub struct CornerCandidateSlot(pub u8);
+
 pub trait CandidateMarker: Component<Mutability = Mutable> {
     fn index(&self) -> u8;
     fn selected(&self) -> bool;
@@ -152,6 +178,10 @@ pub struct RevealedCell;
 #[derive(Component)]
 pub struct CorrectionCell;
 
+/// 试填分支里改过的格子
+#[derive(Component)]
+pub struct TrialCell;
+
 ///  选中的格子
 #[derive(Component)]
 pub struct SelectedCell;
@@ -1,38 +1,75 @@
 use crate::{
-    color::{DARK_BLACK, DARK_GRAY, EXTRA_LIGHT_GRAY, GRAY, LIGHT_GRAY, WHITE_COLOR},
-    game::{AutoCandidateMode, CleanCell, NewCandidate, NewDigit, SelectedCell},
+    color::{DARK_GRAY, LIGHT_GRAY, WHITE_COLOR},
+    game::{
+        AutoCandidateMode, CleanCell, NewCandidate, NewCornerCandidate, NewDigit, SelectedCell,
+        input::{InputBlockers, LONG_PRESS_DURATION, on_touch_press},
+        layout::ControlBoardRoot,
+    },
+    i18n::Localized,
     loading::{FontAssets, TextureAssets},
+    theme::{Theme, ThemeRole, ThemedBackground, ThemedBorder, ThemedText},
 };
 use bevy::prelude::*;
 
 pub(crate) fn plugin(app: &mut App) {
     app.init_resource::<SelectedTab>()
+        .init_resource::<TabBeforeCorner>()
         .add_event::<ToggleTab>()
+        .add_event::<ToggleCornerTab>()
         .add_systems(
             Update,
             (switch_control_tab_ui, show_number).run_if(resource_changed::<SelectedTab>),
         )
+        .add_systems(
+            Update,
+            switch_control_tab_ui.run_if(resource_changed::<Theme>),
+        )
         .add_systems(
             Update,
             (update_auto_candidate_icon,).run_if(resource_changed::<AutoCandidateMode>),
         )
-        .add_observer(update_control_tab);
+        .add_observer(update_control_tab)
+        .add_observer(toggle_corner_tab);
 }
 
 #[derive(Event)]
 pub struct ToggleTab;
 
+/// 切换到角标键盘, 已经在角标键盘时切回之前的键盘
+#[derive(Event)]
+pub struct ToggleCornerTab;
+
 #[derive(Component)]
 pub struct ControlDigit;
 
 #[derive(Component)]
 pub struct ControlCandidate;
 
+/// 键盘上候选模式的小数字
+#[derive(Component)]
+pub(crate) struct ControlCandidateText;
+
+/// 键盘上角标模式左上角的小数字
+#[derive(Component)]
+pub struct ControlCorner;
+
 #[derive(Debug, Clone, Eq, PartialEq, Default)]
 pub enum ControlTab {
     #[default]
     Normal,
     Candidate,
+    Corner,
+}
+
+impl ControlTab {
+    /// 在切换按钮里从左到右的位置
+    fn index(&self) -> usize {
+        match self {
+            ControlTab::Normal => 0,
+            ControlTab::Candidate => 1,
+            ControlTab::Corner => 2,
+        }
+    }
 }
 
 #[derive(Component)]
@@ -41,6 +78,10 @@ struct ChangeTab(ControlTab);
 #[derive(Resource, Debug, Deref, DerefMut, Default)]
 struct SelectedTab(ControlTab);
 
+/// 切到角标键盘之前的键盘, 松开 Shift 时回到这里
+#[derive(Resource, Debug, Default)]
+struct TabBeforeCorner(ControlTab);
+
 pub(crate) fn control_board(
     font_assets: &Res<FontAssets>,
     texture_assets: &Res<TextureAssets>,
@@ -60,6 +101,7 @@ pub(crate) fn control_board(
                 ..default()
             },
             // BackgroundColor(GRAY.into()),
+            ControlBoardRoot,
         ))
         .with_children(|builder| {
             // keyboard
@@ -73,69 +115,43 @@ pub(crate) fn control_board(
                 ))
                 .with_children(|builder| {
                     // 切换按钮
-                    builder
-                        .spawn((
-                            Button,
-                            Node {
-                                width: Val::Px(140.0),
-                                height: Val::Px(38.0),
-                                justify_content: JustifyContent::Center,
-                                align_items: AlignItems::Center,
-                                border: UiRect::all(Val::Px(0.0)),
-                                padding: UiRect::axes(Val::Px(6.0), Val::Px(1.0)),
-                                ..Default::default()
-                            },
-                            BackgroundColor(*DARK_BLACK),
-                            ChangeTab(ControlTab::Normal),
-                            BorderRadius::left(Val::Px(3.0)),
-                            // BorderColor(WHITE_COLOR),
-                        ))
-                        .with_child((
-                            Text::new("Normal"),
-                            TextFont {
-                                font: font_assets.franklin_500.clone(),
-                                font_size: 14.0,
-                                ..default()
-                            },
-                            TextColor(WHITE_COLOR),
-                        ))
-                        .observe(
-                            |_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
-                                commands.trigger(ToggleTab);
-                            },
-                        );
-
-                    builder
-                        .spawn((
-                            Button,
-                            Node {
-                                width: Val::Px(140.0),
-                                height: Val::Px(38.0),
-                                justify_content: JustifyContent::Center,
-                                align_items: AlignItems::Center,
-                                border: UiRect::all(Val::Px(1.0)),
-                                padding: UiRect::axes(Val::Px(6.0), Val::Px(1.0)),
-                                ..Default::default()
-                            },
-                            BackgroundColor(WHITE_COLOR),
-                            ChangeTab(ControlTab::Candidate),
-                            BorderRadius::right(Val::Px(3.0)),
-                            BorderColor(*LIGHT_GRAY),
-                        ))
-                        .with_child((
-                            Text::new("Candidate"),
-                            TextFont {
-                                font: font_assets.franklin_500.clone(),
-                                font_size: 14.0,
-                                ..default()
-                            },
-                            TextColor(*DARK_GRAY),
-                        ))
-                        .observe(
-                            |_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
-                                commands.trigger(ToggleTab);
-                            },
-                        );
+                    for (tab, text) in [
+                        (ControlTab::Normal, "Normal"),
+                        (ControlTab::Candidate, "Candidate"),
+                        (ControlTab::Corner, "Corner"),
+                    ] {
+                        builder
+                            .spawn((
+                                Button,
+                                Node {
+                                    width: Val::Px(80.0),
+                                    height: Val::Px(38.0),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    border: UiRect::all(Val::Px(1.0)),
+                                    padding: UiRect::axes(Val::Px(6.0), Val::Px(1.0)),
+                                    ..Default::default()
+                                },
+                                BackgroundColor(WHITE_COLOR),
+                                match tab {
+                                    ControlTab::Normal => BorderRadius::left(Val::Px(3.0)),
+                                    ControlTab::Candidate => BorderRadius::ZERO,
+                                    ControlTab::Corner => BorderRadius::right(Val::Px(3.0)),
+                                },
+                                BorderColor(*LIGHT_GRAY),
+                                ChangeTab(tab),
+                            ))
+                            .with_child((
+                                Localized::new(text),
+                                TextFont {
+                                    font: font_assets.franklin_500.clone(),
+                                    font_size: 14.0,
+                                    ..default()
+                                },
+                                TextColor(*DARK_GRAY),
+                            ))
+                            .observe(on_click_tab);
+                    }
                 });
 
             // 数字键盘
@@ -171,11 +187,12 @@ pub(crate) fn control_board(
                                     ..default()
                                 },
                                 BorderRadius::all(Val::Px(3.0)),
-                                BackgroundColor(*EXTRA_LIGHT_GRAY),
-                                BorderColor(*GRAY),
+                                ThemedBackground(ThemeRole::KeyBackground),
+                                ThemedBorder(ThemeRole::KeyBorder),
                                 ControlNumber(i),
                             ))
                             .observe(mouse_click_control_digit)
+                            .observe(on_touch_press)
                             .with_children(|builder| {
                                 // 数字格子
                                 builder.spawn((
@@ -185,11 +202,30 @@ pub(crate) fn control_board(
                                         font_size: 32.0,
                                         ..default()
                                     },
-                                    TextColor(*DARK_BLACK),
+                                    ThemedText(ThemeRole::Text),
                                     Visibility::Visible,
                                     ControlDigit,
                                 ));
 
+                                // 角标
+                                builder.spawn((
+                                    Visibility::Hidden,
+                                    Text::new(i.to_string()),
+                                    TextFont {
+                                        font: font_assets.franklin_700.clone(),
+                                        font_size: 16.0,
+                                        ..default()
+                                    },
+                                    ThemedText(ThemeRole::Text),
+                                    Node {
+                                        position_type: PositionType::Absolute,
+                                        left: Val::Px(8.0),
+                                        top: Val::Px(4.0),
+                                        ..default()
+                                    },
+                                    ControlCorner,
+                                ));
+
                                 // 候选格子容器
                                 builder
                                     .spawn((
@@ -221,8 +257,9 @@ pub(crate) fn control_board(
                                                     font_size: 16.0,
                                                     ..default()
                                                 },
-                                                TextColor(*DARK_BLACK),
+                                                ThemedText(ThemeRole::Text),
                                                 TextLayout::new_with_justify(JustifyText::Center),
+                                                ControlCandidateText,
                                                 Node {
                                                     align_items: AlignItems::Center,
                                                     justify_items: JustifyItems::Center,
@@ -258,12 +295,17 @@ pub(crate) fn control_board(
                                 ..default()
                             },
                             BorderRadius::all(Val::Px(3.0)),
-                            BackgroundColor(*EXTRA_LIGHT_GRAY),
-                            BorderColor(*GRAY),
+                            ThemedBackground(ThemeRole::KeyBackground),
+                            ThemedBorder(ThemeRole::KeyBorder),
                         ))
                         .observe(
-                            |_trigger: Trigger<Pointer<Click>>, mut commands: Commands, selected: Single<Entity, With<SelectedCell>>| {
-                                commands.trigger_targets(CleanCell, vec![*selected]);
+                            |_trigger: Trigger<Pointer<Click>>,
+                             mut commands: Commands,
+                             selected: Single<Entity, With<SelectedCell>>,
+                             blockers: InputBlockers| {
+                                if !blockers.blocked() {
+                                    commands.trigger_targets(CleanCell, vec![*selected]);
+                                }
                             },
                         )
                         .with_children(|builder| {
@@ -320,13 +362,13 @@ pub(crate) fn control_board(
                             ));
 
                             builder.spawn((
-                                Text::new("Auto Candidate Mode"),
+                                Localized::new("Auto Candidate Mode"),
                                 TextFont {
                                     font: font_assets.franklin_600.clone(),
                                     font_size: 16.0,
                                     ..default()
                                 },
-                                TextColor(*DARK_BLACK),
+                                ThemedText(ThemeRole::Text),
                                 Node {
                                     margin: UiRect {
                                         left: Val::Px(18.0),
@@ -346,8 +388,14 @@ struct AutoCandidateNotCheck;
 #[derive(Component)]
 struct AutoCandidateCheck;
 
-fn on_click_auto_candidate(_trigger: Trigger<Pointer<Click>>, mut auto: ResMut<AutoCandidateMode>) {
-    auto.0 = !auto.0;
+fn on_click_auto_candidate(
+    _trigger: Trigger<Pointer<Click>>,
+    mut auto: ResMut<AutoCandidateMode>,
+    blockers: InputBlockers,
+) {
+    if !blockers.blocked() {
+        auto.0 = !auto.0;
+    }
 }
 
 fn update_auto_candidate_icon(
@@ -377,47 +425,94 @@ fn update_auto_candidate_icon(
 
 fn show_number(
     selected_tab: Res<SelectedTab>,
-    mut normal_cell: Query<&mut Visibility, (With<ControlDigit>, Without<ControlCandidate>)>,
-    mut candidate: Query<&mut Visibility, (With<ControlCandidate>, Without<ControlDigit>)>,
+    mut normal_cell: Query<
+        &mut Visibility,
+        (
+            With<ControlDigit>,
+            Without<ControlCandidate>,
+            Without<ControlCorner>,
+        ),
+    >,
+    mut candidate: Query<
+        &mut Visibility,
+        (
+            With<ControlCandidate>,
+            Without<ControlDigit>,
+            Without<ControlCorner>,
+        ),
+    >,
+    mut corner: Query<
+        &mut Visibility,
+        (
+            With<ControlCorner>,
+            Without<ControlDigit>,
+            Without<ControlCandidate>,
+        ),
+    >,
 ) {
-    match selected_tab.0 {
-        ControlTab::Normal => {
-            for mut visibility in normal_cell.iter_mut() {
-                *visibility = Visibility::Visible;
-            }
-            for mut visibility in candidate.iter_mut() {
-                *visibility = Visibility::Hidden;
-            }
-        }
-        ControlTab::Candidate => {
-            for mut visibility in normal_cell.iter_mut() {
-                *visibility = Visibility::Hidden;
-            }
-            for mut visibility in candidate.iter_mut() {
-                *visibility = Visibility::Visible;
-            }
+    let visible = |shown: bool| {
+        if shown {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
         }
+    };
+    for mut visibility in normal_cell.iter_mut() {
+        *visibility = visible(selected_tab.0 == ControlTab::Normal);
     }
+    for mut visibility in candidate.iter_mut() {
+        *visibility = visible(selected_tab.0 == ControlTab::Candidate);
+    }
+    for mut visibility in corner.iter_mut() {
+        *visibility = visible(selected_tab.0 == ControlTab::Corner);
+    }
+}
+
+fn update_control_tab(_ev: Trigger<ToggleTab>, mut selected_tab: ResMut<SelectedTab>) {
+    selected_tab.0 = match selected_tab.0 {
+        ControlTab::Normal => ControlTab::Candidate,
+        ControlTab::Candidate | ControlTab::Corner => ControlTab::Normal,
+    };
 }
 
-fn update_control_tab(
-    _ev: Trigger<ToggleTab>,
-    mut l: Local<ControlTab>,
+fn toggle_corner_tab(
+    _ev: Trigger<ToggleCornerTab>,
     mut selected_tab: ResMut<SelectedTab>,
+    mut before_corner: ResMut<TabBeforeCorner>,
 ) {
-    match selected_tab.0 {
-        ControlTab::Normal => {
-            selected_tab.0 = ControlTab::Candidate;
-            *l = ControlTab::Normal;
-        }
-        ControlTab::Candidate => {
-            selected_tab.0 = ControlTab::Normal;
-            *l = ControlTab::Candidate;
+    if selected_tab.0 == ControlTab::Corner {
+        selected_tab.0 = before_corner.0.clone();
+    } else {
+        before_corner.0 = std::mem::replace(&mut selected_tab.0, ControlTab::Corner);
+    }
+}
+
+/// 点击切换按钮, 用切换事件走到目标键盘, 回放里也能按顺序重现
+fn on_click_tab(
+    trigger: Trigger<Pointer<Click>>,
+    q_tab: Query<&ChangeTab>,
+    selected_tab: Res<SelectedTab>,
+    before_corner: Res<TabBeforeCorner>,
+    mut commands: Commands,
+) {
+    let Ok(ChangeTab(tab)) = q_tab.get(trigger.target()) else {
+        return;
+    };
+    match (&selected_tab.0, tab) {
+        (current, target) if current == target => {}
+        (_, ControlTab::Corner) => commands.trigger(ToggleCornerTab),
+        (ControlTab::Corner, target) => {
+            commands.trigger(ToggleCornerTab);
+            if before_corner.0 != *target {
+                commands.trigger(ToggleTab);
+            }
         }
+        _ => commands.trigger(ToggleTab),
     }
 }
 
 fn switch_control_tab_ui(
+    theme: Res<Theme>,
     selected_tab: Res<SelectedTab>,
     mut tab_query: Query<(
         &ChangeTab,
@@ -430,41 +525,36 @@ fn switch_control_tab_ui(
 ) {
     for (change_tab, mut node, mut bg, mut border_color, children) in tab_query.iter_mut() {
         if change_tab.0 == selected_tab.0 {
-            bg.0 = *DARK_BLACK;
-            border_color.0 = WHITE_COLOR;
+            bg.0 = theme.color(ThemeRole::Button);
+            border_color.0 = theme.color(ThemeRole::ButtonText);
             for child in children {
                 if let Ok(mut text_color) = text_color.get_mut(*child) {
-                    text_color.0 = WHITE_COLOR;
+                    text_color.0 = theme.color(ThemeRole::ButtonText);
                 }
             }
         } else {
-            bg.0 = WHITE_COLOR;
-            border_color.0 = *LIGHT_GRAY;
+            bg.0 = theme.color(ThemeRole::Surface);
+            border_color.0 = theme.color(ThemeRole::CellBorder);
             for child in children {
                 if let Ok(mut text_color) = text_color.get_mut(*child) {
-                    text_color.0 = *DARK_GRAY;
+                    text_color.0 = theme.color(ThemeRole::SecondaryText);
                 }
             }
         }
 
-        // normal tab selected
-        if selected_tab.0 == ControlTab::Normal {
-            if change_tab.0 == ControlTab::Normal {
-                node.border = UiRect::all(Val::Px(0.0));
-            } else {
-                node.border = UiRect {
-                    left: Val::Px(0.0),
-                    right: Val::Px(1.0),
-                    top: Val::Px(1.0),
-                    bottom: Val::Px(1.0),
-                }
-            }
-        } else if change_tab.0 == ControlTab::Candidate {
+        // 选中的按钮没有边框, 相邻的按钮之间只画一条线
+        if change_tab.0 == selected_tab.0 {
             node.border = UiRect::all(Val::Px(0.0));
         } else {
+            let index = change_tab.0.index();
+            let left_selected = index == selected_tab.0.index() + 1;
             node.border = UiRect {
-                left: Val::Px(1.0),
-                right: Val::Px(0.0),
+                left: Val::Px(if left_selected { 0.0 } else { 1.0 }),
+                right: Val::Px(if index == ControlTab::Corner.index() {
+                    1.0
+                } else {
+                    0.0
+                }),
                 top: Val::Px(1.0),
                 bottom: Val::Px(1.0),
             }
@@ -473,7 +563,7 @@ fn switch_control_tab_ui(
 }
 
 #[derive(Component)]
-struct ControlNumber(u8);
+pub(crate) struct ControlNumber(u8);
 
 fn mouse_click_control_digit(
     trigger: Trigger<Pointer<Click>>,
@@ -481,15 +571,28 @@ fn mouse_click_control_digit(
     mut commands: Commands,
     selected_tab: Res<SelectedTab>,
     q_selected: Single<Entity, With<SelectedCell>>,
+    blockers: InputBlockers,
 ) {
+    if blockers.blocked() {
+        return;
+    }
+    // 触摸屏上长按数字键输入候选数, 相当于键盘上的 Alt + 数字
+    let long_press =
+        trigger.pointer_id.is_touch() && trigger.event().duration >= LONG_PRESS_DURATION;
     if let Ok(cell_value) = q_cell.get(trigger.target()) {
         match selected_tab.0 {
+            ControlTab::Normal if long_press => {
+                commands.trigger_targets(NewCandidate::new(cell_value.0), vec![*q_selected]);
+            }
             ControlTab::Normal => {
                 commands.trigger_targets(NewDigit::new(cell_value.0), vec![*q_selected]);
             }
             ControlTab::Candidate => {
                 commands.trigger_targets(NewCandidate::new(cell_value.0), vec![*q_selected]);
             }
+            ControlTab::Corner => {
+                commands.trigger_targets(NewCornerCandidate::new(cell_value.0), vec![*q_selected]);
+            }
         }
     }
 }
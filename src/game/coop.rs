@@ -0,0 +1,1046 @@
+use crate::{
+    GameState,
+    game::{
+        CleanCell, NewDigit, SudokuManager,
+        cell_state::{
+            CellMode, ConflictCell, CornerCandidates, CorrectionCell, DigitValueCell, FixedCell,
+            ManualCandidates, RevealedCell, SelectedCell,
+        },
+        load_sudoku,
+        position::CellPosition,
+        replay::ReplayPlayer,
+        screen_reader::Announce,
+        trial::conflicts,
+    },
+    i18n::{Locale, Localized},
+    loading::FontAssets,
+    theme::{ThemeRole, ThemedText},
+};
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use std::sync::{
+    Mutex,
+    mpsc::{Receiver, Sender},
+};
+use sudoku::{Sudoku, bitset::Set, board::Digit};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<CoopSession>()
+        .init_resource::<RemoteEdits>()
+        .add_systems(Startup, start_coop)
+        .add_systems(
+            Update,
+            (
+                receive_coop_messages,
+                recheck_conflicts,
+                sync_puzzle.run_if(resource_changed::<SudokuManager>),
+                send_digits,
+                send_candidates,
+                send_corners,
+                update_coop_status.run_if(resource_changed::<CoopSession>),
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing).and(resource_exists::<CoopLink>)),
+        )
+        .add_systems(Last, forget_remote_edits)
+        .add_observer(send_cursor)
+        .add_observer(on_ping_partner);
+}
+
+/// 主机默认监听的地址
+pub const DEFAULT_HOST_ADDR: &str = "0.0.0.0:7878";
+/// 加入时默认连接的地址
+pub const DEFAULT_JOIN_ADDR: &str = "127.0.0.1:7878";
+
+/// 远端选中格子的描边颜色
+const REMOTE_CURSOR_COLOR: Color = Color::srgb(0.91, 0.29, 0.6);
+
+/// 命令行 `--host [addr]` 开主机, `--join [addr]` 加入
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoopRole {
+    Host(String),
+    Join(String),
+}
+
+impl CoopRole {
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<Self> {
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let (default, role): (_, fn(String) -> CoopRole) = match arg.as_str() {
+                "--host" => (DEFAULT_HOST_ADDR, CoopRole::Host),
+                "--join" => (DEFAULT_JOIN_ADDR, CoopRole::Join),
+                _ => continue,
+            };
+            let addr = args
+                .next_if(|addr| !addr.starts_with("--"))
+                .unwrap_or_else(|| default.to_string());
+            return Some(role(addr));
+        }
+        None
+    }
+
+    /// 时间戳相同时主机的修改优先
+    fn origin(&self) -> u8 {
+        match self {
+            CoopRole::Host(_) => 1,
+            CoopRole::Join(_) => 0,
+        }
+    }
+
+    fn remote_origin(&self) -> u8 {
+        1 - self.origin()
+    }
+}
+
+/// 一行一条的文本协议
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoopMessage {
+    /// 主机的题目
+    Puzzle(String),
+    /// 加入方请求主机重发题目和盘面
+    Sync,
+    Digit {
+        cell: u8,
+        digit: u8,
+        millis: u64,
+    },
+    /// 一个格子的全部手动候选数, 第 n 位是数字 n + 1
+    Candidates {
+        cell: u8,
+        candidates: u16,
+        millis: u64,
+    },
+    /// 一个格子的全部角标, 编码方式和手动候选数相同
+    Corner {
+        cell: u8,
+        corner: u16,
+        millis: u64,
+    },
+    Clear {
+        cell: u8,
+        millis: u64,
+    },
+    /// 对方选中的格子
+    Cursor(u8),
+    Chat(String),
+}
+
+impl CoopMessage {
+    pub fn encode(&self) -> String {
+        match self {
+            CoopMessage::Puzzle(line) => format!("puzzle {}", line),
+            CoopMessage::Sync => "sync".to_string(),
+            CoopMessage::Digit {
+                cell,
+                digit,
+                millis,
+            } => format!("digit {} {} {}", cell, digit, millis),
+            CoopMessage::Candidates {
+                cell,
+                candidates,
+                millis,
+            } => format!("cand {} {} {}", cell, candidates, millis),
+            CoopMessage::Corner {
+                cell,
+                corner,
+                millis,
+            } => format!("corner {} {} {}", cell, corner, millis),
+            CoopMessage::Clear { cell, millis } => format!("clear {} {}", cell, millis),
+            CoopMessage::Cursor(cell) => format!("cursor {}", cell),
+            CoopMessage::Chat(text) => format!("chat {}", text.replace('\n', " ")),
+        }
+    }
+
+    pub fn decode(line: &str) -> Option<Self> {
+        let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let fields = rest.split(' ').collect::<Vec<_>>();
+        let cell = || fields.first()?.parse::<u8>().ok().filter(|cell| *cell < 81);
+        let digit = || {
+            fields
+                .get(1)?
+                .parse::<u8>()
+                .ok()
+                .filter(|d| (1..=9).contains(d))
+        };
+        let mask = || {
+            fields
+                .get(1)?
+                .parse::<u16>()
+                .ok()
+                .filter(|mask| *mask < 1 << 9)
+        };
+        let millis = |index: usize| fields.get(index)?.parse::<u64>().ok();
+        let message = match kind {
+            "puzzle" => CoopMessage::Puzzle(rest.to_string()),
+            "sync" => CoopMessage::Sync,
+            "digit" => CoopMessage::Digit {
+                cell: cell()?,
+                digit: digit()?,
+                millis: millis(2)?,
+            },
+            "cand" => CoopMessage::Candidates {
+                cell: cell()?,
+                candidates: mask()?,
+                millis: millis(2)?,
+            },
+            "corner" => CoopMessage::Corner {
+                cell: cell()?,
+                corner: mask()?,
+                millis: millis(2)?,
+            },
+            "clear" => CoopMessage::Clear {
+                cell: cell()?,
+                millis: millis(1)?,
+            },
+            "cursor" => CoopMessage::Cursor(cell()?),
+            "chat" => CoopMessage::Chat(rest.to_string()),
+            _ => return None,
+        };
+        Some(message)
+    }
+}
+
+/// 修改的时间戳, 毫秒相同时按来源排序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Stamp {
+    pub millis: u64,
+    pub origin: u8,
+}
+
+/// 每个格子最后一次修改的时间, 后写入的生效
+#[derive(Debug, Default)]
+pub struct LastWriter(HashMap<u8, Stamp>);
+
+impl LastWriter {
+    /// 比已有的修改新才接受
+    pub fn accept(&mut self, cell: u8, stamp: Stamp) -> bool {
+        if self.0.get(&cell).is_some_and(|last| *last > stamp) {
+            return false;
+        }
+        self.0.insert(cell, stamp);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// 网络线程发给游戏的事件
+enum CoopEvent {
+    Connected,
+    Disconnected,
+    Error(String),
+    Message(CoopMessage),
+}
+
+/// 和网络线程之间的通道
+#[derive(Resource)]
+pub struct CoopLink {
+    role: CoopRole,
+    /// None 用来叫醒等待发送的线程
+    outgoing: Sender<Option<CoopMessage>>,
+    incoming: Mutex<Receiver<CoopEvent>>,
+}
+
+impl CoopLink {
+    fn send(&self, message: CoopMessage) {
+        let _ = self.outgoing.send(Some(message));
+    }
+}
+
+/// 这一帧里按对方的消息改过的格子
+///
+/// 对方填错的数字不算本机的错误, 也不记进回放和撤销
+#[derive(Resource, Debug, Default, Deref, DerefMut)]
+pub struct RemoteEdits(pub HashSet<Entity>);
+
+fn forget_remote_edits(mut remote_edits: ResMut<RemoteEdits>) {
+    if !remote_edits.is_empty() {
+        remote_edits.clear();
+    }
+}
+
+/// 合作模式的状态
+#[derive(Resource, Debug, Default)]
+pub struct CoopSession {
+    pub connected: bool,
+    pub status: Localized,
+    /// 已经和对方同步过的题目
+    synced_puzzle: Option<String>,
+    writes: LastWriter,
+    /// 双方都已经知道的数字, 本地改得不一样时才发出去
+    digits: HashMap<u8, Digit>,
+    /// 双方都已经知道的手动候选数, 本地改得不一样时才发出去
+    candidates: HashMap<u8, Set<Digit>>,
+    /// 双方都已经知道的角标, 和数字互不覆盖, 单独按时间戳比较
+    corners: HashMap<u8, Set<Digit>>,
+    corner_writes: LastWriter,
+}
+
+impl CoopSession {
+    /// 换题后重新开始记录
+    fn clear_edits(&mut self) {
+        self.writes.clear();
+        self.digits.clear();
+        self.candidates.clear();
+        self.corners.clear();
+        self.corner_writes.clear();
+    }
+
+    /// 本地数字和双方已知的不同时记下并返回要发的消息, 删掉数字时发 `Clear`
+    fn local_digit(&mut self, cell: u8, digit: Option<Digit>, stamp: Stamp) -> Option<CoopMessage> {
+        if self.digits.get(&cell).copied() == digit {
+            return None;
+        }
+        self.writes.accept(cell, stamp);
+        let millis = stamp.millis;
+        match digit {
+            Some(digit) => {
+                self.digits.insert(cell, digit);
+                Some(CoopMessage::Digit {
+                    cell,
+                    digit: digit.get(),
+                    millis,
+                })
+            }
+            None => {
+                self.digits.remove(&cell);
+                Some(CoopMessage::Clear { cell, millis })
+            }
+        }
+    }
+
+    /// 远端的数字比本地的修改新才采用
+    fn remote_digit(&mut self, cell: u8, digit: Option<Digit>, stamp: Stamp) -> bool {
+        if !self.writes.accept(cell, stamp) {
+            return false;
+        }
+        match digit {
+            Some(digit) => self.digits.insert(cell, digit),
+            None => self.digits.remove(&cell),
+        };
+        true
+    }
+
+    /// 本地候选数和双方已知的不同时记下并返回要发的消息
+    fn local_candidates(
+        &mut self,
+        cell: u8,
+        candidates: Set<Digit>,
+        stamp: Stamp,
+    ) -> Option<CoopMessage> {
+        let known = self.candidates.get(&cell).copied().unwrap_or(Set::NONE);
+        if known == candidates {
+            return None;
+        }
+        self.candidates.insert(cell, candidates);
+        self.writes.accept(cell, stamp);
+        Some(CoopMessage::Candidates {
+            cell,
+            candidates: candidates.bits(),
+            millis: stamp.millis,
+        })
+    }
+
+    /// 远端的候选数比本地的修改新才采用
+    fn remote_candidates(&mut self, cell: u8, candidates: u16, stamp: Stamp) -> Option<Set<Digit>> {
+        if !self.writes.accept(cell, stamp) {
+            return None;
+        }
+        let candidates = Set::from_bits(candidates);
+        // 标候选数的格子不会有数字
+        self.digits.remove(&cell);
+        self.candidates.insert(cell, candidates);
+        Some(candidates)
+    }
+
+    /// 本地角标和双方已知的不同时记下并返回要发的消息
+    fn local_corner(&mut self, cell: u8, corner: Set<Digit>, stamp: Stamp) -> Option<CoopMessage> {
+        let known = self.corners.get(&cell).copied().unwrap_or(Set::NONE);
+        if known == corner {
+            return None;
+        }
+        self.corners.insert(cell, corner);
+        self.corner_writes.accept(cell, stamp);
+        Some(CoopMessage::Corner {
+            cell,
+            corner: corner.bits(),
+            millis: stamp.millis,
+        })
+    }
+
+    /// 远端的角标比本地的修改新才采用
+    fn remote_corner(&mut self, cell: u8, corner: u16, stamp: Stamp) -> Option<Set<Digit>> {
+        if !self.corner_writes.accept(cell, stamp) {
+            return None;
+        }
+        let corner = Set::from_bits(corner);
+        self.corners.insert(cell, corner);
+        Some(corner)
+    }
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn start_coop(mut commands: Commands, mut session: ResMut<CoopSession>) {
+    let Some(role) = CoopRole::from_args(std::env::args().skip(1)) else {
+        return;
+    };
+    let (outgoing, outgoing_rx) = std::sync::mpsc::channel();
+    let (incoming_tx, incoming) = std::sync::mpsc::channel();
+    session.status = match &role {
+        CoopRole::Host(addr) => Localized::with_args("Co-op: hosting on {}", [addr]),
+        CoopRole::Join(addr) => Localized::with_args("Co-op: joining {}", [addr]),
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let role = role.clone();
+        let wake = outgoing.clone();
+        std::thread::spawn(move || network::run(role, outgoing_rx, incoming_tx, wake));
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        drop(outgoing_rx);
+        let _ = incoming_tx.send(CoopEvent::Error(
+            "co-op needs the desktop build".to_string(),
+        ));
+    }
+
+    commands.insert_resource(CoopLink {
+        role,
+        outgoing,
+        incoming: Mutex::new(incoming),
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod network {
+    use super::{CoopEvent, CoopMessage, CoopRole};
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::{Shutdown, TcpListener, TcpStream},
+        sync::mpsc::{Receiver, Sender},
+    };
+
+    pub(super) fn run(
+        role: CoopRole,
+        outgoing: Receiver<Option<CoopMessage>>,
+        incoming: Sender<CoopEvent>,
+        wake: Sender<Option<CoopMessage>>,
+    ) {
+        match role {
+            CoopRole::Host(addr) => match TcpListener::bind(&addr) {
+                // 一次只接一个对手, 断开后继续等下一个
+                Ok(listener) => {
+                    for stream in listener.incoming().flatten() {
+                        connection(stream, &outgoing, &incoming, &wake);
+                    }
+                }
+                Err(error) => {
+                    let _ = incoming.send(CoopEvent::Error(error.to_string()));
+                }
+            },
+            CoopRole::Join(addr) => match TcpStream::connect(&addr) {
+                Ok(stream) => connection(stream, &outgoing, &incoming, &wake),
+                Err(error) => {
+                    let _ = incoming.send(CoopEvent::Error(error.to_string()));
+                }
+            },
+        }
+    }
+
+    fn connection(
+        stream: TcpStream,
+        outgoing: &Receiver<Option<CoopMessage>>,
+        incoming: &Sender<CoopEvent>,
+        wake: &Sender<Option<CoopMessage>>,
+    ) {
+        let Ok(reader) = stream.try_clone() else {
+            return;
+        };
+        // 丢掉连上之前积压的消息
+        while outgoing.try_recv().is_ok() {}
+        let _ = incoming.send(CoopEvent::Connected);
+
+        let (incoming, wake) = (incoming.clone(), wake.clone());
+        std::thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if let Some(message) = CoopMessage::decode(&line)
+                    && incoming.send(CoopEvent::Message(message)).is_err()
+                {
+                    return;
+                }
+            }
+            let _ = incoming.send(CoopEvent::Disconnected);
+            let _ = wake.send(None);
+        });
+
+        let mut writer = stream;
+        while let Ok(Some(message)) = outgoing.recv() {
+            if writeln!(writer, "{}", message.encode()).is_err() {
+                break;
+            }
+        }
+        let _ = writer.shutdown(Shutdown::Both);
+    }
+}
+
+/// 远端玩家选中的格子
+#[derive(Component)]
+struct RemoteCursor;
+
+#[allow(clippy::too_many_arguments)]
+fn receive_coop_messages(
+    link: Res<CoopLink>,
+    mut session: ResMut<CoopSession>,
+    mut remote_edits: ResMut<RemoteEdits>,
+    locale: Res<Locale>,
+    sudoku_manager: Res<SudokuManager>,
+    q_cell: Query<(Entity, &CellPosition)>,
+    mut q_board: Query<
+        (
+            &CellPosition,
+            &mut DigitValueCell,
+            &mut ManualCandidates,
+            &mut CornerCandidates,
+            &mut CellMode,
+        ),
+        Without<FixedCell>,
+    >,
+    q_cursor: Query<Entity, With<RemoteCursor>>,
+    mut commands: Commands,
+) {
+    let events = match link.incoming.lock() {
+        Ok(incoming) => incoming.try_iter().collect::<Vec<_>>(),
+        Err(_) => return,
+    };
+    let find_cell = |cell: u8| {
+        q_cell
+            .iter()
+            .find(|(_, position)| position.0 == cell)
+            .map(|(entity, _)| entity)
+    };
+    let remote = link.role.remote_origin();
+
+    for event in events {
+        let message = match event {
+            CoopEvent::Connected => {
+                session.connected = true;
+                session.status = Localized::new("Co-op: connected");
+                commands.trigger(Announce(locale.tr("Partner connected").to_string()));
+                if let CoopRole::Host(_) = link.role {
+                    send_board(&link, &mut session, &sudoku_manager, &q_board.as_readonly());
+                }
+                continue;
+            }
+            CoopEvent::Disconnected => {
+                session.connected = false;
+                session.status = Localized::new("Co-op: partner left");
+                for entity in q_cursor.iter() {
+                    commands.entity(entity).remove::<(RemoteCursor, Outline)>();
+                }
+                continue;
+            }
+            CoopEvent::Error(error) => {
+                session.status = Localized::with_args("Co-op: {}", [error]);
+                continue;
+            }
+            CoopEvent::Message(message) => message,
+        };
+
+        match &message {
+            CoopMessage::Puzzle(line) => {
+                if let CoopRole::Join(_) = link.role
+                    && let Ok(sudoku) = Sudoku::from_str_line(line)
+                    && let Some(solution) = sudoku.solution()
+                {
+                    session.synced_puzzle = Some(line.clone());
+                    session.clear_edits();
+                    load_sudoku(&mut commands, &q_cell, sudoku, solution);
+                }
+            }
+            CoopMessage::Sync => {
+                if let CoopRole::Host(_) = link.role {
+                    send_board(&link, &mut session, &sudoku_manager, &q_board.as_readonly());
+                }
+            }
+            CoopMessage::Digit {
+                cell,
+                digit,
+                millis,
+            } => {
+                let stamp = Stamp {
+                    millis: *millis,
+                    origin: remote,
+                };
+                if let Some(entity) = find_cell(*cell)
+                    && session.remote_digit(*cell, Some(Digit::new(*digit)), stamp)
+                {
+                    remote_edits.insert(entity);
+                    commands.trigger_targets(NewDigit::new(*digit), entity);
+                }
+            }
+            CoopMessage::Candidates {
+                cell,
+                candidates,
+                millis,
+            } => {
+                let stamp = Stamp {
+                    millis: *millis,
+                    origin: remote,
+                };
+                // `NewCandidate` 一次只切换一个候选数, 自动候选数模式下还会改到自动候选数,
+                // 而消息里是对方整格的手动候选数, 所以直接写入, 冲突之后按整个盘面重新算
+                if let Some(entity) = find_cell(*cell)
+                    && let Ok((_, mut digit, mut manual, _, mut mode)) = q_board.get_mut(entity)
+                    && let Some(candidates) = session.remote_candidates(*cell, *candidates, stamp)
+                {
+                    remote_edits.insert(entity);
+                    if digit.0.take().is_some() {
+                        commands.entity(entity).remove::<CorrectionCell>();
+                    }
+                    manual.0 = candidates;
+                    if *mode == CellMode::Digit {
+                        *mode = CellMode::ManualCandidates;
+                    }
+                }
+            }
+            CoopMessage::Corner {
+                cell,
+                corner,
+                millis,
+            } => {
+                let stamp = Stamp {
+                    millis: *millis,
+                    origin: remote,
+                };
+                // 和候选数一样按整格写入
+                if let Some(entity) = find_cell(*cell)
+                    && let Ok((.., mut corner_candidates, _)) = q_board.get_mut(entity)
+                    && let Some(corner) = session.remote_corner(*cell, *corner, stamp)
+                {
+                    remote_edits.insert(entity);
+                    corner_candidates.0 = corner;
+                }
+            }
+            CoopMessage::Clear { cell, millis } => {
+                let stamp = Stamp {
+                    millis: *millis,
+                    origin: remote,
+                };
+                // 只清掉数字, 候选数由 `Candidates` 同步
+                if let Some(entity) = find_cell(*cell)
+                    && session.remote_digit(*cell, None, stamp)
+                    && q_board
+                        .get(entity)
+                        .is_ok_and(|(_, digit, ..)| digit.0.is_some())
+                {
+                    remote_edits.insert(entity);
+                    commands.trigger_targets(CleanCell, entity);
+                }
+            }
+            CoopMessage::Cursor(cell) => {
+                for entity in q_cursor.iter() {
+                    commands.entity(entity).remove::<(RemoteCursor, Outline)>();
+                }
+                if let Some(entity) = find_cell(*cell) {
+                    commands.entity(entity).insert((
+                        RemoteCursor,
+                        Outline::new(Val::Px(3.0), Val::ZERO, REMOTE_CURSOR_COLOR),
+                    ));
+                }
+            }
+            CoopMessage::Chat(text) => {
+                session.status = Localized::with_args("Partner: {}", [text]);
+                commands.trigger(Announce(locale.format("Partner says {}", &[text])));
+            }
+        }
+    }
+}
+
+/// 对方改过格子后按整个盘面重新算冲突
+///
+/// 填数和清除的冲突处理都以本机选中的格子为准, 对方改的格子不一定是选中的那个
+fn recheck_conflicts(
+    remote_edits: Res<RemoteEdits>,
+    q_cell: Query<(Entity, &CellPosition, &DigitValueCell)>,
+    mut commands: Commands,
+) {
+    if remote_edits.is_empty() {
+        return;
+    }
+    let digits = q_cell
+        .iter()
+        .map(|(entity, position, digit)| (entity, *position, digit.0))
+        .collect::<Vec<_>>();
+    for (entity, conflict) in conflicts(&digits) {
+        if conflict.is_empty() {
+            commands.entity(entity).remove::<ConflictCell>();
+        } else {
+            commands.entity(entity).insert(ConflictCell(conflict));
+        }
+    }
+}
+
+/// 主机把题目和已经填的格子发给对方
+///
+/// 对方会重新载入题目, 所以先忘掉双方已知的内容, 发出的格子再重新记下
+fn send_board(
+    link: &CoopLink,
+    session: &mut CoopSession,
+    sudoku_manager: &SudokuManager,
+    q_board: &Query<
+        (
+            &CellPosition,
+            &DigitValueCell,
+            &ManualCandidates,
+            &CornerCandidates,
+            &CellMode,
+        ),
+        Without<FixedCell>,
+    >,
+) {
+    let puzzle = sudoku_manager.solver.clone().to_sudoku();
+    link.send(CoopMessage::Puzzle(puzzle.to_str_line().to_string()));
+    session.digits.clear();
+    session.candidates.clear();
+    session.corners.clear();
+    let stamp = Stamp {
+        millis: now_millis(),
+        origin: link.role.origin(),
+    };
+    for (position, digit, candidates, corner, mode) in q_board.iter() {
+        let cell = position.0;
+        let messages = [
+            session.local_corner(cell, corner.0, stamp),
+            match mode {
+                CellMode::Digit => session.local_digit(cell, digit.0, stamp),
+                CellMode::ManualCandidates => session.local_candidates(cell, candidates.0, stamp),
+                CellMode::AutoCandidates => None,
+            },
+        ];
+        for message in messages.into_iter().flatten() {
+            link.send(message);
+        }
+    }
+}
+
+/// 主机换题时发给对方, 加入方换题时向主机要回题目
+fn sync_puzzle(
+    link: Res<CoopLink>,
+    mut session: ResMut<CoopSession>,
+    sudoku_manager: Res<SudokuManager>,
+) {
+    let line = sudoku_manager
+        .solver
+        .clone()
+        .to_sudoku()
+        .to_str_line()
+        .to_string();
+    if session.synced_puzzle.as_ref() == Some(&line) {
+        return;
+    }
+    session.synced_puzzle = Some(line.clone());
+    session.clear_edits();
+    if !session.connected {
+        return;
+    }
+    match link.role {
+        CoopRole::Host(_) => link.send(CoopMessage::Puzzle(line)),
+        CoopRole::Join(_) => link.send(CoopMessage::Sync),
+    }
+}
+
+/// 数字按整格发送, 试填退回等直接改盘面的操作也能同步
+#[allow(clippy::type_complexity)]
+fn send_digits(
+    q_cell: Query<
+        (&CellPosition, &DigitValueCell),
+        (
+            Changed<DigitValueCell>,
+            Without<FixedCell>,
+            Without<RevealedCell>,
+        ),
+    >,
+    link: Res<CoopLink>,
+    mut session: ResMut<CoopSession>,
+    player: Option<Res<ReplayPlayer>>,
+) {
+    if !session.connected || player.is_some() {
+        return;
+    }
+    let millis = now_millis();
+    for (position, digit) in q_cell.iter() {
+        let stamp = Stamp {
+            millis,
+            origin: link.role.origin(),
+        };
+        if let Some(message) = session.local_digit(position.0, digit.0, stamp) {
+            link.send(message);
+        }
+    }
+}
+
+/// 候选数按整格发送, 两边同时修改时也只会留下同一个结果
+fn send_candidates(
+    q_cell: Query<(&CellPosition, &ManualCandidates), Changed<ManualCandidates>>,
+    link: Res<CoopLink>,
+    mut session: ResMut<CoopSession>,
+    player: Option<Res<ReplayPlayer>>,
+) {
+    if !session.connected || player.is_some() {
+        return;
+    }
+    let millis = now_millis();
+    for (position, candidates) in q_cell.iter() {
+        let stamp = Stamp {
+            millis,
+            origin: link.role.origin(),
+        };
+        if let Some(message) = session.local_candidates(position.0, candidates.0, stamp) {
+            link.send(message);
+        }
+    }
+}
+
+/// 角标也按整格发送
+fn send_corners(
+    q_cell: Query<(&CellPosition, &CornerCandidates), Changed<CornerCandidates>>,
+    link: Res<CoopLink>,
+    mut session: ResMut<CoopSession>,
+    player: Option<Res<ReplayPlayer>>,
+) {
+    if !session.connected || player.is_some() {
+        return;
+    }
+    let millis = now_millis();
+    for (position, corner) in q_cell.iter() {
+        let stamp = Stamp {
+            millis,
+            origin: link.role.origin(),
+        };
+        if let Some(message) = session.local_corner(position.0, corner.0, stamp) {
+            link.send(message);
+        }
+    }
+}
+
+fn send_cursor(
+    trigger: Trigger<OnInsert, SelectedCell>,
+    q_cell: Query<&CellPosition>,
+    link: Option<Res<CoopLink>>,
+    session: Res<CoopSession>,
+) {
+    if let Some(link) = link
+        && session.connected
+        && let Ok(position) = q_cell.get(trigger.target())
+    {
+        link.send(CoopMessage::Cursor(position.0));
+    }
+}
+
+/// 提醒对方看自己选中的格子
+#[derive(Event)]
+pub struct PingPartner;
+
+fn on_ping_partner(
+    _trigger: Trigger<PingPartner>,
+    q_selected: Query<&CellPosition, With<SelectedCell>>,
+    link: Option<Res<CoopLink>>,
+    session: Res<CoopSession>,
+) {
+    if let Some(link) = link
+        && session.connected
+        && let Some(position) = q_selected.iter().next()
+    {
+        link.send(CoopMessage::Chat(format!(
+            "look at row {}, column {}",
+            position.row() + 1,
+            position.col() + 1
+        )));
+    }
+}
+
+#[derive(Component)]
+struct CoopStatusText;
+
+/// 工具栏上的合作模式状态
+pub(crate) fn coop_status(font_assets: &Res<FontAssets>, builder: &mut ChildSpawnerCommands<'_>) {
+    builder.spawn((
+        CoopStatusText,
+        Localized::new(""),
+        TextFont {
+            font_size: 16.0,
+            font: font_assets.franklin_500.clone(),
+            ..default()
+        },
+        ThemedText(ThemeRole::Text),
+        Node {
+            margin: UiRect::right(Val::Px(16.0)),
+            ..default()
+        },
+    ));
+}
+
+fn update_coop_status(
+    session: Res<CoopSession>,
+    mut q_text: Query<&mut Localized, With<CoopStatusText>>,
+) {
+    for mut text in q_text.iter_mut() {
+        *text = session.status.clone();
+    }
+}
+
+#[test]
+fn test_coop_message_round_trip() {
+    let messages = [
+        CoopMessage::Puzzle(Sudoku::generate().to_str_line().to_string()),
+        CoopMessage::Sync,
+        CoopMessage::Digit {
+            cell: 40,
+            digit: 5,
+            millis: 1_700_000_000_000,
+        },
+        CoopMessage::Candidates {
+            cell: 0,
+            candidates: 0b1_0000_0101,
+            millis: 12,
+        },
+        CoopMessage::Corner {
+            cell: 9,
+            corner: 0b1_1000_0000,
+            millis: 13,
+        },
+        CoopMessage::Clear {
+            cell: 80,
+            millis: 7,
+        },
+        CoopMessage::Cursor(3),
+        CoopMessage::Chat("look at row 1, column 2".to_string()),
+    ];
+    for message in messages {
+        assert_eq!(CoopMessage::decode(&message.encode()), Some(message));
+    }
+    assert_eq!(CoopMessage::decode("digit 81 5 0"), None);
+    assert_eq!(CoopMessage::decode("cand 0 512 0"), None);
+    assert_eq!(CoopMessage::decode("corner 0 512 0"), None);
+    assert_eq!(
+        CoopRole::from_args(["--join".to_string()]),
+        Some(CoopRole::Join(DEFAULT_JOIN_ADDR.to_string()))
+    );
+}
+
+#[test]
+fn test_last_writer_wins() {
+    let mut writes = LastWriter::default();
+    let stamp = |millis, origin| Stamp { millis, origin };
+    assert!(writes.accept(10, stamp(100, 0)));
+    assert!(!writes.accept(10, stamp(90, 1)));
+    assert!(writes.accept(10, stamp(100, 1)));
+    assert!(!writes.accept(10, stamp(100, 0)));
+    assert!(writes.accept(11, stamp(1, 0)));
+}
+
+#[test]
+fn test_concurrent_candidates_converge() {
+    let marks = |digits: &[u8]| {
+        digits
+            .iter()
+            .fold(Set::NONE, |set, digit| set | Digit::new(*digit).as_set())
+    };
+    let receive = |session: &mut CoopSession, message: &CoopMessage, origin: u8| {
+        if let CoopMessage::Candidates {
+            cell,
+            candidates,
+            millis,
+        } = *message
+        {
+            session.remote_candidates(cell, candidates, Stamp { millis, origin });
+        }
+    };
+
+    // 主机和加入方在同一毫秒改了同一格
+    let mut host = CoopSession::default();
+    let mut guest = CoopSession::default();
+    let from_host = host
+        .local_candidates(
+            7,
+            marks(&[1, 2]),
+            Stamp {
+                millis: 100,
+                origin: 1,
+            },
+        )
+        .unwrap();
+    let from_guest = guest
+        .local_candidates(
+            7,
+            marks(&[3]),
+            Stamp {
+                millis: 100,
+                origin: 0,
+            },
+        )
+        .unwrap();
+    receive(
+        &mut host,
+        &CoopMessage::decode(&from_guest.encode()).unwrap(),
+        0,
+    );
+    receive(
+        &mut guest,
+        &CoopMessage::decode(&from_host.encode()).unwrap(),
+        1,
+    );
+    assert_eq!(host.candidates.get(&7), guest.candidates.get(&7));
+    assert_eq!(host.candidates.get(&7), Some(&marks(&[1, 2])));
+
+    // 两条修改以相反的顺序到达
+    let mut first = CoopSession::default();
+    let mut second = CoopSession::default();
+    let older = CoopMessage::Candidates {
+        cell: 7,
+        candidates: marks(&[4]).bits(),
+        millis: 200,
+    };
+    let newer = CoopMessage::Candidates {
+        cell: 7,
+        candidates: marks(&[4, 5]).bits(),
+        millis: 300,
+    };
+    receive(&mut first, &older, 0);
+    receive(&mut first, &newer, 1);
+    receive(&mut second, &newer, 1);
+    receive(&mut second, &older, 0);
+    assert_eq!(first.candidates.get(&7), second.candidates.get(&7));
+    assert_eq!(first.candidates.get(&7), Some(&marks(&[4, 5])));
+}
+
+#[test]
+fn test_remote_digit_not_sent_back() {
+    let mut session = CoopSession::default();
+    let stamp = |millis| Stamp { millis, origin: 0 };
+    let digit = Some(Digit::new(4));
+    assert!(session.remote_digit(20, digit, stamp(100)));
+    assert_eq!(session.local_digit(20, digit, stamp(110)), None);
+    assert_eq!(
+        session.local_digit(20, None, stamp(120)),
+        Some(CoopMessage::Clear {
+            cell: 20,
+            millis: 120
+        })
+    );
+    assert!(!session.remote_digit(20, digit, stamp(90)));
+}
@@ -1,21 +1,187 @@
 use crate::{
     GameState,
-    color::{DARK_BLACK, WHITE_COLOR},
-    game::{GameTimer, ResetPuzzle, Settings, SudokuManager},
+    game::{
+        GameTimer, InitPuzzle, ResetPuzzle, Settings, SudokuManager,
+        accessibility::next_font_scale,
+        generator::next_difficulty,
+        lessons::{LessonProgress, StartLesson},
+        mistakes::{Mistakes, next_mistake_limit},
+        pause::next_auto_pause,
+        replay::WatchReplay,
+        scoring::{Difficulty, LastScore, Leaderboard},
+        sharing::ShareResult,
+        stats::GameStats,
+    },
+    i18n::{Locale, Localized, LocalizedFont},
     loading::{FontAssets, TextureAssets},
+    theme::{ThemeRole, ThemedBackground, ThemedText},
+};
+use accesskit::{Node as Accessible, Role, Toggled};
+use bevy::{
+    a11y::AccessibilityNode,
+    input_focus::tab_navigation::{TabGroup, TabIndex},
+    prelude::*,
 };
-use bevy::{prelude::*, window::WindowFocused};
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(
-        Update,
-        (check_window_focus, fade_in_animation, fade_out_animation)
-            .run_if(in_state(GameState::Playing)),
-    )
-    .add_observer(on_pause_game)
-    .add_observer(on_show_settings)
-    .add_observer(on_show_congrats)
-    .add_observer(on_hint);
+    app.init_resource::<DialogStack>()
+        .add_systems(
+            Update,
+            (
+                (
+                    close_dialog_on_escape,
+                    fade_in_animation,
+                    fade_out_animation,
+                )
+                    .run_if(in_state(GameState::Playing)),
+                update_setting_labels.run_if(resource_changed::<Settings>),
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            (pause_virtual_time, show_top_dialog).run_if(resource_changed::<DialogStack>),
+        )
+        .add_systems(OnExit(GameState::Playing), clear_dialog_stack)
+        .add_observer(on_close_dialog)
+        .add_observer(on_confirm)
+        .add_observer(on_close_confirm)
+        .add_observer(on_pause_game)
+        .add_observer(on_show_settings)
+        .add_observer(on_show_congrats)
+        .add_observer(on_show_game_over)
+        .add_observer(on_hint);
+}
+
+/// 弹窗的种类, 挂在弹窗主体上
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialog {
+    Pause,
+    Hint,
+    Settings,
+    Congrats,
+    GameOver,
+    Confirm,
+}
+
+impl Dialog {
+    /// 打开时暂停游戏时间
+    fn pauses_time(&self) -> bool {
+        !matches!(self, Dialog::Congrats | Dialog::GameOver)
+    }
+
+    /// 能用 Esc 或点击背景关闭
+    fn dismissible(&self) -> bool {
+        !matches!(self, Dialog::Congrats | Dialog::GameOver)
+    }
+}
+
+/// 当前打开的弹窗, 最后一个在最上层
+///
+/// 游戏时间的暂停只由这里决定, 各个弹窗只负责打开和关闭自己
+#[derive(Resource, Debug, Default)]
+pub struct DialogStack(Vec<Dialog>);
+
+impl DialogStack {
+    pub fn top(&self) -> Option<Dialog> {
+        self.0.last().copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// 有会暂停游戏时间的弹窗打开
+    pub fn pauses_time(&self) -> bool {
+        self.0.iter().any(Dialog::pauses_time)
+    }
+
+    pub fn is_open(&self, dialog: Dialog) -> bool {
+        self.0.contains(&dialog)
+    }
+
+    /// 已经打开时返回 false
+    fn open(&mut self, dialog: Dialog) -> bool {
+        if self.is_open(dialog) {
+            return false;
+        }
+        self.0.push(dialog);
+        true
+    }
+
+    /// 没有打开时返回 false
+    fn close(&mut self, dialog: Dialog) -> bool {
+        let Some(index) = self.0.iter().position(|open| *open == dialog) else {
+            return false;
+        };
+        self.0.remove(index);
+        true
+    }
+}
+
+/// 没有弹窗时才响应棋盘的键盘操作
+pub fn no_dialog_open(stack: Res<DialogStack>) -> bool {
+    stack.is_empty()
+}
+
+fn pause_virtual_time(stack: Res<DialogStack>, mut time: ResMut<Time<Virtual>>) {
+    if stack.pauses_time() {
+        time.pause();
+    } else {
+        time.unpause();
+    }
+}
+
+/// 叠在下面的弹窗先收起, 上面的关闭后再显示
+fn show_top_dialog(
+    stack: Res<DialogStack>,
+    mut q_dialog: Query<(&Dialog, &mut Node), Without<FadeOut>>,
+) {
+    for (dialog, mut node) in q_dialog.iter_mut() {
+        node.display = if stack.top() == Some(*dialog) {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+fn clear_dialog_stack(mut stack: ResMut<DialogStack>, mut time: ResMut<Time<Virtual>>) {
+    stack.0.clear();
+    time.unpause();
+}
+
+/// 关闭最上层的弹窗
+#[derive(Event)]
+pub struct CloseDialog;
+
+fn on_close_dialog(
+    _trigger: Trigger<CloseDialog>,
+    stack: Res<DialogStack>,
+    mut commands: Commands,
+) {
+    match stack.top() {
+        Some(Dialog::Pause) => commands.trigger(PauseGame(false)),
+        Some(Dialog::Hint) => commands.trigger(ShowHint(false)),
+        Some(Dialog::Settings) => commands.trigger(ShowSettings(false)),
+        Some(Dialog::Confirm) => commands.trigger(CloseConfirm),
+        Some(Dialog::Congrats | Dialog::GameOver) | None => {}
+    }
+}
+
+/// Esc 和返回键关闭最上层的弹窗, 没有弹窗时暂停游戏
+fn close_dialog_on_escape(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    stack: Res<DialogStack>,
+    mut commands: Commands,
+) {
+    if !keyboard_input.any_just_pressed([KeyCode::Escape, KeyCode::BrowserBack]) {
+        return;
+    }
+    match stack.top() {
+        Some(dialog) if dialog.dismissible() => commands.trigger(CloseDialog),
+        Some(_) => {}
+        None => commands.trigger(PauseGame(true)),
+    }
 }
 
 pub(crate) fn dialog_container(
@@ -42,14 +208,29 @@ pub(crate) fn dialog_container(
             ZIndex(999),
             // BackgroundColor(RED.into()),
         ))
-        .observe(|_: Trigger<Pointer<Click>>, mut commands: Commands| {
-            commands.trigger(PauseGame(false));
-            commands.trigger(ShowHint(false));
-            commands.trigger(ShowSettings(false));
+        .observe(|trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+            // 只处理点在背景上的点击, 弹窗里冒泡上来的不算
+            if trigger.event().target == trigger.target() {
+                commands.trigger(CloseDialog);
+            }
         });
 }
 
-fn dialog_child_body() -> (Node, BorderRadius, BoxShadow, BackgroundColor) {
+/// 弹窗主体, 对读屏软件是一个模态对话框, Tab 键只在弹窗内切换
+pub(crate) fn dialog_child_body(
+    label: &str,
+) -> (
+    Node,
+    BorderRadius,
+    BoxShadow,
+    ThemedBackground,
+    DialogBody,
+    AccessibilityNode,
+    TabGroup,
+) {
+    let mut accessible = Accessible::new(Role::Dialog);
+    accessible.set_label(label);
+    accessible.set_modal();
     (
         Node {
             // position_type: PositionType::Absolute,
@@ -73,7 +254,36 @@ fn dialog_child_body() -> (Node, BorderRadius, BoxShadow, BackgroundColor) {
             Val::Px(-1.0),
             Val::Px(12.0),
         ),
-        BackgroundColor(WHITE_COLOR),
+        ThemedBackground(ThemeRole::Surface),
+        DialogBody,
+        AccessibilityNode(accessible),
+        TabGroup::modal(),
+    )
+}
+
+#[derive(Component)]
+pub struct DialogBody;
+
+/// 弹窗右上角的关闭按钮
+fn close_button(texture_assets: &Res<TextureAssets>) -> impl Bundle {
+    let mut accessible = Accessible::new(Role::Button);
+    accessible.set_label("Close");
+    (
+        ImageNode {
+            image: texture_assets.close.clone(),
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            margin: UiRect::all(Val::Px(20.0)),
+            top: Val::Px(0.0),
+            right: Val::Px(0.0),
+            height: Val::Px(18.0),
+            width: Val::Px(18.0),
+            ..default()
+        },
+        AccessibilityNode(accessible),
+        TabIndex(0),
     )
 }
 
@@ -82,7 +292,8 @@ fn spawn_pause(font_assets: &Res<FontAssets>, builder: &mut ChildSpawnerCommands
         .spawn((
             Name::new("pause-container"),
             PauseContainer,
-            dialog_child_body(),
+            Dialog::Pause,
+            dialog_child_body("Game paused"),
         ))
         .with_children(|builder| {
             builder
@@ -95,13 +306,13 @@ fn spawn_pause(font_assets: &Res<FontAssets>, builder: &mut ChildSpawnerCommands
                 ))
                 .with_children(|builder| {
                     builder.spawn((
-                        Text::new("Your game has been paused"),
+                        Localized::new("Your game has been paused"),
                         TextFont {
                             font_size: 16.0,
                             font: font_assets.franklin_600.clone(),
                             ..default()
                         },
-                        TextColor(*DARK_BLACK),
+                        ThemedText(ThemeRole::Text),
                     ));
                 });
 
@@ -123,17 +334,18 @@ fn spawn_pause(font_assets: &Res<FontAssets>, builder: &mut ChildSpawnerCommands
                         ..default()
                     },
                     BorderRadius::all(Val::Px(40.0)),
-                    BackgroundColor(*DARK_BLACK),
+                    ThemedBackground(ThemeRole::Button),
+                    TabIndex(0),
                 ))
                 .with_children(|builder| {
                     builder.spawn((
-                        Text::new("Resume"),
+                        Localized::new("Resume"),
                         TextFont {
                             font_size: 14.0,
                             font: font_assets.franklin_500.clone(),
                             ..default()
                         },
-                        TextColor(WHITE_COLOR),
+                        ThemedText(ThemeRole::ButtonText),
                     ));
                 })
                 .observe(
@@ -153,24 +365,11 @@ fn spawn_hint(
         .spawn((
             Name::new("hint-container"),
             HintContainer,
-            dialog_child_body(),
+            Dialog::Hint,
+            dialog_child_body("How to play"),
         ))
         .with_children(|builder| {
-            builder.spawn((
-                ImageNode {
-                    image: texture_assets.close.clone(),
-                    ..default()
-                },
-                Node {
-                    position_type: PositionType::Absolute,
-                    margin: UiRect::all(Val::Px(20.0)),
-                    top: Val::Px(0.0),
-                    right: Val::Px(0.0),
-                    height: Val::Px(18.0),
-                    width: Val::Px(18.0),
-                    ..default()
-                },
-            )).observe(
+            builder.spawn(close_button(texture_assets)).observe(
                 |_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
                     commands.trigger(ShowHint(false));
                 },
@@ -186,27 +385,27 @@ fn spawn_hint(
                         // margin: UiRect::all(Val::Px(16.0)),
                         ..default()
                     },
-                    // BackgroundColor(*DARK_BLACK),
+                    // ThemedBackground(ThemeRole::Button),
                 ))
                 .with_children(|builder| {
                     builder.spawn((
-                        Text::new("How to play Sudoku"),
+                        Localized::new("How to play Sudoku"),
                         TextFont {
                             font_size: 28.0,
                             font: font_assets.karnak.clone(),
                             ..default()
                         },
-                        TextColor(*DARK_BLACK),
+                        ThemedText(ThemeRole::Text),
                     ));
 
                     builder.spawn((
-                        Text::new("Fill each 3 x 3 set with numbers 1–9."),
+                        Localized::new("Fill each 3 x 3 set with numbers 1–9."),
                         TextFont {
                             font_size: 16.0,
                             font: font_assets.franklin_600.clone(),
                             ..default()
                         },
-                        TextColor(*DARK_BLACK),
+                        ThemedText(ThemeRole::Text),
                     ));
 
                     builder
@@ -226,14 +425,55 @@ fn spawn_hint(
                             ui_list(font_assets, texture_assets, builder, "Note: Each number can only appear on the board 9 times.");
                         });
 
+                    builder
+                        .spawn((
+                            Name::new("lessons-button"),
+                            Button,
+                            Node {
+                                width: Val::Auto,
+                                align_self: AlignSelf::Start,
+                                margin: UiRect::bottom(Val::Px(16.0)),
+                                padding: UiRect::horizontal(Val::Px(24.0)),
+                                min_height: Val::Px(40.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            BorderRadius::all(Val::Px(40.0)),
+                            ThemedBackground(ThemeRole::Button),
+                            TabIndex(0),
+                        ))
+                        .with_children(|builder| {
+                            builder.spawn((
+                                Localized::new("Start lessons"),
+                                TextFont {
+                                    font_size: 14.0,
+                                    font: font_assets.franklin_500.clone(),
+                                    ..default()
+                                },
+                                ThemedText(ThemeRole::ButtonText),
+                            ));
+                        })
+                        .observe(
+                            |_trigger: Trigger<Pointer<Click>>,
+                             progress: Res<LessonProgress>,
+                             mut commands: Commands| {
+                                commands.trigger(ShowHint(false));
+                                commands.trigger(StartLesson {
+                                    lesson: progress.next_lesson(),
+                                    puzzle: 0,
+                                });
+                            },
+                        );
+
                     builder.spawn((
-                        Text::new("Play modes and tips"),
+                        Localized::new("Play modes and tips"),
                         TextFont {
                             font_size: 28.0,
                             font: font_assets.karnak.clone(),
                             ..default()
                         },
-                        TextColor(*DARK_BLACK),
+                        ThemedText(ThemeRole::Text),
                     ));
 
                     builder
@@ -264,23 +504,23 @@ fn spawn_hint(
                         },
                     )).with_children(|builder| {
                         builder.spawn((
-                            Text::new("Have feedback? "),
+                            Localized::new("Have feedback? "),
                             TextFont {
                                 font_size: 16.0,
                                 font: font_assets.franklin_600.clone(),
                                 ..default()
                             },
-                            TextColor(*DARK_BLACK),
+                            ThemedText(ThemeRole::Text),
                         ));
 
                         builder.spawn((
-                            Text::new("Email us"),
+                            Localized::new("Email us"),
                             TextFont {
                                 font_size: 16.0,
                                 font: font_assets.franklin_600.clone(),
                                 ..default()
                             },
-                            TextColor(*DARK_BLACK),
+                            ThemedText(ThemeRole::Text),
                         ));
                     })
                     ;
@@ -292,7 +532,7 @@ fn ui_list(
     font_assets: &Res<FontAssets>,
     texture_assets: &Res<TextureAssets>,
     builder: &mut ChildSpawnerCommands<'_>,
-    text: &str,
+    text: &'static str,
 ) {
     builder
         .spawn(Node {
@@ -318,31 +558,23 @@ fn ui_list(
             ));
 
             builder.spawn((
-                Text::new(text),
+                Localized::new(text),
                 TextFont {
                     font_size: 16.0,
                     font: font_assets.franklin_600.clone(),
                     ..default()
                 },
-                TextColor(*DARK_BLACK),
+                ThemedText(ThemeRole::Text),
             ));
         });
 }
 
-fn check_window_focus(mut windows: EventReader<WindowFocused>, mut commands: Commands) {
-    for window in windows.read() {
-        if !window.focused {
-            commands.trigger(PauseGame(true));
-        }
-    }
-}
-
 #[derive(Event)]
 pub struct PauseGame(pub bool);
 
 fn on_pause_game(
     ev: Trigger<PauseGame>,
-    mut time: ResMut<Time<Virtual>>,
+    mut stack: ResMut<DialogStack>,
     mut commands: Commands,
     q_dialog: Single<(Entity, &mut Visibility), With<DialogContainer>>,
     font_assets: Res<FontAssets>,
@@ -351,17 +583,15 @@ fn on_pause_game(
 ) {
     let (entity, mut visibility) = q_dialog.into_inner();
     if ev.event().0 {
-        if time.is_paused() || sudoku_manager.is_solved {
+        if sudoku_manager.is_solved || !stack.open(Dialog::Pause) {
             return;
         }
 
-        time.pause();
         *visibility = Visibility::Visible;
         commands.entity(entity).with_children(|builder| {
             spawn_pause(&font_assets, builder);
         });
-    } else {
-        time.unpause();
+    } else if stack.close(Dialog::Pause) {
         for pause in q_pause.iter() {
             commands
                 .entity(pause)
@@ -412,6 +642,7 @@ fn fade_in_animation(
 
 fn fade_out_animation(
     time: Res<Time<Real>>,
+    stack: Res<DialogStack>,
     mut q: Query<(Entity, &mut Node, &mut FadeOut), Without<DialogContainer>>,
     mut q_dialog: Single<&mut Visibility, (With<DialogContainer>, Without<FadeOut>)>,
     mut commands: Commands,
@@ -420,7 +651,9 @@ fn fade_out_animation(
         fade_out.0.tick(time.delta());
         node.bottom = Val::Px(-fade_out.percent() * 60.0);
         if fade_out.0.just_finished() {
-            **q_dialog = Visibility::Hidden;
+            if stack.0.is_empty() {
+                **q_dialog = Visibility::Hidden;
+            }
             commands.entity(entity).despawn();
         }
     }
@@ -438,26 +671,23 @@ pub struct ShowHint(pub bool);
 #[allow(clippy::too_many_arguments)]
 fn on_hint(
     trigger: Trigger<ShowHint>,
-    mut time: ResMut<Time<Virtual>>,
+    mut stack: ResMut<DialogStack>,
     mut commands: Commands,
     q_dialog: Single<(Entity, &mut Visibility), With<DialogContainer>>,
     font_assets: Res<FontAssets>,
     texture_assets: Res<TextureAssets>,
     q_hint: Query<Entity, With<HintContainer>>,
-    mut opened: Local<Opened>,
 ) {
     let (entity, mut visibility) = q_dialog.into_inner();
-    let show_hint = trigger.event().0;
-    if show_hint && !opened.0 {
-        opened.0 = true;
-        time.pause();
+    if trigger.event().0 {
+        if !stack.open(Dialog::Hint) {
+            return;
+        }
         *visibility = Visibility::Visible;
         commands.entity(entity).with_children(|builder| {
             spawn_hint(&font_assets, &texture_assets, builder);
         });
-    } else {
-        opened.0 = false;
-        time.unpause();
+    } else if stack.close(Dialog::Hint) {
         for hint in q_hint.iter() {
             commands
                 .entity(hint)
@@ -475,26 +705,27 @@ pub struct SettingContainer;
 #[allow(clippy::too_many_arguments)]
 fn on_show_settings(
     trigger: Trigger<ShowSettings>,
-    mut time: ResMut<Time<Virtual>>,
+    mut stack: ResMut<DialogStack>,
     mut commands: Commands,
     q_dialog: Single<(Entity, &mut Visibility), With<DialogContainer>>,
     font_assets: Res<FontAssets>,
     texture_assets: Res<TextureAssets>,
     q_setting: Query<Entity, With<SettingContainer>>,
     setting: Res<Settings>,
-    mut opened: Local<Opened>,
+    locale: Res<Locale>,
+    fonts: Res<Assets<Font>>,
 ) {
     let (entity, mut visibility) = q_dialog.into_inner();
-    if trigger.event().0 && !opened.0 {
-        opened.0 = true;
-        time.pause();
+    if trigger.event().0 {
+        if !stack.open(Dialog::Settings) {
+            return;
+        }
         *visibility = Visibility::Visible;
         commands.entity(entity).with_children(|builder| {
-            spawn_settings(&font_assets, &texture_assets, builder, &setting);
+            let languages = locale.can_switch_language(&fonts);
+            spawn_settings(&font_assets, &texture_assets, builder, &setting, languages);
         });
-    } else {
-        opened.0 = false;
-        time.unpause();
+    } else if stack.close(Dialog::Settings) {
         for hint in q_setting.iter() {
             commands
                 .entity(hint)
@@ -508,35 +739,21 @@ fn spawn_settings(
     texture_assets: &Res<TextureAssets>,
     builder: &mut ChildSpawnerCommands<'_>,
     settings: &Res<Settings>,
+    languages: bool,
 ) {
     builder
         .spawn((
             Name::new("setting-container"),
             SettingContainer,
-            dialog_child_body(),
+            Dialog::Settings,
+            dialog_child_body("Settings"),
         ))
         .with_children(|builder| {
-            builder
-                .spawn((
-                    ImageNode {
-                        image: texture_assets.close.clone(),
-                        ..default()
-                    },
-                    Node {
-                        position_type: PositionType::Absolute,
-                        margin: UiRect::all(Val::Px(20.0)),
-                        top: Val::Px(0.0),
-                        right: Val::Px(0.0),
-                        height: Val::Px(18.0),
-                        width: Val::Px(18.0),
-                        ..default()
-                    },
-                ))
-                .observe(
-                    |_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
-                        commands.trigger(ShowSettings(false));
-                    },
-                );
+            builder.spawn(close_button(texture_assets)).observe(
+                |_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                    commands.trigger(ShowSettings(false));
+                },
+            );
 
             builder
                 .spawn((
@@ -548,17 +765,17 @@ fn spawn_settings(
                         // margin: UiRect::all(Val::Px(16.0)),
                         ..default()
                     },
-                    // BackgroundColor(*DARK_BLACK),
+                    // ThemedBackground(ThemeRole::Button),
                 ))
                 .with_children(|builder| {
                     builder.spawn((
-                        Text::new("Settings"),
+                        Localized::new("Settings"),
                         TextFont {
                             font_size: 28.0,
                             font: font_assets.karnak.clone(),
                             ..default()
                         },
-                        TextColor(*DARK_BLACK),
+                        ThemedText(ThemeRole::Text),
                         Node {
                             margin: UiRect {
                                 bottom: Val::Px(20.0),
@@ -619,6 +836,150 @@ fn spawn_settings(
                             settings.show_clock = !settings.show_clock;
                         },
                     );
+                    setting_item(
+                        font_assets,
+                        texture_assets,
+                        builder,
+                        "Dark mode",
+                        settings.dark_mode,
+                        |_trigger, mut settings| {
+                            settings.dark_mode = !settings.dark_mode;
+                        },
+                    );
+                    setting_item(
+                        font_assets,
+                        texture_assets,
+                        builder,
+                        "High contrast",
+                        settings.high_contrast,
+                        |_trigger, mut settings| {
+                            settings.high_contrast = !settings.high_contrast;
+                        },
+                    );
+                    setting_item(
+                        font_assets,
+                        texture_assets,
+                        builder,
+                        "Accessibility cues",
+                        settings.accessibility_cues,
+                        |_trigger, mut settings| {
+                            settings.accessibility_cues = !settings.accessibility_cues;
+                        },
+                    );
+                    setting_item(
+                        font_assets,
+                        texture_assets,
+                        builder,
+                        "Count mistakes",
+                        settings.count_mistakes,
+                        |_trigger, mut settings| {
+                            settings.count_mistakes = !settings.count_mistakes;
+                        },
+                    );
+                    setting_item(
+                        font_assets,
+                        texture_assets,
+                        builder,
+                        "Strict mode",
+                        settings.strict_mode,
+                        |_trigger, mut settings| {
+                            settings.strict_mode = !settings.strict_mode;
+                        },
+                    );
+                    setting_cycle_item(
+                        font_assets,
+                        builder,
+                        settings,
+                        |settings| {
+                            let language = settings.language;
+                            language.format("Mistakes allowed: {}", &[&settings.max_mistakes])
+                        },
+                        |settings| {
+                            settings.max_mistakes = next_mistake_limit(settings.max_mistakes)
+                        },
+                    );
+                    setting_cycle_item(
+                        font_assets,
+                        builder,
+                        settings,
+                        |settings| {
+                            let language = settings.language;
+                            language.format(
+                                "Colour palette: {}",
+                                &[&language.tr(settings.palette.label())],
+                            )
+                        },
+                        |settings| settings.palette = settings.palette.next(),
+                    );
+                    setting_cycle_item(
+                        font_assets,
+                        builder,
+                        settings,
+                        |settings| {
+                            let size = format!("{:.0}%", settings.font_scale * 100.0);
+                            settings.language.format("Text size: {}", &[&size])
+                        },
+                        |settings| settings.font_scale = next_font_scale(settings.font_scale),
+                    );
+                    setting_cycle_item(
+                        font_assets,
+                        builder,
+                        settings,
+                        |settings| {
+                            let language = settings.language;
+                            language.format(
+                                "Auto candidates: {}",
+                                &[&language.tr(settings.candidate_elimination.label())],
+                            )
+                        },
+                        |settings| {
+                            settings.candidate_elimination = settings.candidate_elimination.next()
+                        },
+                    );
+                    setting_cycle_item(
+                        font_assets,
+                        builder,
+                        settings,
+                        |settings| {
+                            let language = settings.language;
+                            let limit = match settings.auto_pause_after {
+                                Some(seconds) => language.format("{} min", &[&(seconds / 60)]),
+                                None => language.tr("Off").to_string(),
+                            };
+                            language.format("Auto pause: {}", &[&limit])
+                        },
+                        |settings| {
+                            settings.auto_pause_after = next_auto_pause(settings.auto_pause_after)
+                        },
+                    );
+                    setting_cycle_item(
+                        font_assets,
+                        builder,
+                        settings,
+                        |settings| {
+                            let language = settings.language;
+                            let difficulty = settings
+                                .puzzle_difficulty
+                                .map_or("Any", |difficulty| difficulty.label());
+                            language.format("New puzzles: {}", &[&language.tr(difficulty)])
+                        },
+                        |settings| {
+                            settings.puzzle_difficulty = next_difficulty(settings.puzzle_difficulty)
+                        },
+                    );
+                    // 没有中文字体时只能用英文
+                    if languages {
+                        setting_cycle_item(
+                            font_assets,
+                            builder,
+                            settings,
+                            |settings| {
+                                let language = settings.language;
+                                language.format("Language: {}", &[&language.label()])
+                            },
+                            |settings| settings.language = settings.language.next(),
+                        );
+                    }
                 });
         });
 }
@@ -627,7 +988,7 @@ fn setting_item(
     font_assets: &Res<FontAssets>,
     texture_assets: &Res<TextureAssets>,
     builder: &mut ChildSpawnerCommands<'_>,
-    text: &str,
+    text: &'static str,
     checked: bool,
     change_setting: fn(Trigger<Pointer<Click>>, settings: ResMut<Settings>),
 ) {
@@ -644,6 +1005,8 @@ fn setting_item(
                 ..default()
             },
             CheckOption(checked),
+            setting_accessible(text, checked),
+            TabIndex(0),
         ))
         .observe(click_setting_option)
         .observe(change_setting)
@@ -663,13 +1026,13 @@ fn setting_item(
             ));
 
             builder.spawn((
-                Text::new(text),
+                Localized::new(text),
                 TextFont {
                     font: font_assets.franklin_600.clone(),
                     font_size: 16.0,
                     ..default()
                 },
-                TextColor(*DARK_BLACK),
+                ThemedText(ThemeRole::Text),
                 Node {
                     margin: UiRect {
                         left: Val::Px(18.0),
@@ -681,17 +1044,80 @@ fn setting_item(
         });
 }
 
+/// 点击后在几个选项之间切换的设置项
+fn setting_cycle_item(
+    font_assets: &Res<FontAssets>,
+    builder: &mut ChildSpawnerCommands<'_>,
+    settings: &Res<Settings>,
+    label: fn(&Settings) -> String,
+    cycle: fn(&mut Settings),
+) {
+    builder
+        .spawn((
+            Name::new("text"),
+            Text::new(label(settings)),
+            SettingLabel(label),
+            LocalizedFont::default(),
+            TextFont {
+                font: font_assets.franklin_600.clone(),
+                font_size: 16.0,
+                ..default()
+            },
+            ThemedText(ThemeRole::Text),
+            TabIndex(0),
+            Node {
+                margin: UiRect {
+                    top: Val::Px(10.0),
+                    left: Val::Px(18.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .observe(
+            move |_trigger: Trigger<Pointer<Click>>, mut settings: ResMut<Settings>| {
+                cycle(&mut settings);
+            },
+        );
+}
+
+/// 循环切换的设置项, 设置变化时按当前语言重新生成文字
+#[derive(Component)]
+struct SettingLabel(fn(&Settings) -> String);
+
+fn update_setting_labels(settings: Res<Settings>, mut q_text: Query<(&mut Text, &SettingLabel)>) {
+    for (mut text, SettingLabel(label)) in q_text.iter_mut() {
+        text.0 = label(&settings);
+    }
+}
+
+fn setting_accessible(text: &str, checked: bool) -> AccessibilityNode {
+    let mut accessible = Accessible::new(Role::CheckBox);
+    accessible.set_label(text);
+    accessible.set_toggled(if checked {
+        Toggled::True
+    } else {
+        Toggled::False
+    });
+    AccessibilityNode(accessible)
+}
+
 #[derive(Component)]
 pub struct CheckOption(pub bool);
 
 fn click_setting_option(
     trigger: Trigger<Pointer<Click>>,
-    mut q_option: Query<(&mut CheckOption, &Children)>,
+    mut q_option: Query<(&mut CheckOption, &mut AccessibilityNode, &Children)>,
     mut q_image: Query<&mut ImageNode>,
     texture_assets: Res<TextureAssets>,
 ) {
-    if let Ok((mut checked, children)) = q_option.get_mut(trigger.target()) {
+    if let Ok((mut checked, mut accessible, children)) = q_option.get_mut(trigger.target()) {
         checked.0 = !checked.0;
+        accessible.set_toggled(if checked.0 {
+            Toggled::True
+        } else {
+            Toggled::False
+        });
         for child in children {
             if let Ok(mut image) = q_image.get_mut(*child) {
                 if checked.0 {
@@ -710,22 +1136,38 @@ pub struct ShowCongrats(pub bool);
 #[derive(Component)]
 pub struct CongratsContainer;
 
+#[allow(clippy::too_many_arguments)]
 fn on_show_congrats(
     trigger: Trigger<ShowCongrats>,
+    mut stack: ResMut<DialogStack>,
     mut commands: Commands,
     q_dialog: Single<(Entity, &mut Visibility), With<DialogContainer>>,
     font_assets: Res<FontAssets>,
     texture_assets: Res<TextureAssets>,
     q_congrats: Query<Entity, With<CongratsContainer>>,
     game_timer: Res<GameTimer>,
+    last_score: Option<Res<LastScore>>,
+    leaderboard: Res<Leaderboard>,
+    locale: Res<Locale>,
 ) {
     let (entity, mut visibility) = q_dialog.into_inner();
     if trigger.event().0 {
+        if !stack.open(Dialog::Congrats) {
+            return;
+        }
         *visibility = Visibility::Visible;
         commands.entity(entity).with_children(|builder| {
-            spawn_congrats(&font_assets, &texture_assets, builder, game_timer.clone());
+            spawn_congrats(
+                &font_assets,
+                &texture_assets,
+                builder,
+                game_timer.clone(),
+                last_score.as_deref(),
+                &leaderboard,
+                &locale,
+            );
         });
-    } else {
+    } else if stack.close(Dialog::Congrats) {
         for congrats in q_congrats.iter() {
             commands
                 .entity(congrats)
@@ -739,35 +1181,23 @@ fn spawn_congrats(
     texture_assets: &Res<TextureAssets>,
     builder: &mut ChildSpawnerCommands<'_>,
     timer: GameTimer,
+    last_score: Option<&LastScore>,
+    leaderboard: &Leaderboard,
+    locale: &Locale,
 ) {
     builder
         .spawn((
             Name::new("congrats-container"),
             CongratsContainer,
-            dialog_child_body(),
+            Dialog::Congrats,
+            dialog_child_body("Congratulations"),
         ))
         .with_children(|builder| {
-            builder
-                .spawn((
-                    ImageNode {
-                        image: texture_assets.close.clone(),
-                        ..default()
-                    },
-                    Node {
-                        position_type: PositionType::Absolute,
-                        margin: UiRect::all(Val::Px(20.0)),
-                        top: Val::Px(0.0),
-                        right: Val::Px(0.0),
-                        height: Val::Px(18.0),
-                        width: Val::Px(18.0),
-                        ..default()
-                    },
-                ))
-                .observe(
-                    |_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
-                        commands.trigger(ShowCongrats(false));
-                    },
-                );
+            builder.spawn(close_button(texture_assets)).observe(
+                |_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                    commands.trigger(ShowCongrats(false));
+                },
+            );
 
             builder.spawn((
                 Name::new("congrats-star"),
@@ -795,8 +1225,19 @@ fn spawn_congrats(
                     height: Val::Px(20.0),
                     ..default()
                 },
-                Text::new(format!("You finished a Easy puzzle in {}", timer)),
-                TextColor(*DARK_BLACK),
+                Text::new(locale.format(
+                    last_score.map_or(
+                        "You finished a puzzle in {}",
+                        |score| match score.difficulty {
+                            Difficulty::Easy => "You finished an Easy puzzle in {}",
+                            Difficulty::Medium => "You finished a Medium puzzle in {}",
+                            Difficulty::Hard => "You finished a Hard puzzle in {}",
+                        },
+                    ),
+                    &[&timer],
+                )),
+                LocalizedFont::default(),
+                ThemedText(ThemeRole::Text),
                 TextLayout::default(),
                 TextFont {
                     font: font_assets.franklin_600.clone(),
@@ -805,6 +1246,10 @@ fn spawn_congrats(
                 },
             ));
 
+            if let Some(score) = last_score {
+                spawn_score_breakdown(font_assets, builder, score, leaderboard, locale);
+            }
+
             builder
                 .spawn((
                     Name::new("replay-button"),
@@ -823,17 +1268,17 @@ fn spawn_congrats(
                         ..default()
                     },
                     BorderRadius::all(Val::Px(40.0)),
-                    BackgroundColor(*DARK_BLACK),
+                    ThemedBackground(ThemeRole::Button),
                 ))
                 .with_children(|builder| {
                     builder.spawn((
-                        Text::new("Play another Sudoku"),
+                        Localized::new("Play another Sudoku"),
                         TextFont {
                             font_size: 14.0,
                             font: font_assets.franklin_500.clone(),
                             ..default()
                         },
-                        TextColor(WHITE_COLOR),
+                        ThemedText(ThemeRole::ButtonText),
                     ));
                 })
                 .observe(
@@ -842,5 +1287,432 @@ fn spawn_congrats(
                         commands.trigger(ShowCongrats(false));
                     },
                 );
+
+            builder
+                .spawn((
+                    Name::new("watch-replay-button"),
+                    Button,
+                    Node {
+                        margin: UiRect::top(Val::Px(12.0)),
+                        padding: UiRect::horizontal(Val::Px(38.0)),
+                        min_height: Val::Px(40.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(40.0)),
+                    ThemedBackground(ThemeRole::Button),
+                    TabIndex(0),
+                ))
+                .with_children(|builder| {
+                    builder.spawn((
+                        Localized::new("Watch replay"),
+                        TextFont {
+                            font_size: 14.0,
+                            font: font_assets.franklin_500.clone(),
+                            ..default()
+                        },
+                        ThemedText(ThemeRole::ButtonText),
+                    ));
+                })
+                .observe(
+                    |_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                        commands.trigger(ShowCongrats(false));
+                        commands.trigger(WatchReplay);
+                    },
+                );
+
+            builder
+                .spawn((
+                    Name::new("share-result-button"),
+                    Button,
+                    Node {
+                        margin: UiRect::top(Val::Px(12.0)),
+                        padding: UiRect::horizontal(Val::Px(38.0)),
+                        min_height: Val::Px(40.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(40.0)),
+                    ThemedBackground(ThemeRole::Button),
+                    TabIndex(0),
+                ))
+                .with_children(|builder| {
+                    builder.spawn((
+                        Localized::new("Share result"),
+                        TextFont {
+                            font_size: 14.0,
+                            font: font_assets.franklin_500.clone(),
+                            ..default()
+                        },
+                        ThemedText(ThemeRole::ButtonText),
+                    ));
+                })
+                .observe(
+                    |_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                        commands.trigger(ShareResult);
+                    },
+                );
+        });
+}
+
+#[derive(Event)]
+pub struct ShowGameOver(pub bool);
+
+#[derive(Component)]
+pub struct GameOverContainer;
+
+#[allow(clippy::too_many_arguments)]
+fn on_show_game_over(
+    trigger: Trigger<ShowGameOver>,
+    mut stack: ResMut<DialogStack>,
+    mut commands: Commands,
+    q_dialog: Single<(Entity, &mut Visibility), With<DialogContainer>>,
+    font_assets: Res<FontAssets>,
+    q_game_over: Query<Entity, With<GameOverContainer>>,
+    mistakes: Res<Mistakes>,
+    stats: Res<GameStats>,
+    locale: Res<Locale>,
+) {
+    let (entity, mut visibility) = q_dialog.into_inner();
+    if trigger.event().0 {
+        if !stack.open(Dialog::GameOver) {
+            return;
+        }
+        *visibility = Visibility::Visible;
+        commands.entity(entity).with_children(|builder| {
+            spawn_game_over(&font_assets, builder, &mistakes, &stats, &locale);
+        });
+    } else if stack.close(Dialog::GameOver) {
+        for game_over in q_game_over.iter() {
+            commands
+                .entity(game_over)
+                .insert(FadeOut(Timer::from_seconds(0.2, TimerMode::Once)));
+        }
+    }
+}
+
+fn spawn_game_over(
+    font_assets: &Res<FontAssets>,
+    builder: &mut ChildSpawnerCommands<'_>,
+    mistakes: &Mistakes,
+    stats: &GameStats,
+    locale: &Locale,
+) {
+    builder
+        .spawn((
+            Name::new("game-over-container"),
+            GameOverContainer,
+            Dialog::GameOver,
+            dialog_child_body("Game over"),
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                Localized::new("Game over"),
+                TextFont {
+                    font_size: 28.0,
+                    font: font_assets.karnak.clone(),
+                    ..default()
+                },
+                ThemedText(ThemeRole::Text),
+            ));
+
+            builder.spawn((
+                Node {
+                    margin: UiRect::top(Val::Px(18.0)),
+                    ..default()
+                },
+                Text::new(locale.format(
+                    "You made {} mistakes. This session: {} won, {} lost, {} mistakes.",
+                    &[
+                        &mistakes.count,
+                        &stats.won(),
+                        &stats.lost(),
+                        &stats.total_mistakes(),
+                    ],
+                )),
+                LocalizedFont::default(),
+                ThemedText(ThemeRole::Text),
+                TextFont {
+                    font: font_assets.franklin_600.clone(),
+                    font_size: 16.0,
+                    ..default()
+                },
+            ));
+
+            builder
+                .spawn(Node {
+                    display: Display::Flex,
+                    column_gap: Val::Px(12.0),
+                    margin: UiRect::top(Val::Px(30.0)),
+                    ..default()
+                })
+                .with_children(|builder| {
+                    dialog_button(font_assets, builder, "Retry", |mut commands| {
+                        commands.trigger(ShowGameOver(false));
+                        commands.trigger(ResetPuzzle);
+                    });
+                    dialog_button(font_assets, builder, "New puzzle", |mut commands| {
+                        commands.trigger(ShowGameOver(false));
+                        commands.trigger(InitPuzzle);
+                    });
+                });
         });
 }
+
+/// 需要玩家确认的操作, 确认后执行 `action`
+#[derive(Event, Clone, Copy)]
+pub struct Confirm {
+    pub title: &'static str,
+    pub message: &'static str,
+    /// 确认按钮上的文字
+    pub confirm: &'static str,
+    pub action: fn(Commands),
+}
+
+#[derive(Event)]
+pub struct CloseConfirm;
+
+#[derive(Component)]
+pub struct ConfirmContainer;
+
+fn on_confirm(
+    trigger: Trigger<Confirm>,
+    mut stack: ResMut<DialogStack>,
+    mut commands: Commands,
+    q_dialog: Single<(Entity, &mut Visibility), With<DialogContainer>>,
+    font_assets: Res<FontAssets>,
+) {
+    if !stack.open(Dialog::Confirm) {
+        return;
+    }
+    let (entity, mut visibility) = q_dialog.into_inner();
+    *visibility = Visibility::Visible;
+    let confirm = *trigger.event();
+    commands.entity(entity).with_children(|builder| {
+        spawn_confirm(&font_assets, builder, confirm);
+    });
+}
+
+fn on_close_confirm(
+    _trigger: Trigger<CloseConfirm>,
+    mut stack: ResMut<DialogStack>,
+    mut commands: Commands,
+    q_confirm: Query<Entity, With<ConfirmContainer>>,
+) {
+    if stack.close(Dialog::Confirm) {
+        for confirm in q_confirm.iter() {
+            commands
+                .entity(confirm)
+                .insert(FadeOut(Timer::from_seconds(0.2, TimerMode::Once)));
+        }
+    }
+}
+
+fn spawn_confirm(
+    font_assets: &Res<FontAssets>,
+    builder: &mut ChildSpawnerCommands<'_>,
+    confirm: Confirm,
+) {
+    builder
+        .spawn((
+            Name::new("confirm-container"),
+            ConfirmContainer,
+            Dialog::Confirm,
+            dialog_child_body(confirm.title),
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                Localized::new(confirm.title),
+                TextFont {
+                    font_size: 28.0,
+                    font: font_assets.karnak.clone(),
+                    ..default()
+                },
+                ThemedText(ThemeRole::Text),
+            ));
+
+            builder.spawn((
+                Node {
+                    margin: UiRect::top(Val::Px(18.0)),
+                    ..default()
+                },
+                Localized::new(confirm.message),
+                ThemedText(ThemeRole::Text),
+                TextFont {
+                    font: font_assets.franklin_600.clone(),
+                    font_size: 16.0,
+                    ..default()
+                },
+            ));
+
+            builder
+                .spawn(Node {
+                    display: Display::Flex,
+                    column_gap: Val::Px(12.0),
+                    margin: UiRect::top(Val::Px(30.0)),
+                    ..default()
+                })
+                .with_children(|builder| {
+                    dialog_button(font_assets, builder, "Cancel", |mut commands| {
+                        commands.trigger(CloseConfirm);
+                    });
+                    dialog_button(
+                        font_assets,
+                        builder,
+                        confirm.confirm,
+                        move |mut commands| {
+                            commands.trigger(CloseConfirm);
+                            (confirm.action)(commands);
+                        },
+                    );
+                });
+        });
+}
+
+/// 弹窗底部的圆角按钮
+fn dialog_button(
+    font_assets: &Res<FontAssets>,
+    builder: &mut ChildSpawnerCommands<'_>,
+    text: &'static str,
+    on_click: impl Fn(Commands) + Send + Sync + 'static,
+) {
+    builder
+        .spawn((
+            Button,
+            Node {
+                padding: UiRect::horizontal(Val::Px(38.0)),
+                min_height: Val::Px(48.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BorderRadius::all(Val::Px(40.0)),
+            ThemedBackground(ThemeRole::Button),
+            TabIndex(0),
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                Localized::new(text),
+                TextFont {
+                    font_size: 14.0,
+                    font: font_assets.franklin_500.clone(),
+                    ..default()
+                },
+                ThemedText(ThemeRole::ButtonText),
+            ));
+        })
+        .observe(
+            move |_trigger: Trigger<Pointer<Click>>, commands: Commands| {
+                on_click(commands);
+            },
+        );
+}
+
+/// 祝贺弹窗里的得分明细和当前难度的排行榜
+fn spawn_score_breakdown(
+    font_assets: &Res<FontAssets>,
+    builder: &mut ChildSpawnerCommands<'_>,
+    score: &LastScore,
+    leaderboard: &Leaderboard,
+    locale: &Locale,
+) {
+    let row = |builder: &mut ChildSpawnerCommands<'_>, left: String, right: String, bold: bool| {
+        builder
+            .spawn(Node {
+                display: Display::Flex,
+                width: Val::Px(320.0),
+                justify_content: JustifyContent::SpaceBetween,
+                ..default()
+            })
+            .with_children(|builder| {
+                for text in [left, right] {
+                    builder.spawn((
+                        Text::new(text),
+                        LocalizedFont::default(),
+                        TextFont {
+                            font: if bold {
+                                font_assets.franklin_600.clone()
+                            } else {
+                                font_assets.franklin_500.clone()
+                            },
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        ThemedText(ThemeRole::Text),
+                    ));
+                }
+            });
+    };
+
+    builder
+        .spawn((
+            Name::new("score-breakdown"),
+            Node {
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                margin: UiRect::top(Val::Px(18.0)),
+                row_gap: Val::Px(2.0),
+                ..default()
+            },
+        ))
+        .with_children(|builder| {
+            for (label, points) in score.breakdown.items.iter() {
+                row(builder, label.clone(), format!("{:+}", points), false);
+            }
+            row(
+                builder,
+                locale.tr("Score").to_string(),
+                score.breakdown.total().to_string(),
+                true,
+            );
+
+            builder.spawn((
+                Text::new(locale.format("{} leaderboard", &[&locale.tr(score.difficulty.label())])),
+                LocalizedFont::default(),
+                TextFont {
+                    font: font_assets.franklin_600.clone(),
+                    font_size: 16.0,
+                    ..default()
+                },
+                ThemedText(ThemeRole::Text),
+                Node {
+                    margin: UiRect::top(Val::Px(14.0)),
+                    ..default()
+                },
+            ));
+            for (rank, entry) in leaderboard.entries(score.difficulty).iter().enumerate() {
+                let seconds = entry.time.as_secs();
+                row(
+                    builder,
+                    format!(
+                        "{}. {:02}:{:02}{}",
+                        rank + 1,
+                        seconds / 60,
+                        seconds % 60,
+                        if score.rank == Some(rank) {
+                            "  (new)"
+                        } else {
+                            ""
+                        }
+                    ),
+                    entry.score.to_string(),
+                    score.rank == Some(rank),
+                );
+            }
+        });
+}
+
+#[test]
+fn test_dialog_stack() {
+    let mut stack = DialogStack::default();
+    assert!(stack.open(Dialog::Settings));
+    assert!(stack.open(Dialog::Confirm));
+    assert!(!stack.open(Dialog::Settings));
+    assert_eq!(stack.top(), Some(Dialog::Confirm));
+    assert!(stack.close(Dialog::Confirm));
+    assert!(!stack.close(Dialog::Confirm));
+    assert_eq!(stack.top(), Some(Dialog::Settings));
+}
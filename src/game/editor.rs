@@ -0,0 +1,490 @@
+use crate::{
+    GameState,
+    game::{
+        Game,
+        board::play_board,
+        cell_state::{
+            AutoCandidates, CellMode, CellValueBundle, ConflictCell, CornerCandidates,
+            CorrectionCell, DigitValueCell, FixedCell, ManualCandidates, RevealedCell,
+            SelectedCell,
+        },
+        cleanup_game,
+        generator::is_minimal,
+        guided_solve::panel_button,
+        input::keyboard_move_cell,
+        layout::BoardBody,
+        left_bar,
+        position::CellPosition,
+        scoring::{Difficulty, rate_difficulty},
+        sharing::PendingShare,
+    },
+    i18n::Localized,
+    loading::{FontAssets, TextureAssets},
+    share::{SharedPuzzle, ShowToast, copy_to_clipboard, encode_share_code, share_link, title_bar},
+    theme::{ThemeRole, ThemedBackground, ThemedBorder, ThemedText},
+};
+use bevy::prelude::*;
+use sudoku::{Sudoku, bitset::Set, board::Digit, strategy::StrategySolver};
+
+/// 出题器: 在空棋盘上填提示数, 随时显示解的个数、难度和是否最简
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<PuzzleAnalysis>()
+        .init_resource::<EditorCollection>()
+        .add_systems(OnEnter(GameState::Editor), setup_editor)
+        .add_systems(OnExit(GameState::Editor), cleanup_game)
+        .add_systems(
+            Update,
+            (
+                editor_keyboard_input,
+                keyboard_move_cell,
+                analyse_puzzle.run_if(any_match_filter::<Changed<DigitValueCell>>),
+                update_analysis_text.run_if(
+                    resource_changed::<PuzzleAnalysis>.or(any_match_filter::<Added<EditorInfo>>),
+                ),
+            )
+                .chain()
+                .run_if(in_state(GameState::Editor)),
+        )
+        .add_observer(on_edit_given)
+        .add_observer(on_editor_action);
+}
+
+/// 给选中的格子填上提示数, `None` 表示擦掉
+#[derive(Event, Debug, Clone, Copy)]
+pub struct EditGiven(pub Option<u8>);
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorAction {
+    Clear,
+    Open,
+    Save,
+    Export,
+    StartSolving,
+}
+
+/// 当前盘面的分析结果
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct PuzzleAnalysis {
+    pub clues: usize,
+    /// 最多数到 2, 表示多解
+    pub solutions: usize,
+    /// 只有唯一解时才评级
+    pub difficulty: Option<Difficulty>,
+    pub minimal: bool,
+}
+
+impl PuzzleAnalysis {
+    pub fn new(sudoku: Sudoku) -> Self {
+        let solutions = sudoku.solutions_count_up_to(2);
+        let unique = solutions == 1;
+        Self {
+            clues: sudoku.n_clues() as usize,
+            solutions,
+            difficulty: unique.then(|| rate_difficulty(StrategySolver::from_sudoku(sudoku))),
+            minimal: unique && is_minimal(sudoku),
+        }
+    }
+
+    fn solutions_label(&self) -> &'static str {
+        match self.solutions {
+            0 => "0",
+            1 => "1",
+            _ => "Many",
+        }
+    }
+}
+
+/// 下一次 "Open saved" 读取收藏里的第几道题
+#[derive(Resource, Debug, Default)]
+struct EditorCollection {
+    next: usize,
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+enum EditorInfo {
+    Clues,
+    Solutions,
+    Difficulty,
+    Minimal,
+}
+
+const DIGIT_LABELS: [&str; 9] = ["1", "2", "3", "4", "5", "6", "7", "8", "9"];
+
+fn setup_editor(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    texture_assets: Res<TextureAssets>,
+) {
+    commands.spawn((Game, Camera2d));
+    commands
+        .spawn((
+            Game,
+            Name::new("editor-content"),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ThemedBackground(ThemeRole::Surface),
+        ))
+        .with_children(|builder| {
+            title_bar(&font_assets, builder);
+
+            builder
+                .spawn((
+                    Name::new("editor-toolbar"),
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Px(55.0),
+                        padding: UiRect::axes(Val::Px(24.0), Val::Px(0.0)),
+                        border: UiRect::vertical(Val::Px(1.0)),
+                        display: Display::Flex,
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(24.0),
+                        ..default()
+                    },
+                    ThemedBorder(ThemeRole::Border),
+                ))
+                .with_children(|builder| {
+                    left_bar(&font_assets, &texture_assets, builder);
+                    for info in [
+                        EditorInfo::Clues,
+                        EditorInfo::Solutions,
+                        EditorInfo::Difficulty,
+                        EditorInfo::Minimal,
+                    ] {
+                        builder.spawn((
+                            info,
+                            Localized::new(""),
+                            TextFont {
+                                font_size: 16.0,
+                                font: font_assets.franklin_500.clone(),
+                                ..default()
+                            },
+                            ThemedText(ThemeRole::Text),
+                        ));
+                    }
+                });
+
+            builder
+                .spawn((
+                    Node {
+                        display: Display::Flex,
+                        align_items: AlignItems::Stretch,
+                        justify_content: JustifyContent::Center,
+                        margin: UiRect::axes(Val::Auto, Val::Px(20.0)),
+                        column_gap: Val::Px(24.0),
+                        ..default()
+                    },
+                    BoardBody,
+                ))
+                .with_children(|builder| {
+                    play_board(&font_assets, &texture_assets, builder);
+                    editor_panel(&font_assets, builder);
+                });
+        });
+
+    commands.insert_resource(EditorCollection::default());
+    commands.trigger(EditorAction::Clear);
+}
+
+/// 右侧的数字键和操作按钮
+fn editor_panel(font_assets: &Res<FontAssets>, builder: &mut ChildSpawnerCommands<'_>) {
+    builder
+        .spawn((Node {
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(10.0),
+            ..default()
+        },))
+        .with_children(|builder| {
+            builder
+                .spawn((Node {
+                    display: Display::Grid,
+                    grid_template_columns: RepeatedGridTrack::flex(3, 1.0),
+                    row_gap: Val::Px(6.0),
+                    column_gap: Val::Px(6.0),
+                    ..default()
+                },))
+                .with_children(|builder| {
+                    for (index, label) in DIGIT_LABELS.into_iter().enumerate() {
+                        panel_button(
+                            font_assets,
+                            builder,
+                            label,
+                            EditGiven(Some(index as u8 + 1)),
+                            (),
+                        );
+                    }
+                });
+            panel_button(font_assets, builder, "Erase", EditGiven(None), ());
+            panel_button(font_assets, builder, "Clear", EditorAction::Clear, ());
+            panel_button(font_assets, builder, "Open saved", EditorAction::Open, ());
+            panel_button(font_assets, builder, "Save", EditorAction::Save, ());
+            panel_button(font_assets, builder, "Export", EditorAction::Export, ());
+            panel_button(
+                font_assets,
+                builder,
+                "Start solving",
+                EditorAction::StartSolving,
+                (),
+            );
+        });
+}
+
+fn editor_keyboard_input(keyboard_input: Res<ButtonInput<KeyCode>>, mut commands: Commands) {
+    let digit_keys = [
+        (KeyCode::Digit1, KeyCode::Numpad1),
+        (KeyCode::Digit2, KeyCode::Numpad2),
+        (KeyCode::Digit3, KeyCode::Numpad3),
+        (KeyCode::Digit4, KeyCode::Numpad4),
+        (KeyCode::Digit5, KeyCode::Numpad5),
+        (KeyCode::Digit6, KeyCode::Numpad6),
+        (KeyCode::Digit7, KeyCode::Numpad7),
+        (KeyCode::Digit8, KeyCode::Numpad8),
+        (KeyCode::Digit9, KeyCode::Numpad9),
+    ];
+    for (index, (key, numpad)) in digit_keys.into_iter().enumerate() {
+        if keyboard_input.any_just_pressed([key, numpad]) {
+            commands.trigger(EditGiven(Some(index as u8 + 1)));
+            return;
+        }
+    }
+    if keyboard_input.any_just_pressed([
+        KeyCode::Backspace,
+        KeyCode::Delete,
+        KeyCode::Digit0,
+        KeyCode::Numpad0,
+    ]) {
+        commands.trigger(EditGiven(None));
+    }
+}
+
+/// 出题时格子只有数字, 没有候选数
+fn given_bundle(digit: u8) -> CellValueBundle {
+    CellValueBundle {
+        digit_value: DigitValueCell((digit != 0).then(|| Digit::new(digit))),
+        auto_candidates: AutoCandidates(Set::NONE),
+        manual_candidates: ManualCandidates(Set::NONE),
+        corner_candidates: CornerCandidates(Set::NONE),
+        cell_mode: if digit != 0 {
+            CellMode::Digit
+        } else {
+            CellMode::ManualCandidates
+        },
+    }
+}
+
+fn on_edit_given(
+    trigger: Trigger<EditGiven>,
+    q_selected: Query<Entity, With<SelectedCell>>,
+    mut commands: Commands,
+) {
+    for entity in q_selected.iter() {
+        commands
+            .entity(entity)
+            .insert(given_bundle(trigger.event().0.unwrap_or(0)));
+    }
+}
+
+/// 把盘面读成题目
+fn editor_puzzle<'a>(
+    cells: impl IntoIterator<Item = (&'a CellPosition, &'a DigitValueCell)>,
+) -> Sudoku {
+    let mut bytes = [0u8; 81];
+    for (position, digit_value) in cells {
+        bytes[position.0 as usize] = digit_value.0.map_or(0, |digit| digit.get());
+    }
+    Sudoku::from_bytes(bytes).expect("cell digits are always 0-9")
+}
+
+/// 把题目放到出题棋盘上, 选中第一个格子
+fn load_editor_puzzle(
+    commands: &mut Commands,
+    q_cell: &Query<(Entity, &CellPosition, &DigitValueCell)>,
+    q_unset: &Query<(Entity, &CellPosition), Without<DigitValueCell>>,
+    bytes: [u8; 81],
+) {
+    let cells = q_cell
+        .iter()
+        .map(|(entity, position, _)| (entity, position))
+        .chain(q_unset.iter());
+    for (entity, position) in cells {
+        let mut cell = commands.entity(entity);
+        cell.insert(given_bundle(bytes[position.0 as usize]))
+            .remove::<(
+                SelectedCell,
+                FixedCell,
+                ConflictCell,
+                CorrectionCell,
+                RevealedCell,
+            )>();
+        if position.0 == 0 {
+            cell.insert(SelectedCell);
+        }
+    }
+}
+
+fn analyse_puzzle(
+    q_cell: Query<(&CellPosition, &DigitValueCell)>,
+    mut analysis: ResMut<PuzzleAnalysis>,
+) {
+    analysis.set_if_neq(PuzzleAnalysis::new(editor_puzzle(q_cell.iter())));
+}
+
+fn update_analysis_text(
+    analysis: Res<PuzzleAnalysis>,
+    mut q_info: Query<(&EditorInfo, &mut Localized)>,
+) {
+    for (info, mut text) in q_info.iter_mut() {
+        *text = match info {
+            EditorInfo::Clues => Localized::with_args("Givens: {}", [analysis.clues]),
+            EditorInfo::Solutions => {
+                Localized::with_args("Solutions: {}", [analysis.solutions_label()])
+            }
+            EditorInfo::Difficulty => Localized::with_args(
+                "Rating: {}",
+                [analysis
+                    .difficulty
+                    .map_or("-", |difficulty| difficulty.label())],
+            ),
+            EditorInfo::Minimal => {
+                Localized::with_args("Minimal: {}", [if analysis.minimal { "On" } else { "Off" }])
+            }
+        };
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn on_editor_action(
+    trigger: Trigger<EditorAction>,
+    q_cell: Query<(Entity, &CellPosition, &DigitValueCell)>,
+    q_unset: Query<(Entity, &CellPosition), Without<DigitValueCell>>,
+    analysis: Res<PuzzleAnalysis>,
+    mut collection: ResMut<EditorCollection>,
+    mut pending: ResMut<PendingShare>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    let action = *trigger.event();
+    let puzzle = editor_puzzle(q_cell.iter().map(|(_, position, digit)| (position, digit)));
+    let unique = analysis.solutions == 1;
+    let message = match action {
+        EditorAction::Clear => {
+            load_editor_puzzle(&mut commands, &q_cell, &q_unset, [0; 81]);
+            return;
+        }
+        EditorAction::Open => {
+            let saved = load_collection();
+            if saved.is_empty() {
+                "No saved puzzles"
+            } else {
+                let index = collection.next % saved.len();
+                collection.next = index + 1;
+                load_editor_puzzle(&mut commands, &q_cell, &q_unset, saved[index].to_bytes());
+                return;
+            }
+        }
+        _ if !unique => "The puzzle needs exactly one solution",
+        EditorAction::Save => match save_to_collection(puzzle) {
+            Ok(true) => "Saved to collection",
+            Ok(false) => "Already in collection",
+            Err(err) => {
+                warn!("failed to save puzzle: {}", err);
+                "Could not save puzzle"
+            }
+        },
+        EditorAction::Export => {
+            let link = share_link(&encode_share_code(&puzzle, None, None));
+            if !copy_to_clipboard(&link) {
+                commands.trigger(ShowToast(link));
+                return;
+            }
+            "Share link copied"
+        }
+        EditorAction::StartSolving => {
+            // 和打开分享链接一样开始这道题, 填好的数字都成为固定格子
+            pending.0 = Some(SharedPuzzle {
+                puzzle,
+                progress: None,
+                marks: None,
+            });
+            next_state.set(GameState::Playing);
+            return;
+        }
+    };
+    commands.trigger(ShowToast(message.to_string()));
+}
+
+/// 收藏的题目, 桌面端每行一道题存在 `puzzles/collection.txt`
+#[cfg(not(target_arch = "wasm32"))]
+const COLLECTION_PATH: &str = "puzzles/collection.txt";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_collection() -> Vec<Sudoku> {
+    std::fs::read_to_string(COLLECTION_PATH)
+        .map(|text| parse_collection(&text))
+        .unwrap_or_default()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_collection() -> Vec<Sudoku> {
+    vec![]
+}
+
+/// 已经收藏过时返回 `Ok(false)`
+#[cfg(not(target_arch = "wasm32"))]
+fn save_to_collection(puzzle: Sudoku) -> std::io::Result<bool> {
+    use std::io::Write;
+
+    if load_collection().contains(&puzzle) {
+        return Ok(false);
+    }
+    std::fs::create_dir_all("puzzles")?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(COLLECTION_PATH)?;
+    writeln!(file, "{}", puzzle.to_str_line())?;
+    Ok(true)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn save_to_collection(_puzzle: Sudoku) -> std::io::Result<bool> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+fn parse_collection(text: &str) -> Vec<Sudoku> {
+    text.lines()
+        .filter_map(|line| Sudoku::from_str_line(line.trim()).ok())
+        .collect()
+}
+
+#[test]
+fn test_puzzle_analysis() {
+    let puzzle = Sudoku::from_str_line(
+        "1.....569492.561.8.561.924...964.8.1.64.1....218.356.4.4.5...169.5.614.2621.....5",
+    )
+    .unwrap();
+    let analysis = PuzzleAnalysis::new(puzzle);
+    assert_eq!(analysis.clues, puzzle.n_clues() as usize);
+    assert_eq!(analysis.solutions, 1);
+    assert!(analysis.difficulty.is_some());
+
+    let empty = PuzzleAnalysis::new(Sudoku::from_bytes([0; 81]).unwrap());
+    assert_eq!(empty.solutions, 2);
+    assert_eq!(empty.difficulty, None);
+    assert!(!empty.minimal);
+
+    let mut bytes = puzzle.to_bytes();
+    bytes[1] = bytes[0];
+    assert_eq!(
+        PuzzleAnalysis::new(Sudoku::from_bytes(bytes).unwrap()).solutions,
+        0
+    );
+
+    let text = format!("{}\n\nnot a puzzle\n", puzzle.to_str_line());
+    assert_eq!(parse_collection(&text), vec![puzzle]);
+}
@@ -0,0 +1,571 @@
+use crate::{
+    GameState,
+    game::{
+        InitPuzzle, Settings,
+        guided_solve::{STRATEGIES, strategy_name},
+        scoring::{Difficulty, rate_difficulty},
+    },
+    i18n::Localized,
+    loading::FontAssets,
+    share::ShowToast,
+    theme::{ThemeRole, ThemedBackground, ThemedText},
+};
+use bevy::{
+    platform::collections::HashMap,
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task, block_on, futures_lite::future},
+};
+use std::collections::VecDeque;
+use sudoku::{Sudoku, Symmetry, strategy::StrategySolver};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<PuzzlePool>()
+        .init_resource::<CustomPuzzle>()
+        .init_resource::<PracticeTechnique>()
+        .add_systems(Update, (receive_pool_puzzle, refill_pool).chain())
+        .add_systems(
+            Update,
+            (
+                finish_generation,
+                show_generating_cover,
+                update_technique_text.run_if(resource_changed::<PracticeTechnique>),
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(OnExit(GameState::Playing), cancel_generation)
+        .add_observer(
+            |_: Trigger<InitPuzzle>, mut technique: ResMut<PracticeTechnique>| {
+                technique.0 = None;
+            },
+        );
+}
+
+/// 每种难度预先准备好的题目数量
+const POOL_SIZE: usize = 2;
+
+const DIFFICULTIES: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+/// 设置里的难度选项, `None` 表示不限难度
+pub fn next_difficulty(current: Option<Difficulty>) -> Option<Difficulty> {
+    match current {
+        None => Some(Difficulty::Easy),
+        Some(Difficulty::Easy) => Some(Difficulty::Medium),
+        Some(Difficulty::Medium) => Some(Difficulty::Hard),
+        Some(Difficulty::Hard) => None,
+    }
+}
+
+/// 题目和答案
+pub type Puzzle = (Sudoku, Sudoku);
+
+/// 生成一道有唯一解的题目并评定难度
+fn generate_rated() -> (Difficulty, Puzzle) {
+    loop {
+        let sudoku = Sudoku::generate();
+        if let Some(solution) = sudoku.solution() {
+            let difficulty = rate_difficulty(StrategySolver::from_sudoku(sudoku));
+            return (difficulty, (sudoku, solution));
+        }
+    }
+}
+
+/// 后台准备好的题目, 按难度分开存放
+#[derive(Resource, Default)]
+pub struct PuzzlePool {
+    ready: HashMap<Difficulty, VecDeque<Puzzle>>,
+    task: Option<Task<(Difficulty, Puzzle)>>,
+}
+
+impl PuzzlePool {
+    /// 取出一道指定难度的题目, `None` 时取任意难度
+    pub fn take(&mut self, difficulty: Option<Difficulty>) -> Option<Puzzle> {
+        match difficulty {
+            Some(difficulty) => self.ready.get_mut(&difficulty)?.pop_front(),
+            None => DIFFICULTIES
+                .iter()
+                .find_map(|difficulty| self.ready.get_mut(difficulty)?.pop_front()),
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        DIFFICULTIES.iter().all(|difficulty| {
+            self.ready
+                .get(difficulty)
+                .is_some_and(|puzzles| puzzles.len() >= POOL_SIZE)
+        })
+    }
+
+    /// 放入一道题目, 这个难度已经够了就丢掉
+    fn push(&mut self, difficulty: Difficulty, puzzle: Puzzle) {
+        let puzzles = self.ready.entry(difficulty).or_default();
+        if puzzles.len() < POOL_SIZE {
+            puzzles.push_back(puzzle);
+        }
+    }
+}
+
+/// 题目池不满时, 一次在后台生成一道题目
+fn refill_pool(mut pool: ResMut<PuzzlePool>) {
+    if pool.task.is_some() || pool.is_full() {
+        return;
+    }
+    pool.task = Some(AsyncComputeTaskPool::get().spawn(async { generate_rated() }));
+}
+
+fn receive_pool_puzzle(mut pool: ResMut<PuzzlePool>) {
+    let Some(task) = pool.task.as_mut() else {
+        return;
+    };
+    if let Some((difficulty, puzzle)) = block_on(future::poll_once(task)) {
+        pool.task = None;
+        pool.push(difficulty, puzzle);
+    }
+}
+
+/// 自定义题目的对称方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PuzzleSymmetry {
+    None,
+    /// 180° 旋转对称
+    #[default]
+    HalfRotation,
+    /// 左右镜像
+    Mirror,
+    /// 沿主对角线对称
+    Diagonal,
+}
+
+impl PuzzleSymmetry {
+    pub fn next(self) -> Self {
+        match self {
+            PuzzleSymmetry::None => PuzzleSymmetry::HalfRotation,
+            PuzzleSymmetry::HalfRotation => PuzzleSymmetry::Mirror,
+            PuzzleSymmetry::Mirror => PuzzleSymmetry::Diagonal,
+            PuzzleSymmetry::Diagonal => PuzzleSymmetry::None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PuzzleSymmetry::None => "None",
+            PuzzleSymmetry::HalfRotation => "Rotational",
+            PuzzleSymmetry::Mirror => "Mirror",
+            PuzzleSymmetry::Diagonal => "Diagonal",
+        }
+    }
+
+    fn symmetry(self) -> Symmetry {
+        match self {
+            PuzzleSymmetry::None => Symmetry::None,
+            PuzzleSymmetry::HalfRotation => Symmetry::HalfRotation,
+            PuzzleSymmetry::Mirror => Symmetry::VerticalMirror,
+            PuzzleSymmetry::Diagonal => Symmetry::DiagonalMirror,
+        }
+    }
+
+    /// 必须同时有或同时没有数字的格子
+    fn cells(self, cell: usize) -> [usize; 2] {
+        let (row, col) = (cell / 9, cell % 9);
+        match self {
+            PuzzleSymmetry::None => [cell, cell],
+            PuzzleSymmetry::HalfRotation => [cell, 80 - cell],
+            PuzzleSymmetry::Mirror => [cell, row * 9 + 8 - col],
+            PuzzleSymmetry::Diagonal => [cell, col * 9 + row],
+        }
+    }
+}
+
+/// 可选的提示数范围, `None` 表示不限
+const CLUE_RANGES: [Option<(u8, u8)>; 5] = [
+    None,
+    Some((22, 26)),
+    Some((27, 31)),
+    Some((32, 36)),
+    Some((37, 45)),
+];
+
+pub fn next_clue_range(current: Option<(u8, u8)>) -> Option<(u8, u8)> {
+    let index = CLUE_RANGES
+        .iter()
+        .position(|range| *range == current)
+        .unwrap_or(0);
+    CLUE_RANGES[(index + 1) % CLUE_RANGES.len()]
+}
+
+/// 自定义题目的生成条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeneratorOptions {
+    pub symmetry: PuzzleSymmetry,
+    /// 提示数范围, 包含两端
+    pub clue_range: Option<(u8, u8)>,
+    /// 去掉任何一个提示数都会失去唯一解
+    pub minimal: bool,
+    /// 要练习的技巧在 `STRATEGIES` 里的位置, 题目必须用到它才能解出
+    pub technique: Option<usize>,
+    /// 练习时其余步骤只能用 `STRATEGIES` 里不难于这一项的技巧
+    pub hardest_other: usize,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            symmetry: PuzzleSymmetry::default(),
+            clue_range: None,
+            minimal: false,
+            technique: None,
+            // 到数对为止
+            hardest_other: 3,
+        }
+    }
+}
+
+impl GeneratorOptions {
+    /// 练习技巧时合适的题目少得多, 需要多试几次
+    fn max_attempts(&self) -> usize {
+        if self.technique.is_some() {
+            MAX_CUSTOM_ATTEMPTS * 25
+        } else {
+            MAX_CUSTOM_ATTEMPTS
+        }
+    }
+}
+
+/// 练习的技巧: 下一个技巧, 从隐性唯一数开始, 最后回到不练习
+pub fn next_technique(current: Option<usize>) -> Option<usize> {
+    match current {
+        None => Some(1),
+        Some(index) if index + 1 < STRATEGIES.len() => Some(index + 1),
+        Some(_) => None,
+    }
+}
+
+pub fn technique_name(index: usize) -> &'static str {
+    strategy_name(STRATEGIES[index].clone())
+}
+
+pub fn next_hardest_other(current: usize) -> usize {
+    (current + 1) % STRATEGIES.len()
+}
+
+/// 只用允许的技巧解不出来, 加上要练习的技巧才能解出
+fn needs_technique(sudoku: Sudoku, technique: usize, hardest_other: usize) -> bool {
+    let others = STRATEGIES[..=hardest_other.min(STRATEGIES.len() - 1)]
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != technique)
+        .map(|(_, strategy)| strategy.clone())
+        .collect::<Vec<_>>();
+    let mut with_technique = others.clone();
+    with_technique.push(STRATEGIES[technique].clone());
+    StrategySolver::from_sudoku(sudoku).solve(&others).is_err()
+        && StrategySolver::from_sudoku(sudoku)
+            .solve(&with_technique)
+            .is_ok()
+}
+
+/// 菜单里的自定义题目设置, `active` 时新题目都按这些条件生成
+#[derive(Resource, Debug, Default)]
+pub struct CustomPuzzle {
+    pub options: GeneratorOptions,
+    pub active: bool,
+}
+
+/// 自定义条件太苛刻时最多尝试的次数
+const MAX_CUSTOM_ATTEMPTS: usize = 200;
+
+/// 去掉任何一个提示数都会出现多解
+pub(crate) fn is_minimal(sudoku: Sudoku) -> bool {
+    let bytes = sudoku.to_bytes();
+    (0..81).filter(|cell| bytes[*cell] != 0).all(|cell| {
+        let mut removed = bytes;
+        removed[cell] = 0;
+        Sudoku::from_bytes(removed).is_ok_and(|removed| !removed.is_uniquely_solvable())
+    })
+}
+
+/// 按自定义条件生成一次, 不满足条件时返回 `None`
+fn generate_custom(options: GeneratorOptions) -> Option<Puzzle> {
+    let solution = Sudoku::generate_solved();
+    let mut bytes =
+        Sudoku::generate_with_symmetry_from(solution, options.symmetry.symmetry()).to_bytes();
+    let clues = |bytes: &[u8; 81]| bytes.iter().filter(|digit| **digit != 0).count() as u8;
+
+    if let Some((min, max)) = options.clue_range {
+        // 提示数不够时按对称方式从答案里补回去, 借另一张随机终盘决定补的顺序
+        let noise = Sudoku::generate_solved().to_bytes();
+        let mut empty = (0..81).filter(|cell| bytes[*cell] == 0).collect::<Vec<_>>();
+        empty.sort_by_key(|cell| (noise[*cell], *cell));
+        let answer = solution.to_bytes();
+        for cell in empty {
+            if clues(&bytes) >= min {
+                break;
+            }
+            for cell in options.symmetry.cells(cell) {
+                bytes[cell] = answer[cell];
+            }
+        }
+        if !(min..=max).contains(&clues(&bytes)) {
+            return None;
+        }
+    }
+
+    let sudoku = Sudoku::from_bytes(bytes).ok()?;
+    if !sudoku.is_uniquely_solvable() || (options.minimal && !is_minimal(sudoku)) {
+        return None;
+    }
+    if let Some(technique) = options.technique
+        && !needs_technique(sudoku, technique, options.hardest_other)
+    {
+        return None;
+    }
+    Some((sudoku, solution))
+}
+
+/// 题目从哪里来
+#[derive(Debug, Clone, Copy)]
+enum PuzzleSource {
+    /// 题目池, 或者同样条件的后台任务
+    Pool(Option<Difficulty>),
+    /// 按自定义条件生成, 带着要练习的技巧
+    Custom(Option<usize>),
+}
+
+/// 题目池里没有合适的题目时, 正在后台生成的新题目
+#[derive(Resource)]
+pub struct GeneratingPuzzle {
+    source: PuzzleSource,
+    /// 自定义条件找不到题目时是 `None`
+    task: Task<Option<Puzzle>>,
+}
+
+/// 后台生成的题目准备好了, 由 `init_puzzle` 放到棋盘上
+#[derive(Event)]
+pub struct PuzzleGenerated(pub Puzzle);
+
+/// 从题目池取题, 没有的话开始在后台生成
+pub(crate) fn request_puzzle(
+    commands: &mut Commands,
+    pool: &mut PuzzlePool,
+    settings: &Settings,
+    custom: &CustomPuzzle,
+) -> Option<Puzzle> {
+    // 旧的任务在替换时被丢弃, 也就取消了
+    let (source, task) = if custom.active {
+        let options = custom.options;
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            for _ in 0..options.max_attempts() {
+                if let Some(puzzle) = generate_custom(options) {
+                    return Some(puzzle);
+                }
+                // 让出执行权, 任务被丢弃时可以在两次尝试之间停下
+                future::yield_now().await;
+            }
+            None
+        });
+        (PuzzleSource::Custom(options.technique), task)
+    } else {
+        let difficulty = settings.puzzle_difficulty;
+        if let Some(puzzle) = pool.take(difficulty) {
+            commands.remove_resource::<GeneratingPuzzle>();
+            return Some(puzzle);
+        }
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            loop {
+                let (rated, puzzle) = generate_rated();
+                if difficulty.is_none_or(|difficulty| difficulty == rated) {
+                    return Some(puzzle);
+                }
+                future::yield_now().await;
+            }
+        });
+        (PuzzleSource::Pool(difficulty), task)
+    };
+    commands.insert_resource(GeneratingPuzzle { source, task });
+    None
+}
+
+fn finish_generation(
+    mut generating: Option<ResMut<GeneratingPuzzle>>,
+    mut pool: ResMut<PuzzlePool>,
+    settings: Res<Settings>,
+    mut custom: ResMut<CustomPuzzle>,
+    mut commands: Commands,
+) {
+    let Some(generating) = generating.as_mut() else {
+        return;
+    };
+    // 题目池可能先准备好了合适的题目
+    let from_pool = match generating.source {
+        PuzzleSource::Pool(difficulty) => pool.take(difficulty),
+        PuzzleSource::Custom(_) => None,
+    };
+    let result = match from_pool {
+        Some(puzzle) => Some(Some(puzzle)),
+        None => block_on(future::poll_once(&mut generating.task)),
+    };
+    match result {
+        Some(Some(puzzle)) => {
+            if let PuzzleSource::Custom(technique) = generating.source {
+                commands.insert_resource(PracticeTechnique(technique));
+            }
+            commands.remove_resource::<GeneratingPuzzle>();
+            commands.trigger(PuzzleGenerated(puzzle));
+        }
+        // 找不到符合条件的题目, 改用普通题目
+        Some(None) => {
+            custom.active = false;
+            commands.trigger(ShowToast(
+                "No puzzle matched the custom settings".to_string(),
+            ));
+            if let Some(puzzle) = request_puzzle(&mut commands, &mut pool, &settings, &custom) {
+                commands.remove_resource::<GeneratingPuzzle>();
+                commands.trigger(PuzzleGenerated(puzzle));
+            }
+        }
+        None => {}
+    }
+}
+
+/// 回到菜单时放弃还没生成完的题目
+fn cancel_generation(mut commands: Commands) {
+    commands.remove_resource::<GeneratingPuzzle>();
+}
+
+/// 生成题目时盖住棋盘的提示
+#[derive(Component)]
+struct GeneratingCover;
+
+pub(crate) fn generating_cover(
+    font_assets: &Res<FontAssets>,
+    builder: &mut ChildSpawnerCommands<'_>,
+) {
+    builder.spawn((
+        GeneratingCover,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        ThemedBackground(ThemeRole::Surface),
+        ZIndex(11),
+        Visibility::Hidden,
+        children![(
+            Localized::new("Generating puzzle..."),
+            TextFont {
+                font: font_assets.franklin_500.clone(),
+                font_size: 24.0,
+                ..default()
+            },
+            ThemedText(ThemeRole::SecondaryText),
+        )],
+    ));
+}
+
+fn show_generating_cover(
+    generating: Option<Res<GeneratingPuzzle>>,
+    mut q_cover: Query<&mut Visibility, With<GeneratingCover>>,
+) {
+    for mut visibility in q_cover.iter_mut() {
+        visibility.set_if_neq(if generating.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
+
+/// 当前题目要练习的技巧
+#[derive(Resource, Debug, Default)]
+pub struct PracticeTechnique(pub Option<usize>);
+
+#[derive(Component)]
+struct TechniqueText;
+
+/// 工具栏上难度旁边的练习技巧
+pub(crate) fn technique_label(
+    font_assets: &Res<FontAssets>,
+    builder: &mut ChildSpawnerCommands<'_>,
+) {
+    builder.spawn((
+        TechniqueText,
+        Localized::default(),
+        TextFont {
+            font_size: 18.0,
+            font: font_assets.franklin_500.clone(),
+            ..default()
+        },
+        ThemedText(ThemeRole::SecondaryText),
+        Node {
+            display: Display::None,
+            margin: UiRect::right(Val::Px(16.0)),
+            ..default()
+        },
+    ));
+}
+
+fn update_technique_text(
+    technique: Res<PracticeTechnique>,
+    mut q_text: Query<(&mut Localized, &mut Node), With<TechniqueText>>,
+) {
+    for (mut text, mut node) in q_text.iter_mut() {
+        match technique.0 {
+            Some(index) => {
+                *text = Localized::with_args("Practice: {}", [technique_name(index)]);
+                node.display = Display::Flex;
+            }
+            None => node.display = Display::None,
+        }
+    }
+}
+
+#[test]
+fn test_needs_technique() {
+    // 需要 X 翼的题目
+    let sudoku = Sudoku::from_str_line(
+        "1.....569492.561.8.561.924...964.8.1.64.1....218.356.4.4.5...169.5.614.2621.....5",
+    )
+    .unwrap();
+    let x_wing = STRATEGIES
+        .iter()
+        .position(|strategy| matches!(strategy, sudoku::strategy::Strategy::XWing))
+        .unwrap();
+    assert!(needs_technique(sudoku, x_wing, 3));
+    assert!(!needs_technique(sudoku, 2, 3));
+}
+
+#[test]
+fn test_generate_custom() {
+    let options = GeneratorOptions {
+        symmetry: PuzzleSymmetry::Mirror,
+        clue_range: Some((32, 36)),
+        ..default()
+    };
+    let (sudoku, _) = (0..MAX_CUSTOM_ATTEMPTS)
+        .find_map(|_| generate_custom(options))
+        .unwrap();
+    assert!(sudoku.is_uniquely_solvable());
+    assert!((32..=36).contains(&sudoku.n_clues()));
+    let bytes = sudoku.to_bytes();
+    for cell in 0..81 {
+        let [a, b] = PuzzleSymmetry::Mirror.cells(cell);
+        assert_eq!(bytes[a] == 0, bytes[b] == 0);
+    }
+}
+
+#[test]
+fn test_puzzle_pool() {
+    let puzzle = generate_rated().1;
+    let mut pool = PuzzlePool::default();
+    for _ in 0..POOL_SIZE + 1 {
+        pool.push(Difficulty::Medium, puzzle);
+    }
+    assert_eq!(pool.ready[&Difficulty::Medium].len(), POOL_SIZE);
+    assert!(pool.take(Some(Difficulty::Hard)).is_none());
+    assert!(pool.take(None).is_some());
+    assert!(pool.take(Some(Difficulty::Medium)).is_some());
+    assert!(pool.take(None).is_none());
+}
@@ -0,0 +1,572 @@
+use crate::{
+    GameState,
+    game::{
+        AutoCandidateMode, InitPuzzle, ResetPuzzle, SudokuManager,
+        cell_state::{AutoCandidates, CellMode, DigitValueCell, FixedCell, RevealedCell},
+        input::PressFeedback,
+        position::CellPosition,
+        trial::{CellSnapshot, CellValues},
+    },
+    i18n::Localized,
+    loading::FontAssets,
+    theme::{ThemeRole, ThemedBackground, ThemedText},
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+use sudoku::{
+    Sudoku,
+    board::{Candidate, CellState, Digit},
+    strategy::{Deduction, Strategy, StrategySolver},
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (autoplay_guided_solve, update_guided_panel)
+            .run_if(in_state(GameState::Playing).and(resource_exists::<GuidedSolve>)),
+    )
+    .add_systems(OnExit(GameState::Playing), stop_guided_solve)
+    .add_observer(on_start_guided_solve)
+    .add_observer(on_guided_control)
+    // 换题或重来时盘面另有安排, 不再还原讲解前的格子
+    .add_observer(
+        |_: Trigger<InitPuzzle>,
+         mut commands: Commands,
+         q_panel: Query<Entity, With<GuidedPanel>>| {
+            end_guided_solve(&mut commands, &q_panel);
+        },
+    )
+    .add_observer(
+        |_: Trigger<ResetPuzzle>,
+         mut commands: Commands,
+         q_panel: Query<Entity, With<GuidedPanel>>| {
+            end_guided_solve(&mut commands, &q_panel);
+        },
+    );
+}
+
+/// 讲解模式使用的技巧, 按难度从低到高
+pub(crate) const STRATEGIES: &[Strategy] = &[
+    Strategy::NakedSingles,
+    Strategy::HiddenSingles,
+    Strategy::LockedCandidates,
+    Strategy::NakedPairs,
+    Strategy::XWing,
+    Strategy::HiddenPairs,
+    Strategy::NakedTriples,
+    Strategy::Swordfish,
+    Strategy::HiddenTriples,
+    Strategy::XyWing,
+    Strategy::XyzWing,
+    Strategy::NakedQuads,
+    Strategy::Jellyfish,
+    Strategy::HiddenQuads,
+];
+
+/// 每秒播放的步数
+const SPEEDS: [f32; 4] = [0.5, 1.0, 2.0, 4.0];
+
+pub fn strategy_name(strategy: Strategy) -> &'static str {
+    match strategy {
+        Strategy::NakedSingles => "Naked Single",
+        Strategy::HiddenSingles => "Hidden Single",
+        Strategy::LockedCandidates => "Locked Candidates",
+        Strategy::NakedPairs => "Naked Pair",
+        Strategy::NakedTriples => "Naked Triple",
+        Strategy::NakedQuads => "Naked Quad",
+        Strategy::HiddenPairs => "Hidden Pair",
+        Strategy::HiddenTriples => "Hidden Triple",
+        Strategy::HiddenQuads => "Hidden Quad",
+        Strategy::XWing => "X-Wing",
+        Strategy::Swordfish => "Swordfish",
+        Strategy::Jellyfish => "Jellyfish",
+        Strategy::XyWing => "XY-Wing",
+        Strategy::XyzWing => "XYZ-Wing",
+        Strategy::MutantSwordfish => "Mutant Swordfish",
+        Strategy::MutantJellyfish => "Mutant Jellyfish",
+        Strategy::AvoidableRectangles => "Avoidable Rectangle",
+        _ => "Unknown",
+    }
+}
+
+/// 求解器推出的一步
+#[derive(Debug, Clone)]
+pub struct SolveStep {
+    pub strategy: Strategy,
+    /// 填入的数字
+    pub placements: Vec<Candidate>,
+    /// 排除的候选数
+    pub eliminations: Vec<Candidate>,
+}
+
+impl SolveStep {
+    pub fn from_deduction(deduction: Deduction<&[Candidate]>) -> Self {
+        let strategy = deduction.strategy();
+        let (placements, eliminations) = match deduction {
+            Deduction::NakedSingles(candidate) | Deduction::HiddenSingles(candidate, _) => {
+                (vec![candidate], vec![])
+            }
+            Deduction::LockedCandidates { conflicts, .. }
+            | Deduction::Subsets { conflicts, .. }
+            | Deduction::BasicFish { conflicts, .. }
+            | Deduction::Fish { conflicts, .. }
+            | Deduction::Wing { conflicts, .. }
+            | Deduction::AvoidableRectangle { conflicts, .. } => (vec![], conflicts.to_vec()),
+            _ => (vec![], vec![]),
+        };
+        Self {
+            strategy,
+            placements,
+            eliminations,
+        }
+    }
+}
+
+/// 从当前盘面求解, 返回起始候选数和每一步的推导
+pub fn plan_guided_solve(sudoku: Sudoku) -> ([CellState; 81], Vec<SolveStep>) {
+    let solver = StrategySolver::from_sudoku(sudoku);
+    let grid_state = solver.grid_state();
+    let deductions = match solver.solve(STRATEGIES) {
+        Ok((_, deductions)) | Err((_, deductions)) => deductions,
+    };
+    let steps = deductions.iter().map(SolveStep::from_deduction).collect();
+    (grid_state, steps)
+}
+
+/// 讲解模式的播放状态, 只在讲解模式下存在
+#[derive(Resource)]
+pub struct GuidedSolve {
+    steps: Vec<SolveStep>,
+    cursor: usize,
+    playing: bool,
+    speed: usize,
+    timer: Timer,
+    /// 每一步实际移除的候选数, 后退时还原
+    history: Vec<Vec<(usize, Digit)>>,
+    /// 开始讲解前的格子和是否已揭晓, 退出时还原
+    saved: HashMap<Entity, (CellSnapshot, bool)>,
+    saved_auto_mode: bool,
+}
+
+impl GuidedSolve {
+    fn new(
+        steps: Vec<SolveStep>,
+        saved: HashMap<Entity, (CellSnapshot, bool)>,
+        saved_auto_mode: bool,
+    ) -> Self {
+        Self {
+            steps,
+            cursor: 0,
+            playing: false,
+            speed: 1,
+            timer: Timer::from_seconds(1.0 / SPEEDS[1], TimerMode::Repeating),
+            history: vec![],
+            saved,
+            saved_auto_mode,
+        }
+    }
+
+    fn description(&self) -> Localized {
+        if self.steps.is_empty() {
+            return Localized::new("No logical steps found");
+        }
+        match self.cursor.checked_sub(1).and_then(|i| self.steps.get(i)) {
+            Some(step) => Localized::with_args(
+                "Step {}/{}: {}",
+                [
+                    self.cursor.to_string(),
+                    self.steps.len().to_string(),
+                    strategy_name(step.strategy.clone()).to_string(),
+                ],
+            ),
+            None => Localized::with_args("{} steps, press Play or Next", [self.steps.len()]),
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct StartGuidedSolve;
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuidedControl {
+    Back,
+    TogglePlay,
+    Next,
+    Speed,
+    Exit,
+}
+
+#[derive(Component)]
+struct GuidedPanel;
+
+#[derive(Component)]
+struct GuidedStepText;
+
+#[derive(Component)]
+struct GuidedPlayText;
+
+#[derive(Component)]
+struct GuidedSpeedText;
+
+#[allow(clippy::too_many_arguments)]
+fn on_start_guided_solve(
+    _trigger: Trigger<StartGuidedSolve>,
+    q_cell: Query<(Entity, &CellPosition, CellValues, Has<RevealedCell>), Without<FixedCell>>,
+    q_fixed: Query<(&CellPosition, &DigitValueCell), With<FixedCell>>,
+    q_panel: Query<Entity, With<GuidedPanel>>,
+    sudoku_manager: Res<SudokuManager>,
+    mut auto_mode: ResMut<AutoCandidateMode>,
+    font_assets: Res<FontAssets>,
+    mut commands: Commands,
+) {
+    // 错误的数字不参与求解
+    let solution = sudoku_manager.solution.to_bytes();
+    let mut bytes = [0u8; 81];
+    for (position, digit) in q_fixed.iter() {
+        bytes[position.0 as usize] = digit.0.map_or(0, |d| d.get());
+    }
+    for (_, position, (digit, ..), _) in q_cell.iter() {
+        if let Some(digit) = digit.0
+            && solution[position.0 as usize] == digit.get()
+        {
+            bytes[position.0 as usize] = digit.get();
+        }
+    }
+    let Ok(sudoku) = Sudoku::from_bytes(bytes) else {
+        return;
+    };
+    let (grid_state, steps) = plan_guided_solve(sudoku);
+
+    let saved = q_cell
+        .iter()
+        .map(|(entity, _, values, revealed)| (entity, (CellSnapshot::new(values), revealed)))
+        .collect();
+    let saved_auto_mode = auto_mode.0;
+    auto_mode.0 = true;
+    for (entity, position, ..) in q_cell.iter() {
+        if let CellState::Candidates(candidates) = grid_state[position.0 as usize] {
+            commands
+                .entity(entity)
+                .insert((
+                    DigitValueCell(None),
+                    AutoCandidates(candidates),
+                    CellMode::AutoCandidates,
+                ))
+                .remove::<RevealedCell>();
+        }
+    }
+
+    for entity in q_panel.iter() {
+        commands.entity(entity).despawn();
+    }
+    commands.insert_resource(GuidedSolve::new(steps, saved, saved_auto_mode));
+    spawn_guided_panel(&font_assets, &mut commands);
+}
+
+fn spawn_guided_panel(font_assets: &Res<FontAssets>, commands: &mut Commands) {
+    commands
+        .spawn((
+            Name::new("guided-solve-panel"),
+            GuidedPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(16.0),
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            Pickable::IGNORE,
+            GlobalZIndex(50),
+        ))
+        .with_children(|builder| {
+            builder
+                .spawn((
+                    Node {
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::axes(Val::Px(20.0), Val::Px(12.0)),
+                        border: UiRect::all(Val::Px(1.0)),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(8.0)),
+                    ThemedBackground(ThemeRole::Surface),
+                    BorderColor(Color::BLACK.with_alpha(0.2)),
+                ))
+                .with_children(|builder| {
+                    builder.spawn((
+                        GuidedStepText,
+                        Localized::new(""),
+                        TextFont {
+                            font: font_assets.franklin_600.clone(),
+                            font_size: 18.0,
+                            ..default()
+                        },
+                        ThemedText(ThemeRole::Text),
+                    ));
+
+                    builder
+                        .spawn(Node {
+                            display: Display::Flex,
+                            column_gap: Val::Px(8.0),
+                            margin: UiRect::top(Val::Px(10.0)),
+                            ..default()
+                        })
+                        .with_children(|builder| {
+                            panel_button(font_assets, builder, "Back", GuidedControl::Back, ());
+                            panel_button(
+                                font_assets,
+                                builder,
+                                "Play",
+                                GuidedControl::TogglePlay,
+                                GuidedPlayText,
+                            );
+                            panel_button(font_assets, builder, "Next", GuidedControl::Next, ());
+                            panel_button(
+                                font_assets,
+                                builder,
+                                "Speed 1x",
+                                GuidedControl::Speed,
+                                GuidedSpeedText,
+                            );
+                            panel_button(font_assets, builder, "Exit", GuidedControl::Exit, ());
+                        });
+                });
+        });
+}
+
+/// 底部面板上的圆角按钮, 点击后触发 `event`
+pub(crate) fn panel_button<E: Event + Copy>(
+    font_assets: &Res<FontAssets>,
+    builder: &mut ChildSpawnerCommands<'_>,
+    text: &'static str,
+    event: E,
+    text_marker: impl Bundle,
+) {
+    builder
+        .spawn((
+            Button,
+            Node {
+                min_height: Val::Px(36.0),
+                padding: UiRect::horizontal(Val::Px(18.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BorderRadius::all(Val::Px(40.0)),
+            ThemedBackground(ThemeRole::Button),
+        ))
+        .with_child((
+            Localized::new(text),
+            TextFont {
+                font: font_assets.franklin_500.clone(),
+                font_size: 14.0,
+                ..default()
+            },
+            ThemedText(ThemeRole::ButtonText),
+            text_marker,
+        ))
+        .observe(
+            move |_trigger: Trigger<Pointer<Click>>, mut commands: Commands| {
+                commands.trigger(event);
+            },
+        );
+}
+
+fn on_guided_control(
+    trigger: Trigger<GuidedControl>,
+    guided: Option<ResMut<GuidedSolve>>,
+    mut q_cell: Query<(
+        Entity,
+        &CellPosition,
+        &mut DigitValueCell,
+        &mut AutoCandidates,
+        &mut CellMode,
+    )>,
+    q_panel: Query<Entity, With<GuidedPanel>>,
+    mut auto_mode: ResMut<AutoCandidateMode>,
+    mut commands: Commands,
+) {
+    let Some(mut guided) = guided else {
+        return;
+    };
+
+    match trigger.event() {
+        GuidedControl::Next => {
+            let Some(step) = guided.steps.get(guided.cursor).cloned() else {
+                guided.playing = false;
+                return;
+            };
+            let mut removed = vec![];
+            for placement in step.placements.iter() {
+                let index = placement.cell.get() as usize;
+                let kicker = CellPosition::new(index as u8);
+                for (entity, position, mut digit, mut auto_candidates, mut cell_mode) in
+                    q_cell.iter_mut()
+                {
+                    if position.0 as usize == index {
+                        digit.0 = Some(placement.digit);
+                        *cell_mode = CellMode::Digit;
+                        commands
+                            .entity(entity)
+                            .insert((RevealedCell, PressFeedback(guided_feedback())));
+                    } else if kicker.in_range(position)
+                        && auto_candidates.0.contains(placement.digit)
+                    {
+                        // 同行同列同宫的候选数随之删除
+                        auto_candidates.0.remove(placement.digit.as_set());
+                        removed.push((position.0 as usize, placement.digit));
+                    }
+                }
+            }
+            for elimination in step.eliminations.iter() {
+                let index = elimination.cell.get() as usize;
+                for (entity, position, _, mut auto_candidates, _) in q_cell.iter_mut() {
+                    if position.0 as usize == index && auto_candidates.0.contains(elimination.digit)
+                    {
+                        auto_candidates.0.remove(elimination.digit.as_set());
+                        removed.push((index, elimination.digit));
+                        commands
+                            .entity(entity)
+                            .insert(PressFeedback(guided_feedback()));
+                    }
+                }
+            }
+            guided.history.push(removed);
+            guided.cursor += 1;
+        }
+        GuidedControl::Back => {
+            if guided.cursor == 0 {
+                return;
+            }
+            guided.cursor -= 1;
+            guided.playing = false;
+            let step = guided.steps[guided.cursor].clone();
+            let removed = guided.history.pop().unwrap_or_default();
+            for (entity, position, mut digit, mut auto_candidates, mut cell_mode) in
+                q_cell.iter_mut()
+            {
+                let index = position.0 as usize;
+                if step
+                    .placements
+                    .iter()
+                    .any(|p| p.cell.get() as usize == index)
+                {
+                    digit.0 = None;
+                    *cell_mode = CellMode::AutoCandidates;
+                    commands
+                        .entity(entity)
+                        .remove::<RevealedCell>()
+                        .insert(PressFeedback(guided_feedback()));
+                }
+                for (_, candidate) in removed.iter().filter(|(i, _)| *i == index) {
+                    auto_candidates.0 |= candidate.as_set();
+                }
+            }
+        }
+        GuidedControl::TogglePlay => {
+            guided.playing = !guided.playing;
+            guided.timer.reset();
+        }
+        GuidedControl::Speed => {
+            guided.speed = (guided.speed + 1) % SPEEDS.len();
+            let duration = 1.0 / SPEEDS[guided.speed];
+            guided
+                .timer
+                .set_duration(std::time::Duration::from_secs_f32(duration));
+            guided.timer.reset();
+        }
+        GuidedControl::Exit => {
+            // 回到讲解前的盘面
+            for (entity, (cell, revealed)) in guided.saved.iter() {
+                let mut entity = commands.entity(*entity);
+                entity.insert(cell.bundle());
+                if *revealed {
+                    entity.insert(RevealedCell);
+                } else {
+                    entity.remove::<RevealedCell>();
+                }
+            }
+            auto_mode.0 = guided.saved_auto_mode;
+            end_guided_solve(&mut commands, &q_panel);
+        }
+    }
+}
+
+/// 讲解模式下格子的动画比触摸反馈稍慢
+fn guided_feedback() -> Timer {
+    Timer::from_seconds(0.4, TimerMode::Once)
+}
+
+fn autoplay_guided_solve(mut guided: ResMut<GuidedSolve>, time: Res<Time>, mut commands: Commands) {
+    if !guided.playing {
+        return;
+    }
+    guided.timer.tick(time.delta());
+    if guided.timer.just_finished() {
+        if guided.cursor < guided.steps.len() {
+            commands.trigger(GuidedControl::Next);
+        } else {
+            guided.playing = false;
+        }
+    }
+}
+
+fn update_guided_panel(
+    guided: Res<GuidedSolve>,
+    mut q_step: Query<&mut Localized, With<GuidedStepText>>,
+    mut q_play: Query<&mut Localized, (With<GuidedPlayText>, Without<GuidedStepText>)>,
+    mut q_speed: Query<
+        &mut Localized,
+        (
+            With<GuidedSpeedText>,
+            Without<GuidedStepText>,
+            Without<GuidedPlayText>,
+        ),
+    >,
+) {
+    if !guided.is_changed() {
+        return;
+    }
+    for mut text in q_step.iter_mut() {
+        *text = guided.description();
+    }
+    for mut text in q_play.iter_mut() {
+        *text = Localized::new(if guided.playing { "Pause" } else { "Play" });
+    }
+    for mut text in q_speed.iter_mut() {
+        *text = Localized::with_args("Speed {}x", [SPEEDS[guided.speed]]);
+    }
+}
+
+fn stop_guided_solve(mut commands: Commands, q_panel: Query<Entity, With<GuidedPanel>>) {
+    end_guided_solve(&mut commands, &q_panel);
+}
+
+fn end_guided_solve(commands: &mut Commands, q_panel: &Query<Entity, With<GuidedPanel>>) {
+    commands.remove_resource::<GuidedSolve>();
+    for entity in q_panel.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[test]
+fn test_plan_guided_solve() {
+    let sudoku = Sudoku::from_str_line(
+        "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79",
+    )
+    .unwrap();
+    let (grid_state, steps) = plan_guided_solve(sudoku);
+    let empty = grid_state
+        .iter()
+        .filter(|state| matches!(state, CellState::Candidates(_)))
+        .count();
+    let placed = steps
+        .iter()
+        .map(|step| step.placements.len())
+        .sum::<usize>();
+    assert!(placed <= empty);
+    assert!(!steps.is_empty());
+}
@@ -1,7 +1,46 @@
 use crate::game::{
-    CleanCell, MoveSelectCell, NewCandidate, NewDigit, SelectedCell, control_tab::ToggleTab,
+    CleanCell, MoveSelectCell, NewCandidate, NewCornerCandidate, NewDigit, SelectedCell,
+    control_tab::{ToggleCornerTab, ToggleTab},
+    dialog::DialogStack,
+    generator::GeneratingPuzzle,
+    guided_solve::GuidedSolve,
+    layout::ControlBoardRoot,
+    mistakes::Mistakes,
+    replay::ReplayPlayer,
 };
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, input::touch::Touches, prelude::*};
+use std::time::Duration;
+
+/// 长按多久算作候选数输入
+pub const LONG_PRESS_DURATION: Duration = Duration::from_millis(450);
+
+/// 横向滑动超过这个距离才切换键盘
+const SWIPE_MIN_DISTANCE: f32 = 80.0;
+
+/// 盘面暂时不接受输入的几种情况
+#[derive(SystemParam)]
+pub(crate) struct InputBlockers<'w> {
+    mistakes: Res<'w, Mistakes>,
+    stack: Res<'w, DialogStack>,
+    player: Option<Res<'w, ReplayPlayer>>,
+    generating: Option<Res<'w, GeneratingPuzzle>>,
+    guided: Option<Res<'w, GuidedSolve>>,
+}
+
+impl InputBlockers<'_> {
+    /// 严格模式结束、回放、讲解、生成题目或有弹窗时不能输入
+    pub fn blocked(&self) -> bool {
+        self.mistakes.game_over
+            || !self.stack.is_empty()
+            || self.player.is_some()
+            || self.generating.is_some()
+            || self.guided.is_some()
+    }
+}
+
+pub(crate) fn board_input_allowed(blockers: InputBlockers) -> bool {
+    !blockers.blocked()
+}
 
 pub(crate) fn keyboard_input(
     mut commands: Commands,
@@ -32,6 +71,13 @@ pub(crate) fn keyboard_input(
         return;
     }
 
+    // 按住 Shift 是角标键盘, 同一帧按下的数字照常输入
+    if keyboard_input.any_just_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+        || keyboard_input.any_just_released([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    {
+        commands.trigger(ToggleCornerTab);
+    }
+
     let press_1 = keyboard_input.any_just_pressed([KeyCode::Digit1, KeyCode::Numpad1]);
     let press_2 = keyboard_input.any_just_pressed([KeyCode::Digit2, KeyCode::Numpad2]);
     let press_3 = keyboard_input.any_just_pressed([KeyCode::Digit3, KeyCode::Numpad3]);
@@ -43,6 +89,7 @@ pub(crate) fn keyboard_input(
     let press_9 = keyboard_input.any_just_pressed([KeyCode::Digit9, KeyCode::Numpad9]);
 
     let alt = keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
 
     let num = if press_1 {
         Some(1)
@@ -69,6 +116,8 @@ pub(crate) fn keyboard_input(
     if let Some(num) = num {
         if alt {
             commands.trigger_targets(NewCandidate::new(num), vec![q_selected]);
+        } else if shift {
+            commands.trigger_targets(NewCornerCandidate::new(num), vec![q_selected]);
         } else {
             commands.trigger_targets(NewDigit::new(num), vec![q_selected]);
         }
@@ -135,3 +184,66 @@ pub(crate) fn keyboard_move_cell(
         timer.timer.reset();
     }
 }
+
+/// 在数字键盘上左右滑动切换 Normal / Candidate 键盘
+pub(crate) fn touch_swipe_tab(
+    mut commands: Commands,
+    touches: Res<Touches>,
+    q_pad: Query<(&ComputedNode, &GlobalTransform), With<ControlBoardRoot>>,
+) {
+    // 节点的尺寸和位置是物理像素, 触摸位置是逻辑像素
+    let on_pad = |position: Vec2| {
+        q_pad.iter().any(|(node, transform)| {
+            let center = transform.translation().truncate() * node.inverse_scale_factor();
+            let size = node.size() * node.inverse_scale_factor();
+            Rect::from_center_size(center, size).contains(position)
+        })
+    };
+    for touch in touches.iter_just_released() {
+        let delta = touch.position() - touch.start_position();
+        if on_pad(touch.start_position())
+            && delta.x.abs() > SWIPE_MIN_DISTANCE
+            && delta.x.abs() > delta.y.abs() * 2.0
+        {
+            commands.trigger(ToggleTab);
+            return;
+        }
+    }
+}
+
+/// 触摸按下时的缩放反馈, 代替鼠标悬停预览
+#[derive(Component)]
+pub struct PressFeedback(pub Timer);
+
+impl Default for PressFeedback {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.18, TimerMode::Once))
+    }
+}
+
+pub(crate) fn on_touch_press(trigger: Trigger<Pointer<Pressed>>, mut commands: Commands) {
+    if trigger.pointer_id.is_touch() {
+        commands
+            .entity(trigger.target())
+            .insert(PressFeedback::default());
+    }
+}
+
+pub(crate) fn press_feedback(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut q_feedback: Query<(Entity, &mut Transform, &mut PressFeedback)>,
+) {
+    for (entity, mut transform, mut feedback) in q_feedback.iter_mut() {
+        feedback.0.tick(time.delta());
+        // 先缩小再弹回
+        let t = feedback.0.fraction();
+        let scale = 1.0 - 0.08 * (t * std::f32::consts::PI).sin();
+        transform.scale = Vec3::splat(scale);
+
+        if feedback.0.finished() {
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<PressFeedback>();
+        }
+    }
+}
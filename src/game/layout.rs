@@ -0,0 +1,358 @@
+use crate::{
+    game::{
+        DigitCellContainer, Settings,
+        cell_state::{
+            AutoCandidateCellMarker, CornerCandidateSlot, ManualCandidateCellMarker,
+            SqueezedCandidate,
+        },
+        control_tab::{ControlCandidateText, ControlDigit, ControlNumber},
+    },
+    share::TitleBar,
+};
+use bevy::{prelude::*, window::PrimaryWindow, window::WindowResized};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<BoardLayout>()
+        .add_systems(Startup, init_layout)
+        .add_systems(Update, update_layout.run_if(on_event::<WindowResized>))
+        .add_systems(
+            PostUpdate,
+            apply_layout.run_if(
+                resource_changed::<BoardLayout>
+                    .or(resource_changed::<Settings>)
+                    .or(any_match_filter::<Added<TitleBar>>)
+                    .or(any_match_filter::<Changed<SqueezedCandidate>>),
+            ),
+        );
+}
+
+/// 标题栏高度
+const TITLE_HEIGHT: f32 = 114.0;
+/// 窄屏时的标题栏高度
+const COMPACT_TITLE_HEIGHT: f32 = 64.0;
+/// 工具栏高度 + 边框
+const TOOLBAR_HEIGHT: f32 = 57.0;
+/// 游戏区域的内边距和外边距
+const GAME_PADDING: f32 = 13.0 * 2.0 + 20.0 * 2.0;
+/// 右侧键盘宽度 + 左边距
+const CONTROL_WIDTH: f32 = 240.0 + 40.0;
+/// 竖屏时棋盘下方键盘的高度
+const CONTROL_HEIGHT: f32 = 220.0;
+/// 棋盘外边框和宫格间隙
+const BOARD_FRAME: f32 = 5.0 * 2.0 + 4.0 * 2.0;
+const MIN_CELL_SIZE: f32 = 28.0;
+const MAX_CELL_SIZE: f32 = 88.0;
+/// 键盘数字和候选数字的默认字号
+const KEY_DIGIT_FONT: f32 = 32.0;
+const KEY_CANDIDATE_FONT: f32 = 16.0;
+/// 键盘按钮最大尺寸
+const MAX_KEY_SIZE: f32 = 70.0;
+/// 窄于这个宽度时收起工具栏两侧的留白
+const COMPACT_WIDTH: f32 = 900.0;
+/// 双人对战时两块棋盘之间的间距
+const RACE_GAP: f32 = 40.0;
+/// 双人对战时棋盘上方的进度条高度
+const RACE_PROGRESS_HEIGHT: f32 = 56.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Orientation {
+    #[default]
+    Landscape,
+    Portrait,
+}
+
+/// 根据窗口尺寸计算出来的布局
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct BoardLayout {
+    pub orientation: Orientation,
+    pub compact: bool,
+    pub cell_size: f32,
+}
+
+impl Default for BoardLayout {
+    fn default() -> Self {
+        Self::from_window_size(1400.0, 1000.0)
+    }
+}
+
+impl BoardLayout {
+    pub fn from_window_size(width: f32, height: f32) -> Self {
+        // 竖屏或者放不下右侧键盘的时候，把键盘挪到棋盘下面
+        let orientation = if height > width || width < COMPACT_WIDTH * 0.8 {
+            Orientation::Portrait
+        } else {
+            Orientation::Landscape
+        };
+        let compact = width < COMPACT_WIDTH;
+        let title_height = if compact {
+            COMPACT_TITLE_HEIGHT
+        } else {
+            TITLE_HEIGHT
+        };
+        let header = title_height + TOOLBAR_HEIGHT + GAME_PADDING;
+
+        let (avail_width, avail_height) = match orientation {
+            Orientation::Landscape => (width - GAME_PADDING - CONTROL_WIDTH, height - header),
+            Orientation::Portrait => (width - GAME_PADDING, height - header - CONTROL_HEIGHT),
+        };
+        let board = avail_width.min(avail_height);
+        let cell_size = ((board - BOARD_FRAME) / 9.0).clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+
+        Self {
+            orientation,
+            compact,
+            cell_size,
+        }
+    }
+
+    /// 双人对战时两块棋盘并排, 没有键盘
+    pub fn for_race(width: f32, height: f32) -> Self {
+        let mut layout = Self::from_window_size(width, height);
+        let header = layout.title_height() + RACE_PROGRESS_HEIGHT + GAME_PADDING;
+        let board = ((width - GAME_PADDING - RACE_GAP) / 2.0).min(height - header);
+        layout.cell_size = ((board - BOARD_FRAME) / 9.0).clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+        layout
+    }
+
+    /// 棋盘的总宽度
+    pub fn board_size(&self) -> f32 {
+        self.cell_size * 9.0 + BOARD_FRAME
+    }
+
+    pub fn digit_font_size(&self) -> f32 {
+        self.cell_size * 0.55
+    }
+
+    pub fn candidate_font_size(&self) -> f32 {
+        (self.cell_size * 0.18).max(9.0)
+    }
+
+    /// 有角标时中心的候选数缩小
+    pub fn centre_font_size(&self, squeezed: bool) -> f32 {
+        if squeezed {
+            self.candidate_font_size() * 0.625
+        } else {
+            self.candidate_font_size()
+        }
+    }
+
+    pub fn corner_font_size(&self) -> f32 {
+        self.candidate_font_size() * 0.75
+    }
+
+    pub fn title_height(&self) -> f32 {
+        if self.compact {
+            COMPACT_TITLE_HEIGHT
+        } else {
+            TITLE_HEIGHT
+        }
+    }
+
+    /// 竖屏时九个数字键排成一行
+    pub fn key_size(&self) -> f32 {
+        match self.orientation {
+            Orientation::Landscape => MAX_KEY_SIZE,
+            Orientation::Portrait => ((self.board_size() - 8.0 * 4.0) / 9.0).min(MAX_KEY_SIZE),
+        }
+    }
+}
+
+/// 棋盘和键盘的外层容器
+#[derive(Component)]
+pub struct BoardBody;
+
+/// 棋盘根节点
+#[derive(Component)]
+pub struct BoardRoot;
+
+/// 键盘根节点
+#[derive(Component)]
+pub struct ControlBoardRoot;
+
+/// 工具栏左右两侧
+#[derive(Component)]
+pub struct ToolbarSide;
+
+fn init_layout(mut layout: ResMut<BoardLayout>, window: Single<&Window, With<PrimaryWindow>>) {
+    *layout = BoardLayout::from_window_size(window.width(), window.height());
+}
+
+fn update_layout(mut resized: EventReader<WindowResized>, mut layout: ResMut<BoardLayout>) {
+    if let Some(ev) = resized.read().last() {
+        let new_layout = BoardLayout::from_window_size(ev.width, ev.height);
+        layout.set_if_neq(new_layout);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_layout(
+    layout: Res<BoardLayout>,
+    settings: Res<Settings>,
+    mut q_body: Query<&mut Node, With<BoardBody>>,
+    mut q_board: Query<&mut Node, (With<BoardRoot>, Without<BoardBody>)>,
+    mut q_control: Query<
+        &mut Node,
+        (
+            With<ControlBoardRoot>,
+            Without<BoardBody>,
+            Without<BoardRoot>,
+        ),
+    >,
+    mut q_key: Query<
+        &mut Node,
+        (
+            With<ControlNumber>,
+            Without<BoardBody>,
+            Without<BoardRoot>,
+            Without<ControlBoardRoot>,
+        ),
+    >,
+    mut q_side: Query<
+        &mut Node,
+        (
+            With<ToolbarSide>,
+            Without<BoardBody>,
+            Without<BoardRoot>,
+            Without<ControlBoardRoot>,
+            Without<ControlNumber>,
+        ),
+    >,
+    mut q_title: Query<
+        &mut Node,
+        (
+            With<TitleBar>,
+            Without<BoardBody>,
+            Without<BoardRoot>,
+            Without<ControlBoardRoot>,
+            Without<ControlNumber>,
+            Without<ToolbarSide>,
+        ),
+    >,
+    mut q_digit: Query<&mut TextFont, With<DigitCellContainer>>,
+    mut q_candidate: Query<
+        (&mut TextFont, &SqueezedCandidate),
+        (
+            Or<(
+                With<AutoCandidateCellMarker>,
+                With<ManualCandidateCellMarker>,
+            )>,
+            Without<DigitCellContainer>,
+        ),
+    >,
+    mut q_key_text: Query<
+        (&mut TextFont, Has<ControlDigit>),
+        (
+            Or<(With<ControlDigit>, With<ControlCandidateText>)>,
+            Without<DigitCellContainer>,
+            Without<AutoCandidateCellMarker>,
+            Without<ManualCandidateCellMarker>,
+        ),
+    >,
+    mut q_corner: Query<
+        &mut TextFont,
+        (
+            With<CornerCandidateSlot>,
+            Without<DigitCellContainer>,
+            Without<AutoCandidateCellMarker>,
+            Without<ManualCandidateCellMarker>,
+            Without<ControlDigit>,
+            Without<ControlCandidateText>,
+        ),
+    >,
+) {
+    let board_size = layout.board_size();
+    let portrait = layout.orientation == Orientation::Portrait;
+
+    for mut node in q_body.iter_mut() {
+        if portrait {
+            node.flex_direction = FlexDirection::Column;
+            node.align_items = AlignItems::Center;
+        } else {
+            node.flex_direction = FlexDirection::Row;
+            node.align_items = AlignItems::Stretch;
+        }
+    }
+
+    for mut node in q_board.iter_mut() {
+        node.width = Val::Px(board_size);
+    }
+
+    for mut node in q_control.iter_mut() {
+        if portrait {
+            node.margin = UiRect::top(Val::Px(20.0));
+            node.width = Val::Px(board_size);
+            node.max_width = Val::Auto;
+        } else {
+            node.margin = UiRect::left(Val::Px(40.0));
+            node.width = Val::Auto;
+            node.max_width = Val::Px(240.0);
+        }
+    }
+
+    let key_size = layout.key_size();
+    for mut node in q_key.iter_mut() {
+        node.width = Val::Px(key_size);
+        node.height = Val::Px(key_size);
+    }
+
+    for mut node in q_side.iter_mut() {
+        node.width = if layout.compact {
+            Val::Auto
+        } else {
+            Val::Px(350.0)
+        };
+    }
+
+    for mut node in q_title.iter_mut() {
+        node.height = Val::Px(layout.title_height());
+        node.padding.top = if layout.compact {
+            Val::Px(12.0)
+        } else {
+            Val::Px(26.0)
+        };
+    }
+
+    let scale = settings.font_scale;
+    for mut font in q_digit.iter_mut() {
+        font.font_size = layout.digit_font_size() * scale;
+    }
+
+    for (mut font, squeezed) in q_candidate.iter_mut() {
+        font.font_size = layout.centre_font_size(squeezed.0) * scale;
+    }
+
+    for mut font in q_corner.iter_mut() {
+        font.font_size = layout.corner_font_size() * scale;
+    }
+
+    for (mut font, is_digit) in q_key_text.iter_mut() {
+        let base = if is_digit {
+            KEY_DIGIT_FONT
+        } else {
+            KEY_CANDIDATE_FONT
+        };
+        font.font_size = base * scale;
+    }
+}
+
+#[test]
+fn test_portrait_layout() {
+    let layout = BoardLayout::from_window_size(390.0, 844.0);
+    assert_eq!(layout.orientation, Orientation::Portrait);
+    assert!(layout.compact);
+    assert!(layout.board_size() <= 390.0);
+}
+
+#[test]
+fn test_landscape_layout() {
+    let layout = BoardLayout::from_window_size(1400.0, 1000.0);
+    assert_eq!(layout.orientation, Orientation::Landscape);
+    assert!(!layout.compact);
+    assert!(layout.board_size() + CONTROL_WIDTH <= 1400.0);
+}
+
+#[test]
+fn test_race_layout() {
+    let layout = BoardLayout::for_race(1400.0, 1000.0);
+    assert!(layout.board_size() * 2.0 + RACE_GAP <= 1400.0);
+}
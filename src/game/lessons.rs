@@ -0,0 +1,968 @@
+use crate::{
+    GameState,
+    game::{
+        AutoCandidateMode, GameTimer, InitPuzzle, SudokuManager,
+        cell_state::{
+            AutoCandidates, CellMode, CellValueBundle, ConflictCell, CorrectionCell,
+            DigitValueCell, FixedCell, RevealedCell,
+        },
+        guided_solve::{GuidedControl, STRATEGIES, SolveStep, panel_button},
+        position::CellPosition,
+        trial::{CellSnapshot, CellValues},
+    },
+    i18n::Localized,
+    loading::FontAssets,
+    theme::{ThemeRole, ThemedBackground, ThemedText},
+};
+use bevy::{platform::collections::HashSet, prelude::*};
+use std::mem::discriminant;
+use sudoku::{
+    Sudoku,
+    bitset::Set,
+    board::{Candidate, CellState, Digit},
+    strategy::{Deduction, Strategy, StrategySolver},
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.insert_resource(load_lesson_progress())
+        .add_systems(
+            Update,
+            (check_lesson_moves, update_lesson_panel)
+                .chain()
+                .run_if(in_state(GameState::Playing).and(resource_exists::<ActiveLesson>)),
+        )
+        .add_systems(OnExit(GameState::Playing), stop_lesson)
+        .add_observer(on_start_lesson)
+        .add_observer(on_lesson_control)
+        .add_observer(
+            |_: Trigger<InitPuzzle>,
+             mut commands: Commands,
+             q_panel: Query<Entity, With<LessonPanel>>| {
+                end_lesson(&mut commands, &q_panel);
+            },
+        );
+}
+
+/// 课程里讲解的技巧, 按学习顺序排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Technique {
+    FullHouse,
+    NakedSingle,
+    HiddenSingle,
+    PointingPair,
+    BoxLineReduction,
+    NakedPair,
+    HiddenPair,
+    NakedTriple,
+    HiddenTriple,
+    XWing,
+    Swordfish,
+    XyWing,
+}
+
+impl Technique {
+    pub const ALL: [Technique; 12] = [
+        Technique::FullHouse,
+        Technique::NakedSingle,
+        Technique::HiddenSingle,
+        Technique::PointingPair,
+        Technique::BoxLineReduction,
+        Technique::NakedPair,
+        Technique::HiddenPair,
+        Technique::NakedTriple,
+        Technique::HiddenTriple,
+        Technique::XWing,
+        Technique::Swordfish,
+        Technique::XyWing,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Technique::FullHouse => "Full House",
+            Technique::NakedSingle => "Naked Single",
+            Technique::HiddenSingle => "Hidden Single",
+            Technique::PointingPair => "Pointing Pair",
+            Technique::BoxLineReduction => "Box/Line Reduction",
+            Technique::NakedPair => "Naked Pair",
+            Technique::HiddenPair => "Hidden Pair",
+            Technique::NakedTriple => "Naked Triple",
+            Technique::HiddenTriple => "Hidden Triple",
+            Technique::XWing => "X-Wing",
+            Technique::Swordfish => "Swordfish",
+            Technique::XyWing => "XY-Wing",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Technique::FullHouse => {
+                "A row, column or box has only one empty cell left. Fill in the missing digit."
+            }
+            Technique::NakedSingle => {
+                "A cell has only one candidate left. Find it and enter the digit."
+            }
+            Technique::HiddenSingle => {
+                "A digit fits in only one cell of a row, column or box. Find it and enter the digit."
+            }
+            Technique::PointingPair => {
+                "Inside a box a digit is confined to one row or column. Remove it from the rest of that line."
+            }
+            Technique::BoxLineReduction => {
+                "Inside a row or column a digit is confined to one box. Remove it from the rest of that box."
+            }
+            Technique::NakedPair => {
+                "Two cells in a house share the same two candidates. Remove those digits from the other cells."
+            }
+            Technique::HiddenPair => {
+                "Two digits appear in only two cells of a house. Remove every other candidate from those cells."
+            }
+            Technique::NakedTriple => {
+                "Three cells in a house hold only three digits between them. Remove those digits elsewhere."
+            }
+            Technique::HiddenTriple => {
+                "Three digits appear in only three cells of a house. Remove the other candidates from them."
+            }
+            Technique::XWing => {
+                "A digit sits in the same two columns in two rows. Remove it from the rest of those columns."
+            }
+            Technique::Swordfish => {
+                "A digit is limited to three columns across three rows. Remove it from the rest of those columns."
+            }
+            Technique::XyWing => {
+                "A pivot cell XY sees pincers XZ and YZ. Remove Z from cells that see both pincers."
+            }
+        }
+    }
+
+    /// 需要用户填入数字, 否则需要删除候选数
+    pub fn is_placement(&self) -> bool {
+        matches!(
+            self,
+            Technique::FullHouse | Technique::NakedSingle | Technique::HiddenSingle
+        )
+    }
+
+    fn strategy(&self) -> Strategy {
+        match self {
+            Technique::FullHouse | Technique::NakedSingle => Strategy::NakedSingles,
+            Technique::HiddenSingle => Strategy::HiddenSingles,
+            Technique::PointingPair | Technique::BoxLineReduction => Strategy::LockedCandidates,
+            Technique::NakedPair => Strategy::NakedPairs,
+            Technique::HiddenPair => Strategy::HiddenPairs,
+            Technique::NakedTriple => Strategy::NakedTriples,
+            Technique::HiddenTriple => Strategy::HiddenTriples,
+            Technique::XWing => Strategy::XWing,
+            Technique::Swordfish => Strategy::Swordfish,
+            Technique::XyWing => Strategy::XyWing,
+        }
+    }
+
+    /// 按难度用到这个技巧为止的所有技巧
+    fn strategies(&self) -> &'static [Strategy] {
+        let strategy = self.strategy();
+        let index = STRATEGIES
+            .iter()
+            .position(|s| discriminant(s) == discriminant(&strategy))
+            .unwrap_or(STRATEGIES.len() - 1);
+        &STRATEGIES[..=index]
+    }
+
+    fn matches(&self, deduction: &Deduction<&[Candidate]>, state: &[CellState; 81]) -> bool {
+        if discriminant(&deduction.strategy()) != discriminant(&self.strategy()) {
+            return false;
+        }
+        match (self, deduction) {
+            (Technique::FullHouse, Deduction::NakedSingles(candidate)) => {
+                is_full_house(state, candidate.cell.get())
+            }
+            (Technique::NakedSingle, Deduction::NakedSingles(candidate)) => {
+                !is_full_house(state, candidate.cell.get())
+            }
+            (Technique::PointingPair, Deduction::LockedCandidates { is_pointing, .. }) => {
+                *is_pointing
+            }
+            (Technique::BoxLineReduction, Deduction::LockedCandidates { is_pointing, .. }) => {
+                !*is_pointing
+            }
+            _ => true,
+        }
+    }
+
+    /// 从生成器里挑出来的题目, 每道题在解题过程中都会用到这个技巧
+    fn puzzles(&self) -> &'static [&'static str] {
+        match self {
+            Technique::FullHouse => &[
+                "...657.4...6.4..8.....8.9.1729......8.......6......5371.7.6.....8..7.1...4.291...",
+                ".95....8.4....3...3.....6...8..5.9..15.7.9.46..2.8..7...9.....7...4....5.6....39.",
+                ".....68..2.14......6.35...1.1....38.8...3...6.26....7.1...28.9......91.3..71.....",
+            ],
+            Technique::NakedSingle => &[
+                "...529..3.4....15......8.2....8...75..23.76..67...1....1.2......83....1.9..135...",
+                "..21....9...7.....7.3.8.41...4.9..3..7.4.3.6..3..5.2...27.1.5.3.....4...4....57..",
+                "...79..35....2..1.......8.731.4...7..7.....2..5...1.634.7.......9..8....18..37...",
+            ],
+            Technique::HiddenSingle => &[
+                ".9...681...7.....5..6.58.3.....896.4.........6.923.....7.49.1..9.....2...426...8.",
+                "75....3....4.2...9..14.3.7.....4.6..9...1...2..6.3.....6.1.82..1...7.4....2....91",
+                "....2...98.4....2.1..9.365.4..6.......3...1.......2..5.584.6..1.1....9.49...8....",
+            ],
+            Technique::PointingPair => &[
+                "...5.1....56......3..86...774....3...19...75...8....462...95..8......62....1.8...",
+                "...3..157.7..2.......9..6.36....584...........894....25.1..9.......4..6.962..3...",
+                "...4.2.5694.....8.....1....1...7.6...2.5.3.4...8.2...3....4.....7.....9228.3.6...",
+            ],
+            Technique::BoxLineReduction => &[
+                ".6.....3....9..5..9..7..4..5..1.87..8.2...6.1..76.9..4..5..1..7..8..6....7.....4.",
+                ".5.......2......8.....123.6..54...7.78.1.5.29.2...31..6.257.....4......7.......3.",
+                "73.9......8....25.....5..79..8....13....6....34....8..97..2.....14....2......4.91",
+            ],
+            Technique::NakedPair => &[
+                ".9..5.8....3..41....71...5..2.3...818.......946...1.2..7...25....29..4....4.3..1.",
+                ".24...85....57...9...2..1....6....85..8.2.7..15....3....1..7...6...83....85...67.",
+                "..5......4...18..5...57..6..18.9.47...4...2...72.5.19..9..83...2..96...1......6..",
+            ],
+            Technique::HiddenPair => &[
+                "..3...2.......817.....2.5.9.3.54..8...43.26...7..69.5.7.2.8.....451.......8...9..",
+                ".23.5...7...7..5..74.9.3.8..3.....2...28.16...1.....5..9.1.7.32..1..9...3...2.91.",
+                ".5329..18........2......34.5..17....8...3...5....54..7.81......3........76..1389.",
+            ],
+            Technique::NakedTriple => &[
+                "93..2.56..468.....1.85...9.3.....97.4.......5.59.....2.9...57.6.....815..15.7..89",
+                "2....85....6.....1759....4......1.5.17.2.5.86.3.8......9....6748.....1....26....8",
+                "...98.65.....2..71.....19.45.1.....7.7..6..1.9.....4.68.56.....69..1.....47.58...",
+            ],
+            Technique::HiddenTriple => &[
+                "..7..6.4...8.2...72....93....4.32.6..9.....3..2.49.7....32....14...5.8...7.6..4..",
+                "..94.28.5.8...69.4..7....2...81.......18597.......31...5....6..8.46...9.1.63.45..",
+                ".7..2....5.98.....3.....8...5.36......15.23......14.6...7.....1.....39.6....7..4.",
+            ],
+            Technique::XWing => &[
+                ".59........3296..116..34..........72.2..4..9.71..........82..193..4518........23.",
+                ".....4...7.2..6.3..34.1..5..781....9.........6....834..4..5.72..5.2..4.3...9.....",
+                "6.3......7....8..6.1...3.95.2.3.96....5...9....14.2.7.54.9...6.9..7....8......2.9",
+            ],
+            Technique::Swordfish => &[
+                "...4....6..8..694.....2.3.7.92..1..3..59674..4..8..59.3.4.1.....896..1..6....4...",
+                ".....6.4.4...3..5..8.5..1..89.1..4....2...6....7..4.13..1..5.3..7..9...1.3.2.....",
+                "74.....3.2.3.5......163.2......7.4.2...9.8...8.2.1......4.613......9.1.4.9.....27",
+            ],
+            Technique::XyWing => &[
+                "1.......2..7.1.63.95.2.3...5.....9.....857.....2.....3...3.1.59.35.8.4..4.......6",
+                ".4.......9.86..3.....59.14.8..2..57..1..7..9..72..4..1.83.62.....1..97.3.......1.",
+                "3.8.62.7..6.7..4.2...........6.251.7...3.6...9.241.3...........2.5..7.3..8.29.6.5",
+            ],
+        }
+    }
+}
+
+/// 格子所在的行、列或宫只剩这一个空格
+fn is_full_house(state: &[CellState; 81], cell: u8) -> bool {
+    let position = CellPosition::new(cell);
+    let empty_in = |same_house: &dyn Fn(&CellPosition) -> bool| {
+        (0..81)
+            .map(CellPosition::new)
+            .filter(|other| same_house(other))
+            .filter(|other| matches!(state[other.0 as usize], CellState::Candidates(_)))
+            .count()
+    };
+    empty_in(&|other| other.row() == position.row()) == 1
+        || empty_in(&|other| other.col() == position.col()) == 1
+        || empty_in(&|other| other.block() == position.block()) == 1
+}
+
+/// 盘面上所有的唯余 (`hidden` 为 false) 或排除 (`hidden` 为 true) 填数
+fn single_placements(state: &[CellState; 81], hidden: bool) -> Vec<Candidate> {
+    let candidates = |index: usize| match state[index] {
+        CellState::Candidates(candidates) => candidates,
+        CellState::Digit(_) => Set::NONE,
+    };
+    let mut placements = vec![];
+    for index in 0..81 {
+        let position = CellPosition::new(index as u8);
+        for digit in candidates(index) {
+            let single = if hidden {
+                // 同一行、列或宫里只有这个格子能放这个数字
+                let only_here = |same_house: &dyn Fn(&CellPosition) -> bool| {
+                    (0..81).all(|other| {
+                        other == index
+                            || !same_house(&CellPosition::new(other as u8))
+                            || !candidates(other).contains(digit)
+                    })
+                };
+                only_here(&|other| other.row() == position.row())
+                    || only_here(&|other| other.col() == position.col())
+                    || only_here(&|other| other.block() == position.block())
+            } else {
+                candidates(index).len() == 1
+            };
+            if single {
+                placements.push(Candidate::new(index as u8, digit.get()));
+            }
+        }
+    }
+    placements
+}
+
+/// 把一步推导应用到盘面上
+fn apply_step(state: &mut [CellState; 81], step: &SolveStep) {
+    for placement in step.placements.iter() {
+        let position = CellPosition::new(placement.cell.get());
+        state[position.0 as usize] = CellState::Digit(placement.digit);
+        for (index, cell_state) in state.iter_mut().enumerate() {
+            if let CellState::Candidates(candidates) = cell_state
+                && position.in_range(&CellPosition::new(index as u8))
+            {
+                candidates.remove(placement.digit.as_set());
+            }
+        }
+    }
+    for elimination in step.eliminations.iter() {
+        if let CellState::Candidates(candidates) = &mut state[elimination.cell.get() as usize] {
+            candidates.remove(elimination.digit.as_set());
+        }
+    }
+}
+
+/// 一道课程题: 盘面停在需要用到目标技巧的那一步
+#[derive(Debug, Clone)]
+pub struct LessonPosition {
+    pub state: [CellState; 81],
+    pub solution: Sudoku,
+    /// 可以接受的填数
+    pub placements: Vec<Candidate>,
+    /// 需要删除的候选数
+    pub eliminations: Vec<Candidate>,
+}
+
+pub fn lesson_position(sudoku: Sudoku, technique: Technique) -> Option<LessonPosition> {
+    let solution = sudoku.solution()?;
+    let solver = StrategySolver::from_sudoku(sudoku);
+    let mut state = solver.grid_state();
+    let deductions = match solver.solve(technique.strategies()) {
+        Ok((_, deductions)) | Err((_, deductions)) => deductions,
+    };
+
+    for deduction in deductions.iter() {
+        let step = SolveStep::from_deduction(deduction);
+        if technique.matches(&deduction, &state) {
+            let mut placements = step.placements;
+            if technique.is_placement() {
+                // 同一盘面上其他用同样技巧能填的格子也算对
+                placements.extend(
+                    single_placements(&state, technique == Technique::HiddenSingle)
+                        .into_iter()
+                        .filter(|candidate| {
+                            technique == Technique::HiddenSingle
+                                || is_full_house(&state, candidate.cell.get())
+                                    == (technique == Technique::FullHouse)
+                        }),
+                );
+            }
+            return Some(LessonPosition {
+                state,
+                solution,
+                placements,
+                eliminations: step.eliminations,
+            });
+        }
+        apply_step(&mut state, &step);
+    }
+    None
+}
+
+/// 已经完成的课程, 保存在 `scores/lessons.txt`
+#[derive(Resource, Debug, Default)]
+pub struct LessonProgress {
+    completed: HashSet<Technique>,
+}
+
+impl LessonProgress {
+    /// 每行一个已完成的技巧名
+    fn encode(&self) -> String {
+        Technique::ALL
+            .iter()
+            .filter(|technique| self.completed.contains(*technique))
+            .map(|technique| format!("{}\n", technique.name()))
+            .collect()
+    }
+
+    /// 无法识别的行直接跳过
+    fn decode(text: &str) -> Self {
+        let completed = text
+            .lines()
+            .filter_map(|line| {
+                Technique::ALL
+                    .into_iter()
+                    .find(|technique| technique.name() == line.trim())
+            })
+            .collect();
+        Self { completed }
+    }
+
+    /// 第一个还没完成的课程
+    pub fn next_lesson(&self) -> usize {
+        Technique::ALL
+            .iter()
+            .position(|technique| !self.completed.contains(technique))
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const LESSON_PROGRESS_PATH: &str = "scores/lessons.txt";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_lesson_progress() -> LessonProgress {
+    std::fs::read_to_string(LESSON_PROGRESS_PATH)
+        .map(|text| LessonProgress::decode(&text))
+        .unwrap_or_default()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_lesson_progress() -> LessonProgress {
+    LessonProgress::default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_lesson_progress(progress: &LessonProgress) {
+    let result = std::fs::create_dir_all("scores")
+        .and_then(|_| std::fs::write(LESSON_PROGRESS_PATH, progress.encode()));
+    if let Err(err) = result {
+        warn!("failed to save lesson progress: {}", err);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn save_lesson_progress(_progress: &LessonProgress) {}
+
+/// 进入课程前的对局, 退出课程时还原
+#[derive(Resource)]
+struct SavedGame {
+    sudoku_manager: SudokuManager,
+    game_timer: GameTimer,
+    auto_mode: bool,
+    cells: Vec<SavedCell>,
+}
+
+struct SavedCell {
+    entity: Entity,
+    values: CellSnapshot,
+    fixed: bool,
+    revealed: bool,
+    correction: bool,
+    conflict: Option<HashSet<Entity>>,
+}
+
+type SavedCellQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        CellValues,
+        Has<FixedCell>,
+        Has<RevealedCell>,
+        Has<CorrectionCell>,
+        Option<&'static ConflictCell>,
+    ),
+>;
+
+impl SavedGame {
+    fn new(
+        sudoku_manager: &SudokuManager,
+        game_timer: &GameTimer,
+        auto_mode: bool,
+        q_saved: &SavedCellQuery,
+    ) -> Self {
+        let cells = q_saved
+            .iter()
+            .map(
+                |(entity, values, fixed, revealed, correction, conflict)| SavedCell {
+                    entity,
+                    values: CellSnapshot::new(values),
+                    fixed,
+                    revealed,
+                    correction,
+                    conflict: conflict.map(|conflict| conflict.0.clone()),
+                },
+            )
+            .collect();
+        Self {
+            sudoku_manager: sudoku_manager.clone(),
+            game_timer: game_timer.clone(),
+            auto_mode,
+            cells,
+        }
+    }
+
+    fn restore(&self, commands: &mut Commands) {
+        commands.insert_resource(self.sudoku_manager.clone());
+        commands.insert_resource(self.game_timer.clone());
+        commands.insert_resource(AutoCandidateMode(self.auto_mode));
+        for cell in &self.cells {
+            let mut entity = commands.entity(cell.entity);
+            entity.insert(cell.values.bundle()).remove::<(
+                FixedCell,
+                RevealedCell,
+                CorrectionCell,
+                ConflictCell,
+            )>();
+            if cell.fixed {
+                entity.insert(FixedCell);
+            }
+            if cell.revealed {
+                entity.insert(RevealedCell);
+            }
+            if cell.correction {
+                entity.insert(CorrectionCell);
+            }
+            if let Some(conflict) = &cell.conflict {
+                entity.insert(ConflictCell(conflict.clone()));
+            }
+        }
+    }
+}
+
+/// 正在进行的课程, 只在课程模式下存在
+#[derive(Resource)]
+pub struct ActiveLesson {
+    lesson: usize,
+    puzzle: usize,
+    placements: Vec<Candidate>,
+    /// 还没删除的候选数
+    remaining: Vec<Candidate>,
+    /// 用户当前应该看到的数字和候选数
+    digits: [Option<Digit>; 81],
+    candidates: [Set<Digit>; 81],
+    feedback: Localized,
+    completed: bool,
+}
+
+impl ActiveLesson {
+    fn technique(&self) -> Technique {
+        Technique::ALL[self.lesson]
+    }
+}
+
+/// 开始第 `lesson` 课的第 `puzzle` 道题
+#[derive(Event, Debug, Clone, Copy)]
+pub struct StartLesson {
+    pub lesson: usize,
+    pub puzzle: usize,
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LessonControl {
+    Prev,
+    Next,
+    Another,
+    Exit,
+}
+
+#[derive(Component)]
+struct LessonPanel;
+
+#[derive(Component)]
+struct LessonTitleText;
+
+#[derive(Component)]
+struct LessonFeedbackText;
+
+#[derive(Component)]
+struct LessonProgressText;
+
+#[allow(clippy::too_many_arguments)]
+fn on_start_lesson(
+    trigger: Trigger<StartLesson>,
+    q_cell: Query<(Entity, &CellPosition)>,
+    q_saved: SavedCellQuery,
+    q_panel: Query<Entity, With<LessonPanel>>,
+    mut auto_mode: ResMut<AutoCandidateMode>,
+    sudoku_manager: Res<SudokuManager>,
+    game_timer: Res<GameTimer>,
+    saved: Option<Res<SavedGame>>,
+    font_assets: Res<FontAssets>,
+    mut commands: Commands,
+) {
+    let StartLesson { lesson, puzzle } = *trigger.event();
+    let technique = Technique::ALL[lesson % Technique::ALL.len()];
+    let puzzles = technique.puzzles();
+    let puzzle = puzzle % puzzles.len();
+    let Some(position) = Sudoku::from_str_line(puzzles[puzzle])
+        .ok()
+        .and_then(|sudoku| lesson_position(sudoku, technique))
+    else {
+        warn!("lesson puzzle {} of {:?} is invalid", puzzle, technique);
+        return;
+    };
+
+    commands.trigger(GuidedControl::Exit);
+    // 换课时保留最早保存的对局
+    if saved.is_none() {
+        commands.insert_resource(SavedGame::new(
+            &sudoku_manager,
+            &game_timer,
+            auto_mode.0,
+            &q_saved,
+        ));
+    }
+    commands.insert_resource(SudokuManager {
+        solution: position.solution,
+        solver: StrategySolver::from_grid_state(position.state),
+        is_solved: false,
+    });
+
+    auto_mode.0 = true;
+    for (entity, cell_position) in q_cell.iter() {
+        let cell_state = position.state[cell_position.0 as usize];
+        commands
+            .entity(entity)
+            .insert(CellValueBundle::from_cell_state(cell_state, true))
+            .remove::<(ConflictCell, CorrectionCell, RevealedCell)>();
+        if matches!(cell_state, CellState::Digit(_)) {
+            commands.entity(entity).insert(FixedCell);
+        } else {
+            commands.entity(entity).remove::<FixedCell>();
+        }
+    }
+
+    let mut digits = [None; 81];
+    let mut candidates = [Set::NONE; 81];
+    for (index, cell_state) in position.state.iter().enumerate() {
+        match cell_state {
+            CellState::Digit(digit) => digits[index] = Some(*digit),
+            CellState::Candidates(set) => candidates[index] = *set,
+        }
+    }
+
+    for entity in q_panel.iter() {
+        commands.entity(entity).despawn();
+    }
+    commands.insert_resource(ActiveLesson {
+        lesson: lesson % Technique::ALL.len(),
+        puzzle,
+        placements: position.placements,
+        remaining: position.eliminations,
+        digits,
+        candidates,
+        feedback: if technique.is_placement() {
+            Localized::new("Enter the digit this technique reveals.")
+        } else {
+            Localized::new("Tap candidates to remove the ones this technique rules out.")
+        },
+        completed: false,
+    });
+    spawn_lesson_panel(&font_assets, &mut commands, technique);
+}
+
+/// 检查用户的每一步, 不是这个技巧得出的填数或删除会被撤回
+#[allow(clippy::type_complexity)]
+fn check_lesson_moves(
+    mut lesson: ResMut<ActiveLesson>,
+    mut progress: ResMut<LessonProgress>,
+    mut q_cell: Query<
+        (
+            &CellPosition,
+            &mut DigitValueCell,
+            &mut AutoCandidates,
+            &mut CellMode,
+        ),
+        (
+            Without<FixedCell>,
+            Or<(Changed<DigitValueCell>, Changed<AutoCandidates>)>,
+        ),
+    >,
+    q_conflict: Query<Entity, With<ConflictCell>>,
+    mut commands: Commands,
+) {
+    if lesson.completed {
+        return;
+    }
+    let technique = lesson.technique();
+
+    let mut wrong = false;
+    let mut removed = false;
+    for (position, mut digit, mut auto_candidates, mut cell_mode) in q_cell.iter_mut() {
+        let index = position.0 as usize;
+        if digit.0 != lesson.digits[index] {
+            let correct = digit.0.is_some_and(|digit| {
+                lesson
+                    .placements
+                    .contains(&Candidate::new(index as u8, digit.get()))
+            });
+            if correct {
+                lesson.digits[index] = digit.0;
+                lesson.completed = true;
+            } else {
+                wrong = true;
+                digit.0 = lesson.digits[index];
+                *cell_mode = CellMode::AutoCandidates;
+            }
+            continue;
+        }
+
+        let expected = lesson.candidates[index];
+        if auto_candidates.0 == expected {
+            continue;
+        }
+        let mut accepted = expected;
+        for candidate in expected.without(auto_candidates.0) {
+            let elimination = Candidate::new(index as u8, candidate.get());
+            if let Some(i) = lesson.remaining.iter().position(|c| *c == elimination) {
+                lesson.remaining.swap_remove(i);
+                accepted.remove(candidate.as_set());
+                removed = true;
+            } else {
+                wrong = true;
+            }
+        }
+        // 课程里不需要加回候选数
+        wrong |= !auto_candidates.0.without(expected).is_empty();
+        lesson.candidates[index] = accepted;
+        if auto_candidates.0 != accepted {
+            auto_candidates.0 = accepted;
+        }
+        if !technique.is_placement() && lesson.remaining.is_empty() {
+            lesson.completed = true;
+        }
+    }
+
+    if lesson.completed {
+        if progress.completed.insert(technique) {
+            save_lesson_progress(&progress);
+        }
+        lesson.feedback =
+            Localized::new("Well done! Move on to the next lesson or try another puzzle.");
+    } else if wrong {
+        for entity in q_conflict.iter() {
+            commands.entity(entity).remove::<ConflictCell>();
+        }
+        lesson.feedback = Localized::with_args(
+            "That move doesn't follow from {}. Try again.",
+            [technique.name()],
+        );
+    } else if removed {
+        lesson.feedback =
+            Localized::with_args("Good. {} more to remove.", [lesson.remaining.len()]);
+    }
+}
+
+fn spawn_lesson_panel(
+    font_assets: &Res<FontAssets>,
+    commands: &mut Commands,
+    technique: Technique,
+) {
+    commands
+        .spawn((
+            Name::new("lesson-panel"),
+            LessonPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(16.0),
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            Pickable::IGNORE,
+            GlobalZIndex(50),
+        ))
+        .with_children(|builder| {
+            builder
+                .spawn((
+                    Node {
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        max_width: Val::Px(520.0),
+                        padding: UiRect::axes(Val::Px(20.0), Val::Px(12.0)),
+                        border: UiRect::all(Val::Px(1.0)),
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(8.0)),
+                    ThemedBackground(ThemeRole::Surface),
+                    BorderColor(Color::BLACK.with_alpha(0.2)),
+                ))
+                .with_children(|builder| {
+                    builder.spawn((
+                        LessonTitleText,
+                        Localized::new(technique.name()),
+                        TextFont {
+                            font: font_assets.franklin_600.clone(),
+                            font_size: 18.0,
+                            ..default()
+                        },
+                        ThemedText(ThemeRole::Text),
+                    ));
+                    builder.spawn((
+                        Localized::new(technique.description()),
+                        TextFont {
+                            font: font_assets.franklin_500.clone(),
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextLayout::new_with_justify(JustifyText::Center),
+                        ThemedText(ThemeRole::Text),
+                    ));
+                    builder.spawn((
+                        LessonFeedbackText,
+                        Localized::new(""),
+                        TextFont {
+                            font: font_assets.franklin_600.clone(),
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        ThemedText(ThemeRole::Text),
+                    ));
+                    builder.spawn((
+                        LessonProgressText,
+                        Localized::new(""),
+                        TextFont {
+                            font: font_assets.franklin_500.clone(),
+                            font_size: 12.0,
+                            ..default()
+                        },
+                        ThemedText(ThemeRole::Text),
+                    ));
+
+                    builder
+                        .spawn(Node {
+                            display: Display::Flex,
+                            column_gap: Val::Px(8.0),
+                            margin: UiRect::top(Val::Px(10.0)),
+                            ..default()
+                        })
+                        .with_children(|builder| {
+                            panel_button(font_assets, builder, "Prev", LessonControl::Prev, ());
+                            panel_button(
+                                font_assets,
+                                builder,
+                                "Another",
+                                LessonControl::Another,
+                                (),
+                            );
+                            panel_button(font_assets, builder, "Next", LessonControl::Next, ());
+                            panel_button(font_assets, builder, "Exit", LessonControl::Exit, ());
+                        });
+                });
+        });
+}
+
+fn on_lesson_control(
+    trigger: Trigger<LessonControl>,
+    lesson: Option<Res<ActiveLesson>>,
+    saved: Option<Res<SavedGame>>,
+    q_panel: Query<Entity, With<LessonPanel>>,
+    mut commands: Commands,
+) {
+    let Some(lesson) = lesson else {
+        return;
+    };
+    let count = Technique::ALL.len();
+    match trigger.event() {
+        LessonControl::Prev => commands.trigger(StartLesson {
+            lesson: (lesson.lesson + count - 1) % count,
+            puzzle: 0,
+        }),
+        LessonControl::Next => commands.trigger(StartLesson {
+            lesson: (lesson.lesson + 1) % count,
+            puzzle: 0,
+        }),
+        LessonControl::Another => commands.trigger(StartLesson {
+            lesson: lesson.lesson,
+            puzzle: lesson.puzzle + 1,
+        }),
+        LessonControl::Exit => match saved {
+            Some(saved) => {
+                saved.restore(&mut commands);
+                end_lesson(&mut commands, &q_panel);
+            }
+            // 开新题时会结束课程
+            None => commands.trigger(InitPuzzle),
+        },
+    }
+}
+
+fn update_lesson_panel(
+    lesson: Res<ActiveLesson>,
+    progress: Res<LessonProgress>,
+    mut q_title: Query<&mut Localized, With<LessonTitleText>>,
+    mut q_feedback: Query<&mut Localized, (With<LessonFeedbackText>, Without<LessonTitleText>)>,
+    mut q_progress: Query<
+        &mut Localized,
+        (
+            With<LessonProgressText>,
+            Without<LessonTitleText>,
+            Without<LessonFeedbackText>,
+        ),
+    >,
+) {
+    if !lesson.is_changed() && !progress.is_changed() {
+        return;
+    }
+    let technique = lesson.technique();
+    for mut text in q_title.iter_mut() {
+        *text = Localized::with_args(
+            "Lesson {}: {}{}",
+            [
+                (lesson.lesson + 1).to_string(),
+                technique.name().to_string(),
+                if progress.completed.contains(&technique) {
+                    " (completed)"
+                } else {
+                    ""
+                }
+                .to_string(),
+            ],
+        );
+    }
+    for mut text in q_feedback.iter_mut() {
+        *text = lesson.feedback.clone();
+    }
+    for mut text in q_progress.iter_mut() {
+        *text = Localized::with_args(
+            "Completed {}/{} lessons",
+            [progress.completed.len(), Technique::ALL.len()],
+        );
+    }
+}
+
+fn end_lesson(commands: &mut Commands, q_panel: &Query<Entity, With<LessonPanel>>) {
+    commands.remove_resource::<ActiveLesson>();
+    commands.remove_resource::<SavedGame>();
+    for entity in q_panel.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn stop_lesson(mut commands: Commands, q_panel: Query<Entity, With<LessonPanel>>) {
+    end_lesson(&mut commands, &q_panel);
+}
+
+#[test]
+fn test_lesson_puzzles_use_technique() {
+    for technique in Technique::ALL {
+        for line in technique.puzzles() {
+            let sudoku = Sudoku::from_str_line(line).unwrap();
+            let position = lesson_position(sudoku, technique).unwrap();
+            if technique.is_placement() {
+                assert!(!position.placements.is_empty());
+            } else {
+                assert!(!position.eliminations.is_empty());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_lesson_progress_round_trip() {
+    let progress = LessonProgress {
+        completed: [Technique::FullHouse, Technique::HiddenSingle]
+            .into_iter()
+            .collect(),
+    };
+    let decoded = LessonProgress::decode(&format!("{}bogus\n", progress.encode()));
+    assert_eq!(decoded.completed, progress.completed);
+}
@@ -0,0 +1,172 @@
+use crate::{
+    GameState,
+    game::{
+        GameTimer, NewDigit, PuzzleStarted, ResetPuzzle, Settings, SudokuManager,
+        cell_state::{FixedCell, RevealedCell},
+        coop::RemoteEdits,
+        dialog::ShowGameOver,
+        lessons::ActiveLesson,
+        position::CellPosition,
+        replay::ReplayPlayer,
+        screen_reader::Announce,
+        stats::{GameRecord, GameStats},
+        trial::TrialBranches,
+    },
+    i18n::{Locale, LocalizedFont},
+    loading::FontAssets,
+    theme::{ThemeRole, ThemedText},
+};
+use bevy::prelude::*;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<Mistakes>()
+        .add_systems(
+            Update,
+            update_mistakes_text.run_if(
+                in_state(GameState::Playing).and(
+                    resource_changed::<Mistakes>
+                        .or(resource_changed::<Settings>)
+                        .or(resource_changed::<Locale>),
+                ),
+            ),
+        )
+        .add_observer(count_mistake)
+        .add_observer(on_mistakes_made)
+        .add_observer(
+            |_: Trigger<PuzzleStarted>, mut mistakes: ResMut<Mistakes>| {
+                *mistakes = Mistakes::default();
+            },
+        )
+        .add_observer(|_: Trigger<ResetPuzzle>, mut mistakes: ResMut<Mistakes>| {
+            *mistakes = Mistakes::default();
+        });
+}
+
+/// 可选的严格模式错误次数上限
+pub const MISTAKE_LIMITS: [u32; 3] = [1, 3, 5];
+
+/// 切换到下一档错误次数上限
+pub fn next_mistake_limit(current: u32) -> u32 {
+    MISTAKE_LIMITS
+        .iter()
+        .copied()
+        .find(|limit| *limit > current)
+        .unwrap_or(MISTAKE_LIMITS[0])
+}
+
+/// 当前这局填错的次数
+#[derive(Resource, Debug, Default)]
+pub struct Mistakes {
+    pub count: u32,
+    pub game_over: bool,
+}
+
+#[derive(Component)]
+struct MistakesText;
+
+/// 工具栏上的错误次数
+pub(crate) fn mistakes_counter(
+    font_assets: &Res<FontAssets>,
+    builder: &mut ChildSpawnerCommands<'_>,
+) {
+    builder.spawn((
+        MistakesText,
+        Text::new(""),
+        LocalizedFont::default(),
+        TextFont {
+            font_size: 16.0,
+            font: font_assets.franklin_500.clone(),
+            ..default()
+        },
+        ThemedText(ThemeRole::Text),
+        Node {
+            margin: UiRect::right(Val::Px(16.0)),
+            ..default()
+        },
+        Visibility::Hidden,
+    ));
+}
+
+/// 填错了数字, 试填保留时一次可能带进好几个
+#[derive(Event)]
+pub struct MistakesMade(pub u32);
+
+fn count_mistake(
+    trigger: Trigger<NewDigit>,
+    q_cell: Query<&CellPosition, (Without<FixedCell>, Without<RevealedCell>)>,
+    sudoku_manager: Res<SudokuManager>,
+    trial: Res<TrialBranches>,
+    remote_edits: Res<RemoteEdits>,
+    mut commands: Commands,
+) {
+    // 试填中的数字等保留时再算, 合作时对方填的数字由对方自己算
+    if trial.depth() > 0 || remote_edits.contains(&trigger.target()) {
+        return;
+    }
+    let Ok(position) = q_cell.get(trigger.target()) else {
+        return;
+    };
+    let solution = sudoku_manager.solution.to_bytes();
+    if solution[position.0 as usize] == trigger.event().0.get() {
+        return;
+    }
+    commands.trigger(MistakesMade(1));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn on_mistakes_made(
+    trigger: Trigger<MistakesMade>,
+    settings: Res<Settings>,
+    lesson: Option<Res<ActiveLesson>>,
+    player: Option<Res<ReplayPlayer>>,
+    game_timer: Res<GameTimer>,
+    mut mistakes: ResMut<Mistakes>,
+    mut stats: ResMut<GameStats>,
+    locale: Res<Locale>,
+    mut commands: Commands,
+) {
+    // 不管设置如何都计入得分, 设置只决定是否显示和严格模式
+    if lesson.is_some() || player.is_some() || mistakes.game_over {
+        return;
+    }
+    mistakes.count += trigger.event().0;
+    if settings.strict_mode && mistakes.count >= settings.max_mistakes {
+        mistakes.game_over = true;
+        stats.records.push(GameRecord {
+            won: false,
+            time: game_timer.elapsed(),
+            mistakes: mistakes.count,
+        });
+        commands.trigger(Announce(locale.tr("Game over").to_string()));
+        commands.trigger(ShowGameOver(true));
+    }
+}
+
+fn update_mistakes_text(
+    mistakes: Res<Mistakes>,
+    settings: Res<Settings>,
+    locale: Res<Locale>,
+    mut q_text: Query<(&mut Text, &mut Visibility), With<MistakesText>>,
+) {
+    for (mut text, mut visibility) in q_text.iter_mut() {
+        text.0 = if settings.strict_mode {
+            locale.format(
+                "Mistakes: {}/{}",
+                &[&mistakes.count, &settings.max_mistakes],
+            )
+        } else {
+            locale.format("Mistakes: {}", &[&mistakes.count])
+        };
+        *visibility = if settings.count_mistakes || settings.strict_mode {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+#[test]
+fn test_next_mistake_limit() {
+    assert_eq!(next_mistake_limit(1), 3);
+    assert_eq!(next_mistake_limit(5), 1);
+}
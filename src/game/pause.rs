@@ -0,0 +1,154 @@
+use crate::{
+    GameState,
+    game::{
+        GameTimer, Settings, SudokuManager,
+        dialog::{DialogStack, PauseGame},
+        guided_solve::GuidedSolve,
+        mistakes::Mistakes,
+        replay::ReplayPlayer,
+    },
+    theme::{ThemeRole, ThemedBackground},
+};
+use bevy::{
+    input::{keyboard::KeyboardInput, mouse::MouseButtonInput, mouse::MouseWheel},
+    prelude::*,
+    window::{AppLifecycle, WindowFocused},
+};
+use std::time::Duration;
+
+/// 实际解题时间 (`GameTimer`) 只在下面这些情况都不成立时增加:
+/// 打开了会暂停的弹窗、窗口失去焦点、应用切到后台、闲置超过自动暂停时间
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<IdleTracker>()
+        .add_systems(OnEnter(GameState::Playing), reset_idle)
+        .add_systems(
+            Update,
+            (
+                pause_on_focus_lost,
+                pause_on_suspend,
+                auto_pause_when_idle.run_if(
+                    not(resource_exists::<ReplayPlayer>).and(not(resource_exists::<GuidedSolve>)),
+                ),
+                cover_board_when_paused.run_if(resource_changed::<DialogStack>),
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+}
+
+/// 可选的自动暂停时间, 单位秒
+const AUTO_PAUSE_LIMITS: [Option<u32>; 4] = [None, Some(60), Some(120), Some(300)];
+
+pub fn next_auto_pause(current: Option<u32>) -> Option<u32> {
+    let index = AUTO_PAUSE_LIMITS
+        .iter()
+        .position(|limit| *limit == current)
+        .unwrap_or(0);
+    AUTO_PAUSE_LIMITS[(index + 1) % AUTO_PAUSE_LIMITS.len()]
+}
+
+fn pause_on_focus_lost(mut windows: EventReader<WindowFocused>, mut commands: Commands) {
+    for window in windows.read() {
+        if !window.focused {
+            commands.trigger(PauseGame(true));
+        }
+    }
+}
+
+/// 手机和网页切到后台时暂停
+fn pause_on_suspend(mut lifecycle: EventReader<AppLifecycle>, mut commands: Commands) {
+    for event in lifecycle.read() {
+        if matches!(event, AppLifecycle::WillSuspend | AppLifecycle::Suspended) {
+            commands.trigger(PauseGame(true));
+        }
+    }
+}
+
+/// 最后一次操作的时刻, 按真实时间计
+#[derive(Resource, Debug, Default)]
+struct IdleTracker {
+    last_input: Duration,
+}
+
+fn reset_idle(real_time: Res<Time<Real>>, mut idle: ResMut<IdleTracker>) {
+    idle.last_input = real_time.elapsed();
+}
+
+/// 闲置超时后暂停, 并把闲置的这段时间从解题时间里扣掉
+#[allow(clippy::too_many_arguments)]
+fn auto_pause_when_idle(
+    real_time: Res<Time<Real>>,
+    settings: Res<Settings>,
+    stack: Res<DialogStack>,
+    sudoku_manager: Res<SudokuManager>,
+    mistakes: Res<Mistakes>,
+    mut idle: ResMut<IdleTracker>,
+    mut game_timer: ResMut<GameTimer>,
+    mut keyboard: EventReader<KeyboardInput>,
+    mut mouse_button: EventReader<MouseButtonInput>,
+    mut cursor: EventReader<CursorMoved>,
+    mut wheel: EventReader<MouseWheel>,
+    mut touch: EventReader<TouchInput>,
+    mut commands: Commands,
+) {
+    let had_input = keyboard.read().count()
+        + mouse_button.read().count()
+        + cursor.read().count()
+        + wheel.read().count()
+        + touch.read().count()
+        > 0;
+    let now = real_time.elapsed();
+    if had_input || !stack.is_empty() || sudoku_manager.is_solved || mistakes.game_over {
+        idle.last_input = now;
+        return;
+    }
+    let Some(limit) = settings.auto_pause_after else {
+        return;
+    };
+    // 闲置期间没有弹窗, 解题时间和真实时间走得一样快
+    let idle_time = now.saturating_sub(idle.last_input);
+    if idle_time >= Duration::from_secs(limit as u64) {
+        let elapsed = game_timer.elapsed().saturating_sub(idle_time);
+        game_timer.set_elapsed(elapsed);
+        idle.last_input = now;
+        commands.trigger(PauseGame(true));
+    }
+}
+
+/// 盖住棋盘的遮罩, 暂停时不能偷看题目
+#[derive(Component)]
+pub struct BoardCover;
+
+pub(crate) fn board_cover() -> impl Bundle {
+    (
+        BoardCover,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        ThemedBackground(ThemeRole::Surface),
+        ZIndex(10),
+        Visibility::Hidden,
+    )
+}
+
+fn cover_board_when_paused(
+    stack: Res<DialogStack>,
+    mut q_cover: Query<&mut Visibility, With<BoardCover>>,
+) {
+    for mut visibility in q_cover.iter_mut() {
+        *visibility = if stack.pauses_time() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+#[test]
+fn test_next_auto_pause() {
+    assert_eq!(next_auto_pause(None), Some(60));
+    assert_eq!(next_auto_pause(Some(300)), None);
+    assert_eq!(next_auto_pause(Some(45)), Some(60));
+}
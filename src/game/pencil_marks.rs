@@ -0,0 +1,126 @@
+use crate::{
+    GameState,
+    game::{
+        AutoCandidateMode,
+        auto_candidates::{CandidateElimination, CandidateGrid},
+        cell_state::{
+            CellMode, CornerCandidates, DigitValueCell, FixedCell, ManualCandidates, RevealedCell,
+        },
+        input::board_input_allowed,
+        position::CellPosition,
+    },
+};
+use bevy::prelude::*;
+use sudoku::{bitset::Set, board::Digit};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        pencil_mark_shortcuts.run_if(in_state(GameState::Playing).and(board_input_allowed)),
+    )
+    .add_observer(on_fill_candidates)
+    .add_observer(on_remove_impossible_candidates);
+}
+
+/// 按当前盘面给所有空格填上手动候选数, 快捷键 F
+#[derive(Event)]
+pub struct FillCandidates;
+
+/// 只删掉和已填数字冲突的手动候选数和角标, 快捷键 E
+#[derive(Event)]
+pub struct RemoveImpossibleCandidates;
+
+fn pencil_mark_shortcuts(keyboard_input: Res<ButtonInput<KeyCode>>, mut commands: Commands) {
+    if keyboard_input.just_pressed(KeyCode::KeyF) {
+        commands.trigger(FillCandidates);
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyE) {
+        commands.trigger(RemoveImpossibleCandidates);
+    }
+}
+
+/// 只按行、列、宫里已经填入的数字排除后, 每个格子还能填的数字
+fn possible_digits<'a>(
+    cells: impl IntoIterator<Item = (&'a CellPosition, &'a DigitValueCell)>,
+) -> [Set<Digit>; 81] {
+    let mut grid = CandidateGrid::default();
+    for (position, digit_value) in cells {
+        grid.set_digit(position.0 as usize, digit_value.0);
+    }
+    grid.candidates(CandidateElimination::Singles)
+}
+
+type EditableCell = (Without<FixedCell>, Without<RevealedCell>);
+
+fn on_fill_candidates(
+    _trigger: Trigger<FillCandidates>,
+    q_digit: Query<(&CellPosition, &DigitValueCell)>,
+    mut q_cell: Query<
+        (
+            &CellPosition,
+            &DigitValueCell,
+            &mut ManualCandidates,
+            &mut CellMode,
+        ),
+        EditableCell,
+    >,
+    mut auto_mode: ResMut<AutoCandidateMode>,
+) {
+    let possible = possible_digits(q_digit.iter());
+    // 自动候选模式下看不到手动候选数, 填完切回手动模式
+    if auto_mode.0 {
+        auto_mode.0 = false;
+    }
+    for (position, digit_value, mut manual_candidates, mut cell_mode) in q_cell.iter_mut() {
+        if digit_value.0.is_some() {
+            continue;
+        }
+        manual_candidates.0 = possible[position.0 as usize];
+        if *cell_mode != CellMode::ManualCandidates {
+            *cell_mode = CellMode::ManualCandidates;
+        }
+    }
+}
+
+fn on_remove_impossible_candidates(
+    _trigger: Trigger<RemoveImpossibleCandidates>,
+    q_digit: Query<(&CellPosition, &DigitValueCell)>,
+    mut q_cell: Query<
+        (
+            &CellPosition,
+            &DigitValueCell,
+            &mut ManualCandidates,
+            &mut CornerCandidates,
+        ),
+        EditableCell,
+    >,
+) {
+    let possible = possible_digits(q_digit.iter());
+    for (position, digit_value, mut manual_candidates, mut corner_candidates) in q_cell.iter_mut() {
+        if digit_value.0.is_some() {
+            continue;
+        }
+        let possible = possible[position.0 as usize];
+        let kept = manual_candidates.0 & possible;
+        if kept != manual_candidates.0 {
+            manual_candidates.0 = kept;
+        }
+        let kept = corner_candidates.0 & possible;
+        if kept != corner_candidates.0 {
+            corner_candidates.0 = kept;
+        }
+    }
+}
+
+#[test]
+fn test_possible_digits() {
+    let positions = (0..81).map(CellPosition::new).collect::<Vec<_>>();
+    let digits = (0..81)
+        .map(|index| DigitValueCell((index == 0).then(|| Digit::new(5))))
+        .collect::<Vec<_>>();
+    let possible = possible_digits(positions.iter().zip(digits.iter()));
+    assert_eq!(possible[0], Set::NONE);
+    assert!(!possible[8].contains(Digit::new(5)));
+    assert!(possible[40].contains(Digit::new(5)));
+    assert_eq!(possible[40].len(), 9);
+}
@@ -0,0 +1,108 @@
+use crate::{
+    game::{
+        SudokuManager,
+        cell_state::{AutoCandidates, CellMode, DigitValueCell, FixedCell, ManualCandidates},
+        position::CellPosition,
+        scoring::Assists,
+    },
+    i18n::Locale,
+    share::ShowToast,
+};
+use bevy::prelude::*;
+use sudoku::{Sudoku, bitset::Set, board::Digit};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_observer(on_check_progress);
+}
+
+/// 只告诉玩家还有几个格子和答案不符, 不标出是哪几个
+#[derive(Event)]
+pub struct CheckProgress;
+
+/// 一个格子当前显示的内容
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum CellEntry {
+    Digit(Digit),
+    Candidates(Set<Digit>),
+}
+
+/// 填错数字, 或者候选数里已经排除了正确答案的格子数
+///
+/// 还没有标候选数的空格不算
+pub(crate) fn off_track_cells(
+    solution: &Sudoku,
+    cells: impl IntoIterator<Item = (CellPosition, CellEntry)>,
+) -> usize {
+    let solution = solution.to_bytes();
+    cells
+        .into_iter()
+        .filter(|(position, entry)| {
+            let answer = Digit::new(solution[position.0 as usize]);
+            match entry {
+                CellEntry::Digit(digit) => *digit != answer,
+                CellEntry::Candidates(candidates) => {
+                    !candidates.is_empty() && !candidates.contains(answer)
+                }
+            }
+        })
+        .count()
+}
+
+fn on_check_progress(
+    _trigger: Trigger<CheckProgress>,
+    q_cell: Query<
+        (
+            &CellPosition,
+            &DigitValueCell,
+            &ManualCandidates,
+            &AutoCandidates,
+            &CellMode,
+        ),
+        Without<FixedCell>,
+    >,
+    sudoku_manager: Res<SudokuManager>,
+    locale: Res<Locale>,
+    mut assists: ResMut<Assists>,
+    mut commands: Commands,
+) {
+    assists.progress_checks += 1;
+    let cells = q_cell.iter().map(|(position, digit, manual, auto, mode)| {
+        let entry = match (digit.0, mode) {
+            (Some(digit), _) => CellEntry::Digit(digit),
+            (None, CellMode::AutoCandidates) => CellEntry::Candidates(auto.0),
+            (None, _) => CellEntry::Candidates(manual.0),
+        };
+        (*position, entry)
+    });
+    let message = match off_track_cells(&sudoku_manager.solution, cells) {
+        0 => locale.tr("You're on track").to_string(),
+        1 => locale.tr("1 cell doesn't match the solution").to_string(),
+        count => locale.format("{} cells don't match the solution", &[&count]),
+    };
+    commands.trigger(ShowToast(message));
+}
+
+#[test]
+fn test_off_track_cells() {
+    let solution = Sudoku::generate_solved();
+    let bytes = solution.to_bytes();
+    let answer = |cell: u8| Digit::new(bytes[cell as usize]);
+    let other = |cell: u8| Digit::new(bytes[cell as usize] % 9 + 1);
+
+    let cells = [
+        (CellPosition(0), CellEntry::Digit(answer(0))),
+        (CellPosition(1), CellEntry::Candidates(Set::NONE)),
+        (
+            CellPosition(2),
+            CellEntry::Candidates(answer(2).as_set() | other(2).as_set()),
+        ),
+    ];
+    assert_eq!(off_track_cells(&solution, cells), 0);
+
+    let cells = [
+        (CellPosition(0), CellEntry::Digit(other(0))),
+        (CellPosition(1), CellEntry::Candidates(other(1).as_set())),
+        (CellPosition(2), CellEntry::Candidates(answer(2).as_set())),
+    ];
+    assert_eq!(off_track_cells(&solution, cells), 2);
+}
@@ -0,0 +1,758 @@
+use crate::{
+    GameState,
+    game::{
+        DigitCellContainer, Game, GameTimer,
+        board::play_board,
+        cell_state::{
+            AutoCandidateCellMarker, AutoCandidates, CandidatesValue, CellMode, CellValueBundle,
+            ConflictCell, CornerCandidateSlot, CornerCandidates, CorrectionCell, DigitValueCell,
+            FixedCell, ManualCandidateCellMarker, ManualCandidates, RevealedCell, SelectedCell,
+            SqueezedCandidate,
+        },
+        cleanup_game, correct_cells,
+        dialog::dialog_child_body,
+        guided_solve::panel_button,
+        layout::{BoardLayout, BoardRoot, apply_layout},
+        position::CellPosition,
+    },
+    i18n::Localized,
+    loading::{FontAssets, TextureAssets},
+    share::title_bar,
+    theme::{ThemeRole, ThemedBackground, ThemedText},
+};
+use bevy::{
+    input::gamepad::{Gamepad, GamepadButton},
+    prelude::*,
+    time::Stopwatch,
+    window::PrimaryWindow,
+};
+use sudoku::{Sudoku, bitset::Set, board::Digit};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::Race), setup_race)
+        .add_systems(OnExit(GameState::Race), (cleanup_game, stop_race))
+        .add_systems(
+            Update,
+            (
+                (keyboard_race_input, gamepad_race_input).run_if(race_running),
+                update_race_progress,
+                update_progress_bars,
+            )
+                .chain()
+                .run_if(in_state(GameState::Race).and(resource_exists::<RaceMatch>)),
+        )
+        .add_systems(
+            PostUpdate,
+            fit_race_boards.after(apply_layout).run_if(
+                in_state(GameState::Race).and(
+                    resource_changed::<BoardLayout>
+                        .or(any_match_filter::<Added<BoardRoot>>)
+                        .or(any_match_filter::<Changed<SqueezedCandidate>>),
+                ),
+            ),
+        )
+        .add_observer(start_race)
+        .add_observer(on_race_input)
+        .add_observer(on_race_finished)
+        .add_observer(on_race_control);
+}
+
+/// 对战中的玩家, 同时挂在棋盘和它的每个格子上
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Player {
+    One,
+    Two,
+}
+
+impl Player {
+    pub const ALL: [Player; 2] = [Player::One, Player::Two];
+
+    fn index(&self) -> usize {
+        match self {
+            Player::One => 0,
+            Player::Two => 1,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Player::One => "Player 1",
+            Player::Two => "Player 2",
+        }
+    }
+
+    fn keys(&self) -> &'static KeyScheme {
+        match self {
+            Player::One => &PLAYER_ONE_KEYS,
+            Player::Two => &PLAYER_TWO_KEYS,
+        }
+    }
+
+    fn help(&self) -> &'static str {
+        match self {
+            Player::One => "WASD move · 1-9 digit · hold Shift for candidates · Q clear",
+            Player::Two => {
+                "Arrows move · numpad digit · hold Enter for candidates · 0 clear · or gamepad"
+            }
+        }
+    }
+}
+
+/// 一个玩家的一次操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaceAction {
+    /// 按行、列移动选中格子
+    Move(i8, i8),
+    Digit(u8),
+    Candidate(u8),
+    Clear,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct RaceInput {
+    pub player: Player,
+    pub action: RaceAction,
+}
+
+/// 每个玩家占半边键盘
+struct KeyScheme {
+    up: KeyCode,
+    down: KeyCode,
+    left: KeyCode,
+    right: KeyCode,
+    digits: [KeyCode; 9],
+    /// 按住时输入候选数
+    candidate: KeyCode,
+    clear: KeyCode,
+}
+
+const PLAYER_ONE_KEYS: KeyScheme = KeyScheme {
+    up: KeyCode::KeyW,
+    down: KeyCode::KeyS,
+    left: KeyCode::KeyA,
+    right: KeyCode::KeyD,
+    digits: [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ],
+    candidate: KeyCode::ShiftLeft,
+    clear: KeyCode::KeyQ,
+};
+
+const PLAYER_TWO_KEYS: KeyScheme = KeyScheme {
+    up: KeyCode::ArrowUp,
+    down: KeyCode::ArrowDown,
+    left: KeyCode::ArrowLeft,
+    right: KeyCode::ArrowRight,
+    digits: [
+        KeyCode::Numpad1,
+        KeyCode::Numpad2,
+        KeyCode::Numpad3,
+        KeyCode::Numpad4,
+        KeyCode::Numpad5,
+        KeyCode::Numpad6,
+        KeyCode::Numpad7,
+        KeyCode::Numpad8,
+        KeyCode::Numpad9,
+    ],
+    candidate: KeyCode::NumpadEnter,
+    clear: KeyCode::Numpad0,
+};
+
+impl KeyScheme {
+    fn action(&self, keys: &ButtonInput<KeyCode>) -> Option<RaceAction> {
+        let moves = [
+            (self.up, -1, 0),
+            (self.down, 1, 0),
+            (self.left, 0, -1),
+            (self.right, 0, 1),
+        ];
+        for (key, row, col) in moves {
+            if keys.just_pressed(key) {
+                return Some(RaceAction::Move(row, col));
+            }
+        }
+        if keys.just_pressed(self.clear) {
+            return Some(RaceAction::Clear);
+        }
+        let digit = (1..=9u8).find(|digit| keys.just_pressed(self.digits[*digit as usize - 1]))?;
+        if keys.pressed(self.candidate) {
+            Some(RaceAction::Candidate(digit))
+        } else {
+            Some(RaceAction::Digit(digit))
+        }
+    }
+}
+
+/// 当前这场对战
+#[derive(Resource, Debug)]
+pub struct RaceMatch {
+    pub solution: Sudoku,
+    /// 每个玩家填对的格子数
+    pub progress: [usize; 2],
+    pub winner: Option<Player>,
+    /// 手柄用肩键选择要填的数字
+    pub pad_digit: u8,
+}
+
+fn race_running(race: Res<RaceMatch>) -> bool {
+    race.winner.is_none()
+}
+
+/// 重新出一道题, 两块棋盘同时开始
+#[derive(Event)]
+pub struct StartRace;
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaceControl {
+    Rematch,
+    Menu,
+}
+
+/// 每块棋盘的外层, 用来给格子找到所属的玩家
+#[derive(Component)]
+struct RaceBoard(Player);
+
+#[derive(Component)]
+struct RaceProgressText(Player);
+
+#[derive(Component)]
+struct RaceProgressFill(Player);
+
+#[derive(Component)]
+struct RaceTimerText;
+
+#[derive(Component)]
+struct RaceWinnerContainer;
+
+fn setup_race(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    texture_assets: Res<TextureAssets>,
+) {
+    commands.spawn((Game, Camera2d));
+    commands.insert_resource(GameTimer(Stopwatch::new()));
+    commands
+        .spawn((
+            Game,
+            Name::new("race-content"),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ThemedBackground(ThemeRole::Surface),
+        ))
+        .with_children(|builder| {
+            title_bar(&font_assets, builder);
+
+            builder.spawn((
+                RaceTimerText,
+                Text::new("00:00:00"),
+                TextFont {
+                    font_size: 16.0,
+                    font: font_assets.franklin_500.clone(),
+                    ..default()
+                },
+                ThemedText(ThemeRole::Text),
+                Node {
+                    align_self: AlignSelf::Center,
+                    ..default()
+                },
+            ));
+
+            builder
+                .spawn((
+                    Name::new("race-boards"),
+                    Node {
+                        display: Display::Flex,
+                        justify_content: JustifyContent::Center,
+                        column_gap: Val::Px(40.0),
+                        padding: UiRect::all(Val::Px(13.0)),
+                        ..default()
+                    },
+                ))
+                .with_children(|builder| {
+                    for player in Player::ALL {
+                        builder
+                            .spawn((
+                                RaceBoard(player),
+                                Node {
+                                    display: Display::Flex,
+                                    flex_direction: FlexDirection::Column,
+                                    ..default()
+                                },
+                            ))
+                            .with_children(|builder| {
+                                race_progress(&font_assets, builder, player);
+                                play_board(&font_assets, &texture_assets, builder);
+                            });
+                    }
+                });
+        });
+
+    commands.trigger(StartRace);
+}
+
+/// 棋盘上方的玩家名、进度条和按键说明
+fn race_progress(
+    font_assets: &Res<FontAssets>,
+    builder: &mut ChildSpawnerCommands<'_>,
+    player: Player,
+) {
+    builder.spawn((
+        RaceProgressText(player),
+        Localized::new(player.label()),
+        TextFont {
+            font_size: 16.0,
+            font: font_assets.franklin_700.clone(),
+            ..default()
+        },
+        ThemedText(ThemeRole::Text),
+    ));
+    builder
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(8.0),
+                margin: UiRect::vertical(Val::Px(4.0)),
+                ..default()
+            },
+            BorderRadius::all(Val::Px(4.0)),
+            ThemedBackground(ThemeRole::Border),
+        ))
+        .with_child((
+            RaceProgressFill(player),
+            Node {
+                width: Val::Percent(0.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            BorderRadius::all(Val::Px(4.0)),
+            ThemedBackground(ThemeRole::Button),
+        ));
+    builder.spawn((
+        Localized::new(player.help()),
+        TextFont {
+            font_size: 12.0,
+            font: font_assets.franklin_500.clone(),
+            ..default()
+        },
+        ThemedText(ThemeRole::SecondaryText),
+        Node {
+            margin: UiRect::bottom(Val::Px(8.0)),
+            ..default()
+        },
+    ));
+}
+
+/// 两块棋盘放同一道题, 每块各自选中第一个格子
+fn start_race(
+    _trigger: Trigger<StartRace>,
+    q_cell: Query<(Entity, &CellPosition)>,
+    q_board: Query<&RaceBoard>,
+    q_parent: Query<&ChildOf>,
+    q_winner: Query<Entity, With<RaceWinnerContainer>>,
+    mut commands: Commands,
+) {
+    let (sudoku, solution) = loop {
+        let sudoku = Sudoku::generate();
+        if let Some(solution) = sudoku.solution() {
+            break (sudoku, solution);
+        }
+    };
+    info!("race sudoku: {:?}", sudoku);
+
+    for entity in q_winner.iter() {
+        commands.entity(entity).despawn();
+    }
+    commands.insert_resource(GameTimer(Stopwatch::new()));
+    commands.insert_resource(RaceMatch {
+        solution,
+        progress: [0; 2],
+        winner: None,
+        pad_digit: 1,
+    });
+
+    let bytes = sudoku.to_bytes();
+    for (entity, position) in q_cell.iter() {
+        let Some(player) = q_parent
+            .iter_ancestors(entity)
+            .find_map(|ancestor| q_board.get(ancestor).ok())
+        else {
+            continue;
+        };
+        let given = bytes[position.0 as usize];
+        let bundle = CellValueBundle {
+            digit_value: DigitValueCell((given != 0).then(|| Digit::new(given))),
+            auto_candidates: AutoCandidates(Set::NONE),
+            manual_candidates: ManualCandidates(Set::NONE),
+            corner_candidates: CornerCandidates(Set::NONE),
+            cell_mode: if given != 0 {
+                CellMode::Digit
+            } else {
+                CellMode::ManualCandidates
+            },
+        };
+        let mut cell = commands.entity(entity);
+        cell.insert((bundle, player.0))
+            .remove::<(SelectedCell, ConflictCell, CorrectionCell, RevealedCell)>();
+        if given != 0 {
+            cell.insert(FixedCell);
+        } else {
+            cell.remove::<FixedCell>();
+        }
+        if position.0 == 0 {
+            cell.insert(SelectedCell);
+        }
+    }
+}
+
+fn keyboard_race_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Menu);
+        return;
+    }
+    for player in Player::ALL {
+        if let Some(action) = player.keys().action(&keys) {
+            commands.trigger(RaceInput { player, action });
+        }
+    }
+}
+
+/// 手柄控制第二个玩家: 方向键移动, 肩键选数字, A 填数, X 候选数, B 清除
+fn gamepad_race_input(
+    q_gamepad: Query<&Gamepad>,
+    mut race: ResMut<RaceMatch>,
+    mut commands: Commands,
+) {
+    let Some(gamepad) = q_gamepad.iter().next() else {
+        return;
+    };
+    if gamepad.just_pressed(GamepadButton::LeftTrigger) {
+        race.pad_digit = if race.pad_digit == 1 {
+            9
+        } else {
+            race.pad_digit - 1
+        };
+    }
+    if gamepad.just_pressed(GamepadButton::RightTrigger) {
+        race.pad_digit = race.pad_digit % 9 + 1;
+    }
+
+    let digit = race.pad_digit;
+    let actions = [
+        (GamepadButton::DPadUp, RaceAction::Move(-1, 0)),
+        (GamepadButton::DPadDown, RaceAction::Move(1, 0)),
+        (GamepadButton::DPadLeft, RaceAction::Move(0, -1)),
+        (GamepadButton::DPadRight, RaceAction::Move(0, 1)),
+        (GamepadButton::South, RaceAction::Digit(digit)),
+        (GamepadButton::West, RaceAction::Candidate(digit)),
+        (GamepadButton::East, RaceAction::Clear),
+    ];
+    for (button, action) in actions {
+        if gamepad.just_pressed(button) {
+            commands.trigger(RaceInput {
+                player: Player::Two,
+                action,
+            });
+        }
+    }
+}
+
+fn on_race_input(
+    trigger: Trigger<RaceInput>,
+    mut q_cell: Query<(
+        Entity,
+        &Player,
+        &CellPosition,
+        &mut DigitValueCell,
+        &mut ManualCandidates,
+        &mut CellMode,
+        Has<FixedCell>,
+    )>,
+    q_selected: Query<(Entity, &Player, &CellPosition), With<SelectedCell>>,
+    mut commands: Commands,
+) {
+    let RaceInput { player, action } = *trigger.event();
+    let Some((entity, _, position)) = q_selected.iter().find(|(_, owner, _)| **owner == player)
+    else {
+        return;
+    };
+
+    if let RaceAction::Move(row, col) = action {
+        let row = (position.row() as i8 + row).clamp(0, 8) as u8;
+        let col = (position.col() as i8 + col).clamp(0, 8) as u8;
+        let target = CellPosition::from_row_col(row, col);
+        if let Some((other, ..)) = q_cell
+            .iter()
+            .find(|(_, owner, other, ..)| **owner == player && **other == target)
+            && other != entity
+        {
+            commands.entity(entity).remove::<SelectedCell>();
+            commands.entity(other).insert(SelectedCell);
+        }
+        return;
+    }
+
+    let Ok((.., mut digit_value, mut candidates, mut cell_mode, false)) = q_cell.get_mut(entity)
+    else {
+        return;
+    };
+    match action {
+        RaceAction::Digit(digit) => {
+            digit_value.0 = Some(Digit::new(digit));
+            *cell_mode = CellMode::Digit;
+        }
+        RaceAction::Candidate(digit) => {
+            if *cell_mode == CellMode::Digit {
+                digit_value.0 = None;
+                *cell_mode = CellMode::ManualCandidates;
+            }
+            candidates.insert(Digit::new(digit));
+        }
+        RaceAction::Clear => {
+            digit_value.0 = None;
+            candidates.0 = Set::NONE;
+            *cell_mode = CellMode::ManualCandidates;
+        }
+        RaceAction::Move(..) => {}
+    }
+}
+
+/// 和 check_solver 用同一个标准, 先填满 81 格的玩家获胜
+fn update_race_progress(
+    q_cell: Query<(&DigitValueCell, &CellPosition, &Player)>,
+    mut race: ResMut<RaceMatch>,
+    mut game_timer: ResMut<GameTimer>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    if race.winner.is_some() {
+        return;
+    }
+    game_timer.tick(time.delta());
+
+    let progress = Player::ALL.map(|player| {
+        correct_cells(
+            &race.solution,
+            q_cell
+                .iter()
+                .filter(|(.., owner)| **owner == player)
+                .map(|(digit, position, _)| (digit, position)),
+        )
+    });
+    if race.progress != progress {
+        race.progress = progress;
+    }
+
+    if let Some(winner) = Player::ALL
+        .into_iter()
+        .find(|player| progress[player.index()] == 81)
+    {
+        race.winner = Some(winner);
+        commands.trigger(RaceFinished(winner));
+    }
+}
+
+fn update_progress_bars(
+    race: Res<RaceMatch>,
+    game_timer: Res<GameTimer>,
+    q_gamepad: Query<(), With<Gamepad>>,
+    mut q_text: Query<(&mut Localized, &RaceProgressText)>,
+    mut q_fill: Query<(&mut Node, &RaceProgressFill)>,
+    mut q_timer: Query<&mut Text, With<RaceTimerText>>,
+) {
+    for mut text in q_timer.iter_mut() {
+        text.0 = game_timer.to_string();
+    }
+    if !race.is_changed() {
+        return;
+    }
+    for (mut text, RaceProgressText(player)) in q_text.iter_mut() {
+        let count = race.progress[player.index()];
+        *text = if *player == Player::Two && !q_gamepad.is_empty() {
+            Localized::with_args(
+                "{}  {}/81  (pad digit {})",
+                [
+                    player.label().to_string(),
+                    count.to_string(),
+                    race.pad_digit.to_string(),
+                ],
+            )
+        } else {
+            Localized::with_args("{}  {}/81", [player.label().to_string(), count.to_string()])
+        };
+    }
+    for (mut node, RaceProgressFill(player)) in q_fill.iter_mut() {
+        node.width = Val::Percent(race.progress[player.index()] as f32 / 81.0 * 100.0);
+    }
+}
+
+/// 两块棋盘并排, 按窗口大小缩小棋盘和字号
+fn fit_race_boards(
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut q_board: Query<&mut Node, With<BoardRoot>>,
+    mut q_digit: Query<&mut TextFont, With<DigitCellContainer>>,
+    mut q_candidate: Query<
+        (&mut TextFont, &SqueezedCandidate),
+        (
+            Or<(
+                With<AutoCandidateCellMarker>,
+                With<ManualCandidateCellMarker>,
+            )>,
+            Without<DigitCellContainer>,
+        ),
+    >,
+    mut q_corner: Query<
+        &mut TextFont,
+        (
+            With<CornerCandidateSlot>,
+            Without<DigitCellContainer>,
+            Without<AutoCandidateCellMarker>,
+            Without<ManualCandidateCellMarker>,
+        ),
+    >,
+) {
+    let layout = BoardLayout::for_race(window.width(), window.height());
+    for mut node in q_board.iter_mut() {
+        node.width = Val::Px(layout.board_size());
+    }
+    for mut font in q_digit.iter_mut() {
+        font.font_size = layout.digit_font_size();
+    }
+    for (mut font, squeezed) in q_candidate.iter_mut() {
+        font.font_size = layout.centre_font_size(squeezed.0);
+    }
+    for mut font in q_corner.iter_mut() {
+        font.font_size = layout.corner_font_size();
+    }
+}
+
+/// 有玩家先解出了
+#[derive(Event)]
+pub struct RaceFinished(pub Player);
+
+fn on_race_finished(
+    trigger: Trigger<RaceFinished>,
+    race_root: Single<Entity, (With<Game>, With<Node>)>,
+    game_timer: Res<GameTimer>,
+    race: Res<RaceMatch>,
+    font_assets: Res<FontAssets>,
+    mut commands: Commands,
+) {
+    let winner = trigger.event().0;
+    let loser = Player::ALL
+        .into_iter()
+        .find(|player| *player != winner)
+        .unwrap_or(winner);
+    commands.entity(*race_root).with_children(|builder| {
+        builder
+            .spawn((
+                RaceWinnerContainer,
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                GlobalZIndex(999),
+            ))
+            .with_children(|builder| {
+                builder
+                    .spawn(dialog_child_body("Race finished"))
+                    .with_children(|builder| {
+                        builder.spawn((
+                            Localized::with_args("{} wins!", [winner.label()]),
+                            TextFont {
+                                font_size: 36.0,
+                                font: font_assets.karnak.clone(),
+                                ..default()
+                            },
+                            ThemedText(ThemeRole::Text),
+                        ));
+                        builder.spawn((
+                            Localized::with_args(
+                                "Solved in {}. {} had {}/81 cells.",
+                                [
+                                    game_timer.to_string(),
+                                    loser.label().to_string(),
+                                    race.progress[loser.index()].to_string(),
+                                ],
+                            ),
+                            TextFont {
+                                font_size: 16.0,
+                                font: font_assets.franklin_500.clone(),
+                                ..default()
+                            },
+                            ThemedText(ThemeRole::Text),
+                            Node {
+                                margin: UiRect::vertical(Val::Px(24.0)),
+                                ..default()
+                            },
+                        ));
+                        builder
+                            .spawn(Node {
+                                column_gap: Val::Px(12.0),
+                                ..default()
+                            })
+                            .with_children(|builder| {
+                                panel_button(
+                                    &font_assets,
+                                    builder,
+                                    "Rematch",
+                                    RaceControl::Rematch,
+                                    (),
+                                );
+                                panel_button(&font_assets, builder, "Menu", RaceControl::Menu, ());
+                            });
+                    });
+            });
+    });
+}
+
+fn on_race_control(
+    trigger: Trigger<RaceControl>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    match trigger.event() {
+        RaceControl::Rematch => commands.trigger(StartRace),
+        RaceControl::Menu => next_state.set(GameState::Menu),
+    }
+}
+
+fn stop_race(mut commands: Commands) {
+    commands.remove_resource::<RaceMatch>();
+}
+
+#[test]
+fn test_key_scheme_action() {
+    let mut keys = ButtonInput::<KeyCode>::default();
+    keys.press(KeyCode::KeyW);
+    assert_eq!(PLAYER_ONE_KEYS.action(&keys), Some(RaceAction::Move(-1, 0)));
+    assert_eq!(PLAYER_TWO_KEYS.action(&keys), None);
+
+    keys.clear();
+    keys.press(KeyCode::NumpadEnter);
+    keys.press(KeyCode::Numpad7);
+    assert_eq!(
+        PLAYER_TWO_KEYS.action(&keys),
+        Some(RaceAction::Candidate(7))
+    );
+}
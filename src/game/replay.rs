@@ -0,0 +1,827 @@
+use crate::{
+    GameState,
+    game::{
+        AutoCandidateMode, CheckCell, CheckPuzzle, CleanCell, FindHint, GameTimer, InitPuzzle,
+        NewCandidate, NewCornerCandidate, NewDigit, PuzzleStarted, ResetPuzzle, RevealCell,
+        SudokuManager,
+        cell_state::{AutoCandidates, CorrectionCell, DigitValueCell, FixedCell, SelectedCell},
+        control_tab::{ToggleCornerTab, ToggleTab},
+        coop::RemoteEdits,
+        guided_solve::panel_button,
+        hint_cell, load_sudoku,
+        pencil_marks::{FillCandidates, RemoveImpossibleCandidates},
+        position::CellPosition,
+        scoring::PuzzleSolved,
+        trial::{TrialBranches, TrialControl},
+        undo::{Undo, UndoHistory},
+    },
+    i18n::Localized,
+    loading::FontAssets,
+    theme::{ThemeRole, ThemedBackground, ThemedText},
+};
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+use std::time::Duration;
+use sudoku::Sudoku;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<ReplayRecorder>()
+        .add_systems(
+            Update,
+            record_auto_mode.run_if(
+                in_state(GameState::Playing)
+                    .and(resource_changed::<AutoCandidateMode>)
+                    .and(not(resource_exists::<ReplayPlayer>)),
+            ),
+        )
+        .add_systems(
+            Update,
+            (play_replay, update_replay_panel)
+                .chain()
+                .run_if(in_state(GameState::Playing).and(resource_exists::<ReplayPlayer>)),
+        )
+        .add_systems(Last, forget_derived)
+        .add_systems(OnExit(GameState::Playing), stop_replay)
+        .add_observer(record_select)
+        .add_observer(record_digit)
+        .add_observer(record_candidate)
+        .add_observer(record_corner_candidate)
+        .add_observer(record_clear)
+        .add_observer(record_tab)
+        .add_observer(record_corner_tab)
+        .add_observer(record_fill_candidates)
+        .add_observer(record_remove_impossible)
+        .add_observer(record_hint)
+        .add_observer(record_check_cell)
+        .add_observer(record_check_puzzle)
+        .add_observer(record_reveal)
+        .add_observer(record_trial)
+        .add_observer(record_undo)
+        .add_observer(save_replay)
+        .add_observer(on_watch_replay)
+        .add_observer(on_replay_control)
+        .add_observer(
+            |_: Trigger<InitPuzzle>,
+             mut commands: Commands,
+             q_panel: Query<Entity, With<ReplayPanel>>| {
+                end_replay(&mut commands, &q_panel);
+            },
+        )
+        .add_observer(
+            |_: Trigger<PuzzleStarted>, mut recorder: ResMut<ReplayRecorder>| {
+                recorder.actions.clear();
+            },
+        )
+        .add_observer(
+            |_: Trigger<ResetPuzzle>, mut recorder: ResMut<ReplayRecorder>| {
+                recorder.actions.clear();
+            },
+        );
+}
+
+/// 玩家的一次操作, 格子用 0..81 的序号表示
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayAction {
+    Select(u8),
+    Digit(u8, u8),
+    Candidate(u8, u8),
+    Corner(u8, u8),
+    Clear(u8),
+    AutoMode(bool),
+    ToggleTab,
+    ToggleCornerTab,
+    FillCandidates,
+    RemoveImpossible,
+    Hint,
+    CheckCell(u8),
+    CheckPuzzle,
+    Reveal(u8),
+    TrialStart,
+    TrialCommit,
+    TrialRevert,
+    Undo,
+}
+
+/// 一局的回放: 题目和带时间戳 (毫秒) 的操作
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub puzzle: Sudoku,
+    pub actions: Vec<(u32, ReplayAction)>,
+}
+
+impl Replay {
+    /// 第一行是题目, 第二行每个操作形如 `1520:d405`
+    pub fn encode(&self) -> String {
+        let actions = self
+            .actions
+            .iter()
+            .map(|(ms, action)| {
+                let action = match action {
+                    ReplayAction::Select(cell) => format!("s{:02}", cell),
+                    ReplayAction::Digit(cell, digit) => format!("d{:02}{}", cell, digit),
+                    ReplayAction::Candidate(cell, digit) => format!("c{:02}{}", cell, digit),
+                    ReplayAction::Corner(cell, digit) => format!("m{:02}{}", cell, digit),
+                    ReplayAction::Clear(cell) => format!("x{:02}", cell),
+                    ReplayAction::AutoMode(auto) => format!("a{}", *auto as u8),
+                    ReplayAction::ToggleTab => "t".to_string(),
+                    ReplayAction::ToggleCornerTab => "q".to_string(),
+                    ReplayAction::FillCandidates => "f".to_string(),
+                    ReplayAction::RemoveImpossible => "e".to_string(),
+                    ReplayAction::Hint => "h".to_string(),
+                    ReplayAction::CheckCell(cell) => format!("k{:02}", cell),
+                    ReplayAction::CheckPuzzle => "p".to_string(),
+                    ReplayAction::Reveal(cell) => format!("r{:02}", cell),
+                    ReplayAction::TrialStart => "b".to_string(),
+                    ReplayAction::TrialCommit => "o".to_string(),
+                    ReplayAction::TrialRevert => "u".to_string(),
+                    ReplayAction::Undo => "z".to_string(),
+                };
+                format!("{}:{}", ms, action)
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("{}\n{}\n", self.puzzle.to_str_line(), actions)
+    }
+
+    pub fn decode(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        let puzzle = Sudoku::from_str_line(lines.next()?).ok()?;
+        let mut actions = vec![];
+        for token in lines.next().unwrap_or_default().split_whitespace() {
+            let (ms, action) = token.split_once(':')?;
+            let ms = ms.parse().ok()?;
+            let (code, args) = action.split_at_checked(1)?;
+            let cell = || args.get(..2)?.parse::<u8>().ok().filter(|cell| *cell < 81);
+            let digit = || {
+                args.get(2..3)?
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|digit| (1..=9).contains(digit))
+            };
+            let action = match code {
+                "s" => ReplayAction::Select(cell()?),
+                "d" => ReplayAction::Digit(cell()?, digit()?),
+                "c" => ReplayAction::Candidate(cell()?, digit()?),
+                "m" => ReplayAction::Corner(cell()?, digit()?),
+                "x" => ReplayAction::Clear(cell()?),
+                "a" => ReplayAction::AutoMode(args == "1"),
+                "t" => ReplayAction::ToggleTab,
+                "q" => ReplayAction::ToggleCornerTab,
+                "f" => ReplayAction::FillCandidates,
+                "e" => ReplayAction::RemoveImpossible,
+                "h" => ReplayAction::Hint,
+                "k" => ReplayAction::CheckCell(cell()?),
+                "p" => ReplayAction::CheckPuzzle,
+                "r" => ReplayAction::Reveal(cell()?),
+                "b" => ReplayAction::TrialStart,
+                "o" => ReplayAction::TrialCommit,
+                "u" => ReplayAction::TrialRevert,
+                "z" => ReplayAction::Undo,
+                _ => return None,
+            };
+            actions.push((ms, action));
+        }
+        Some(Self { puzzle, actions })
+    }
+
+    fn duration(&self) -> u32 {
+        self.actions.last().map_or(0, |(ms, _)| *ms)
+    }
+}
+
+/// 记录当前这局的操作
+#[derive(Resource, Debug, Default)]
+pub struct ReplayRecorder {
+    actions: Vec<(u32, ReplayAction)>,
+    /// 别的操作接着带出的操作, 回放时会再次带出, 不再单独记录
+    derived: Vec<ReplayAction>,
+}
+
+/// 最近一次完成的对局回放
+#[derive(Resource, Debug, Clone)]
+pub struct LastReplay(pub Replay);
+
+type RecordParams<'w, 's> = (
+    ResMut<'w, ReplayRecorder>,
+    Res<'w, GameTimer>,
+    Option<Res<'w, ReplayPlayer>>,
+    Query<'w, 's, &'static CellPosition>,
+);
+
+type HintCells<'w, 's> =
+    Query<'w, 's, (Entity, &'static AutoCandidates, &'static DigitValueCell), Without<FixedCell>>;
+
+fn record(
+    (mut recorder, game_timer, player, _): RecordParams,
+    action: impl FnOnce() -> Option<ReplayAction>,
+) {
+    // 回放时不记录
+    if player.is_some() {
+        return;
+    }
+    if let Some(action) = action() {
+        if let Some(index) = recorder.derived.iter().position(|other| *other == action) {
+            recorder.derived.remove(index);
+            return;
+        }
+        let ms = game_timer.elapsed().as_millis() as u32;
+        recorder.actions.push((ms, action));
+    }
+}
+
+/// 记下 `action`, 并跳过它随后带出的 `derived`
+fn record_with_derived(
+    mut params: RecordParams,
+    action: Option<ReplayAction>,
+    derived: Option<ReplayAction>,
+) {
+    if params.2.is_none()
+        && let Some(derived) = derived
+    {
+        params.0.derived.push(derived);
+    }
+    record(params, || action);
+}
+
+/// 带出的操作都在同一帧里发生, 没等到的不再跳过
+fn forget_derived(mut recorder: ResMut<ReplayRecorder>) {
+    if !recorder.derived.is_empty() {
+        recorder.derived.clear();
+    }
+}
+
+fn cell_index(q_cell: &Query<&CellPosition>, entity: Entity) -> Option<u8> {
+    q_cell.get(entity).ok().map(|position| position.0)
+}
+
+fn record_select(trigger: Trigger<OnInsert, SelectedCell>, params: RecordParams) {
+    let cell = cell_index(&params.3, trigger.target());
+    record(params, || cell.map(ReplayAction::Select));
+}
+
+fn record_digit(trigger: Trigger<NewDigit>, params: RecordParams, remote_edits: Res<RemoteEdits>) {
+    // 合作时对方的操作记在对方的回放里
+    if remote_edits.contains(&trigger.target()) {
+        return;
+    }
+    let cell = cell_index(&params.3, trigger.target());
+    let digit = trigger.event().0.get();
+    record(params, || cell.map(|cell| ReplayAction::Digit(cell, digit)));
+}
+
+fn record_candidate(
+    trigger: Trigger<NewCandidate>,
+    params: RecordParams,
+    remote_edits: Res<RemoteEdits>,
+) {
+    if remote_edits.contains(&trigger.target()) {
+        return;
+    }
+    let cell = cell_index(&params.3, trigger.target());
+    let digit = trigger.event().0.get();
+    record(params, || {
+        cell.map(|cell| ReplayAction::Candidate(cell, digit))
+    });
+}
+
+fn record_corner_candidate(
+    trigger: Trigger<NewCornerCandidate>,
+    params: RecordParams,
+    remote_edits: Res<RemoteEdits>,
+) {
+    if remote_edits.contains(&trigger.target()) {
+        return;
+    }
+    let cell = cell_index(&params.3, trigger.target());
+    let digit = trigger.event().0.get();
+    record(params, || {
+        cell.map(|cell| ReplayAction::Corner(cell, digit))
+    });
+}
+
+fn record_clear(trigger: Trigger<CleanCell>, params: RecordParams, remote_edits: Res<RemoteEdits>) {
+    if remote_edits.contains(&trigger.target()) {
+        return;
+    }
+    let cell = cell_index(&params.3, trigger.target());
+    record(params, || cell.map(ReplayAction::Clear));
+}
+
+fn record_tab(_trigger: Trigger<ToggleTab>, params: RecordParams) {
+    record(params, || Some(ReplayAction::ToggleTab));
+}
+
+fn record_corner_tab(_trigger: Trigger<ToggleCornerTab>, params: RecordParams) {
+    record(params, || Some(ReplayAction::ToggleCornerTab));
+}
+
+fn record_fill_candidates(_trigger: Trigger<FillCandidates>, params: RecordParams) {
+    record(params, || Some(ReplayAction::FillCandidates));
+}
+
+fn record_remove_impossible(_trigger: Trigger<RemoveImpossibleCandidates>, params: RecordParams) {
+    record(params, || Some(ReplayAction::RemoveImpossible));
+}
+
+fn record_hint(_trigger: Trigger<FindHint>, params: RecordParams, q_hint: HintCells) {
+    let select = hint_cell(q_hint.iter())
+        .and_then(|entity| cell_index(&params.3, entity))
+        .map(ReplayAction::Select);
+    record_with_derived(params, Some(ReplayAction::Hint), select);
+}
+
+fn record_check_cell(trigger: Trigger<CheckCell>, params: RecordParams) {
+    // 填数时自动检查会随着回放的数字再次发生
+    if trigger.event().automatic {
+        return;
+    }
+    let cell = cell_index(&params.3, trigger.target());
+    record(params, || cell.map(ReplayAction::CheckCell));
+}
+
+fn record_check_puzzle(_trigger: Trigger<CheckPuzzle>, params: RecordParams) {
+    record(params, || Some(ReplayAction::CheckPuzzle));
+}
+
+fn record_reveal(
+    trigger: Trigger<RevealCell>,
+    params: RecordParams,
+    sudoku_manager: Res<SudokuManager>,
+) {
+    let cell = cell_index(&params.3, trigger.target());
+    let solution = sudoku_manager.solution.to_bytes();
+    let digit = cell.map(|cell| ReplayAction::Digit(cell, solution[cell as usize]));
+    record_with_derived(params, cell.map(ReplayAction::Reveal), digit);
+}
+
+fn record_trial(trigger: Trigger<TrialControl>, params: RecordParams) {
+    let action = match trigger.event() {
+        TrialControl::Start => ReplayAction::TrialStart,
+        TrialControl::Commit => ReplayAction::TrialCommit,
+        TrialControl::Revert => ReplayAction::TrialRevert,
+    };
+    record(params, || Some(action));
+}
+
+fn record_undo(_trigger: Trigger<Undo>, params: RecordParams) {
+    record(params, || Some(ReplayAction::Undo));
+}
+
+fn record_auto_mode(auto_mode: Res<AutoCandidateMode>, params: RecordParams) {
+    let auto = auto_mode.0;
+    record(params, || Some(ReplayAction::AutoMode(auto)));
+}
+
+/// 完成时保存回放, 桌面端同时写入 `replays` 目录
+fn save_replay(
+    _trigger: Trigger<PuzzleSolved>,
+    recorder: Res<ReplayRecorder>,
+    sudoku_manager: Res<SudokuManager>,
+    player: Option<Res<ReplayPlayer>>,
+    mut commands: Commands,
+) {
+    if player.is_some() {
+        return;
+    }
+    let replay = Replay {
+        puzzle: sudoku_manager.solver.clone().to_sudoku(),
+        actions: recorder.actions.clone(),
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = std::path::Path::new("replays").join(format!(
+            "{}.replay",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let result =
+            std::fs::create_dir_all("replays").and_then(|_| std::fs::write(&path, replay.encode()));
+        if let Err(err) = result {
+            warn!("failed to save replay {:?}: {}", path, err);
+        }
+    }
+
+    commands.insert_resource(LastReplay(replay));
+}
+
+/// 读取 `replays` 目录里最新的回放
+#[cfg(not(target_arch = "wasm32"))]
+fn load_latest_replay() -> Option<Replay> {
+    let path = std::fs::read_dir("replays")
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "replay"))
+        .max()?;
+    Replay::decode(&std::fs::read_to_string(path).ok()?)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_latest_replay() -> Option<Replay> {
+    None
+}
+
+/// 开始回放最近一次完成的对局
+#[derive(Event)]
+pub struct WatchReplay;
+
+/// 回放速度倍数
+const SPEEDS: [f32; 4] = [1.0, 2.0, 4.0, 8.0];
+
+/// 回放状态, 只在回放时存在
+#[derive(Resource)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    cursor: usize,
+    elapsed: f32,
+    playing: bool,
+    speed: usize,
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum ReplayControl {
+    TogglePlay,
+    Speed,
+    /// 跳到整段回放的某个比例位置
+    Seek(f32),
+    Exit,
+}
+
+#[derive(Component)]
+struct ReplayPanel;
+
+#[derive(Component)]
+struct ReplayTimeText;
+
+#[derive(Component)]
+struct ReplayPlayText;
+
+#[derive(Component)]
+struct ReplaySpeedText;
+
+#[derive(Component)]
+struct ReplayTimeline;
+
+#[derive(Component)]
+struct ReplayTimelineFill;
+
+fn on_watch_replay(
+    _trigger: Trigger<WatchReplay>,
+    last_replay: Option<Res<LastReplay>>,
+    q_panel: Query<Entity, With<ReplayPanel>>,
+    font_assets: Res<FontAssets>,
+    mut commands: Commands,
+) {
+    let Some(replay) = last_replay
+        .map(|last_replay| last_replay.0.clone())
+        .or_else(load_latest_replay)
+    else {
+        return;
+    };
+    for entity in q_panel.iter() {
+        commands.entity(entity).despawn();
+    }
+    commands.insert_resource(ReplayPlayer {
+        replay,
+        cursor: 0,
+        elapsed: 0.0,
+        playing: true,
+        speed: 0,
+    });
+    commands.trigger(ReplayControl::Seek(0.0));
+    spawn_replay_panel(&font_assets, &mut commands);
+}
+
+/// 重新摆好题目, 之后每帧由 `play_replay` 执行到 `until` 毫秒为止的操作
+fn restart_board(
+    player: &mut ReplayPlayer,
+    until: u32,
+    commands: &mut Commands,
+    q_cell: &Query<(Entity, &CellPosition)>,
+) {
+    let Some(solution) = player.replay.puzzle.solution() else {
+        return;
+    };
+    for (entity, _) in q_cell.iter() {
+        commands
+            .entity(entity)
+            .remove::<(SelectedCell, CorrectionCell)>();
+    }
+    commands.insert_resource(TrialBranches::default());
+    commands.insert_resource(UndoHistory::default());
+    load_sudoku(commands, q_cell, player.replay.puzzle, solution);
+    player.cursor = 0;
+    player.elapsed = until as f32;
+}
+
+fn apply_actions(
+    player: &mut ReplayPlayer,
+    until: u32,
+    commands: &mut Commands,
+    q_cell: &Query<(Entity, &CellPosition)>,
+) {
+    let cell = |index: u8| {
+        q_cell
+            .iter()
+            .find(|(_, position)| position.0 == index)
+            .map(|(entity, _)| entity)
+    };
+    // 撤销历史按帧划分步骤, 有撤销的回放每帧只执行一个操作
+    let one_per_frame = player
+        .replay
+        .actions
+        .iter()
+        .any(|(_, action)| *action == ReplayAction::Undo);
+    while let Some((ms, action)) = player.replay.actions.get(player.cursor).copied() {
+        if ms > until {
+            break;
+        }
+        player.cursor += 1;
+        match action {
+            ReplayAction::Select(index) => {
+                for (entity, _) in q_cell.iter() {
+                    commands.entity(entity).remove::<SelectedCell>();
+                }
+                if let Some(entity) = cell(index) {
+                    commands.entity(entity).insert(SelectedCell);
+                }
+            }
+            ReplayAction::Digit(index, digit) => {
+                if let Some(entity) = cell(index) {
+                    commands.trigger_targets(NewDigit::new(digit), vec![entity]);
+                }
+            }
+            ReplayAction::Candidate(index, digit) => {
+                if let Some(entity) = cell(index) {
+                    commands.trigger_targets(NewCandidate::new(digit), vec![entity]);
+                }
+            }
+            ReplayAction::Corner(index, digit) => {
+                if let Some(entity) = cell(index) {
+                    commands.trigger_targets(NewCornerCandidate::new(digit), vec![entity]);
+                }
+            }
+            ReplayAction::Clear(index) => {
+                if let Some(entity) = cell(index) {
+                    commands.trigger_targets(CleanCell, vec![entity]);
+                }
+            }
+            ReplayAction::AutoMode(auto) => {
+                commands.insert_resource(AutoCandidateMode(auto));
+            }
+            ReplayAction::ToggleTab => commands.trigger(ToggleTab),
+            ReplayAction::ToggleCornerTab => commands.trigger(ToggleCornerTab),
+            ReplayAction::FillCandidates => commands.trigger(FillCandidates),
+            ReplayAction::RemoveImpossible => commands.trigger(RemoveImpossibleCandidates),
+            ReplayAction::Hint => commands.trigger(FindHint),
+            ReplayAction::CheckCell(index) => {
+                if let Some(entity) = cell(index) {
+                    commands.trigger_targets(CheckCell::default(), vec![entity]);
+                }
+            }
+            ReplayAction::CheckPuzzle => commands.trigger(CheckPuzzle),
+            ReplayAction::TrialStart => commands.trigger(TrialControl::Start),
+            ReplayAction::TrialCommit => commands.trigger(TrialControl::Commit),
+            ReplayAction::TrialRevert => commands.trigger(TrialControl::Revert),
+            ReplayAction::Undo => commands.trigger(Undo),
+            ReplayAction::Reveal(index) => {
+                if let Some(entity) = cell(index) {
+                    commands.trigger_targets(RevealCell::default(), vec![entity]);
+                }
+            }
+        }
+        if one_per_frame {
+            break;
+        }
+    }
+}
+
+fn play_replay(
+    mut player: ResMut<ReplayPlayer>,
+    mut game_timer: ResMut<GameTimer>,
+    time: Res<Time>,
+    q_cell: Query<(Entity, &CellPosition)>,
+    mut commands: Commands,
+) {
+    if player.playing {
+        player.elapsed += time.delta_secs() * 1000.0 * SPEEDS[player.speed];
+        if player.elapsed >= player.replay.duration() as f32 {
+            player.playing = false;
+        }
+    }
+    // 暂停时跳转也要追上 `elapsed`
+    let until = player.elapsed as u32;
+    apply_actions(&mut player, until, &mut commands, &q_cell);
+    game_timer.set_elapsed(Duration::from_millis(player.elapsed as u64));
+}
+
+fn on_replay_control(
+    trigger: Trigger<ReplayControl>,
+    player: Option<ResMut<ReplayPlayer>>,
+    q_cell: Query<(Entity, &CellPosition)>,
+    mut commands: Commands,
+) {
+    let Some(mut player) = player else {
+        return;
+    };
+    match *trigger.event() {
+        ReplayControl::TogglePlay => {
+            // 播完后再点播放从头开始
+            if !player.playing && player.cursor >= player.replay.actions.len() {
+                restart_board(&mut player, 0, &mut commands, &q_cell);
+            }
+            player.playing = !player.playing;
+        }
+        ReplayControl::Speed => player.speed = (player.speed + 1) % SPEEDS.len(),
+        ReplayControl::Seek(fraction) => {
+            let until = (player.replay.duration() as f32 * fraction.clamp(0.0, 1.0)) as u32;
+            restart_board(&mut player, until, &mut commands, &q_cell);
+        }
+        // 开新题时会结束回放
+        ReplayControl::Exit => commands.trigger(InitPuzzle),
+    }
+}
+
+fn spawn_replay_panel(font_assets: &Res<FontAssets>, commands: &mut Commands) {
+    commands
+        .spawn((
+            Name::new("replay-panel"),
+            ReplayPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(16.0),
+                left: Val::Px(0.0),
+                right: Val::Px(0.0),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            Pickable::IGNORE,
+            GlobalZIndex(50),
+        ))
+        .with_children(|builder| {
+            builder
+                .spawn((
+                    Node {
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::axes(Val::Px(20.0), Val::Px(12.0)),
+                        border: UiRect::all(Val::Px(1.0)),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(8.0)),
+                    ThemedBackground(ThemeRole::Surface),
+                    BorderColor(Color::BLACK.with_alpha(0.2)),
+                ))
+                .with_children(|builder| {
+                    builder.spawn((
+                        ReplayTimeText,
+                        Localized::new(""),
+                        TextFont {
+                            font: font_assets.franklin_600.clone(),
+                            font_size: 18.0,
+                            ..default()
+                        },
+                        ThemedText(ThemeRole::Text),
+                    ));
+
+                    // 时间轴, 点击跳转
+                    builder
+                        .spawn((
+                            ReplayTimeline,
+                            Button,
+                            Node {
+                                width: Val::Px(360.0),
+                                height: Val::Px(10.0),
+                                margin: UiRect::top(Val::Px(10.0)),
+                                ..default()
+                            },
+                            BorderRadius::all(Val::Px(5.0)),
+                            ThemedBackground(ThemeRole::Border),
+                            RelativeCursorPosition::default(),
+                        ))
+                        .with_child((
+                            ReplayTimelineFill,
+                            Node {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            BorderRadius::all(Val::Px(5.0)),
+                            ThemedBackground(ThemeRole::Button),
+                            Pickable::IGNORE,
+                        ))
+                        .observe(
+                            |trigger: Trigger<Pointer<Click>>,
+                             q_timeline: Query<&RelativeCursorPosition>,
+                             mut commands: Commands| {
+                                if let Ok(cursor) = q_timeline.get(trigger.target())
+                                    && let Some(position) = cursor.normalized
+                                {
+                                    commands.trigger(ReplayControl::Seek(position.x));
+                                }
+                            },
+                        );
+
+                    builder
+                        .spawn(Node {
+                            display: Display::Flex,
+                            column_gap: Val::Px(8.0),
+                            margin: UiRect::top(Val::Px(10.0)),
+                            ..default()
+                        })
+                        .with_children(|builder| {
+                            panel_button(
+                                font_assets,
+                                builder,
+                                "Pause",
+                                ReplayControl::TogglePlay,
+                                ReplayPlayText,
+                            );
+                            panel_button(
+                                font_assets,
+                                builder,
+                                "Speed 1x",
+                                ReplayControl::Speed,
+                                ReplaySpeedText,
+                            );
+                            panel_button(font_assets, builder, "Exit", ReplayControl::Exit, ());
+                        });
+                });
+        });
+}
+
+#[allow(clippy::type_complexity)]
+fn update_replay_panel(
+    player: Res<ReplayPlayer>,
+    game_timer: Res<GameTimer>,
+    mut q_time: Query<&mut Localized, With<ReplayTimeText>>,
+    mut q_play: Query<&mut Localized, (With<ReplayPlayText>, Without<ReplayTimeText>)>,
+    mut q_speed: Query<
+        &mut Localized,
+        (
+            With<ReplaySpeedText>,
+            Without<ReplayTimeText>,
+            Without<ReplayPlayText>,
+        ),
+    >,
+    mut q_fill: Query<&mut Node, With<ReplayTimelineFill>>,
+) {
+    if !player.is_changed() {
+        return;
+    }
+    for mut text in q_time.iter_mut() {
+        *text = Localized::with_args(
+            "Replay {} ({}/{} moves)",
+            [
+                game_timer.to_string(),
+                player.cursor.to_string(),
+                player.replay.actions.len().to_string(),
+            ],
+        );
+    }
+    for mut text in q_play.iter_mut() {
+        *text = Localized::new(if player.playing { "Pause" } else { "Play" });
+    }
+    for mut text in q_speed.iter_mut() {
+        *text = Localized::with_args("Speed {}x", [SPEEDS[player.speed]]);
+    }
+    let duration = player.replay.duration().max(1) as f32;
+    for mut node in q_fill.iter_mut() {
+        node.width = Val::Percent((player.elapsed / duration * 100.0).min(100.0));
+    }
+}
+
+fn stop_replay(mut commands: Commands, q_panel: Query<Entity, With<ReplayPanel>>) {
+    end_replay(&mut commands, &q_panel);
+}
+
+fn end_replay(commands: &mut Commands, q_panel: &Query<Entity, With<ReplayPanel>>) {
+    commands.remove_resource::<ReplayPlayer>();
+    for entity in q_panel.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[test]
+fn test_replay_encode_round_trip() {
+    let replay = Replay {
+        puzzle: Sudoku::generate(),
+        actions: vec![
+            (0, ReplayAction::Select(0)),
+            (1520, ReplayAction::Digit(40, 5)),
+            (2100, ReplayAction::Candidate(80, 9)),
+            (2200, ReplayAction::ToggleCornerTab),
+            (2300, ReplayAction::Corner(80, 3)),
+            (2400, ReplayAction::FillCandidates),
+            (2500, ReplayAction::AutoMode(true)),
+            (3000, ReplayAction::Hint),
+            (3100, ReplayAction::Reveal(7)),
+            (3200, ReplayAction::TrialStart),
+            (3300, ReplayAction::TrialRevert),
+            (3400, ReplayAction::TrialCommit),
+            (3500, ReplayAction::Undo),
+        ],
+    };
+    assert_eq!(Replay::decode(&replay.encode()), Some(replay));
+}
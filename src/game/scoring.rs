@@ -0,0 +1,458 @@
+use crate::{
+    GameState,
+    game::{
+        CheckCell, CheckPuzzle, FindHint, GameTimer, PuzzleStarted, ResetPuzzle, RevealCell,
+        RevealPuzzle, SudokuManager,
+        dialog::ShowCongrats,
+        guided_solve::{GuidedSolve, STRATEGIES},
+        lessons::ActiveLesson,
+        mistakes::Mistakes,
+        replay::ReplayPlayer,
+    },
+    i18n::Localized,
+    loading::FontAssets,
+    theme::{ThemeRole, ThemedText},
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+use std::{mem::discriminant, time::Duration};
+use sudoku::strategy::StrategySolver;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<PuzzleDifficulty>()
+        .init_resource::<Assists>()
+        .insert_resource(load_leaderboard())
+        .add_systems(
+            Update,
+            (
+                rate_puzzle.run_if(resource_changed::<SudokuManager>),
+                update_difficulty_text.run_if(resource_changed::<PuzzleDifficulty>),
+                count_guided_solve.run_if(resource_added::<GuidedSolve>),
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_observer(on_puzzle_solved)
+        .add_observer(|_: Trigger<FindHint>, mut assists: ResMut<Assists>| {
+            assists.hints += 1;
+        })
+        .add_observer(
+            |trigger: Trigger<CheckCell>, mut assists: ResMut<Assists>| {
+                if !trigger.event().automatic {
+                    assists.checked_cells += 1;
+                }
+            },
+        )
+        .add_observer(|_: Trigger<CheckPuzzle>, mut assists: ResMut<Assists>| {
+            assists.checked_puzzles += 1;
+        })
+        .add_observer(
+            |trigger: Trigger<RevealCell>, mut assists: ResMut<Assists>| {
+                if !trigger.event().whole_puzzle {
+                    assists.revealed_cells += 1;
+                }
+            },
+        )
+        .add_observer(|_: Trigger<RevealPuzzle>, mut assists: ResMut<Assists>| {
+            assists.revealed_puzzles += 1;
+        })
+        .add_observer(|_: Trigger<PuzzleStarted>, mut assists: ResMut<Assists>| {
+            *assists = Assists::default();
+        })
+        .add_observer(|_: Trigger<ResetPuzzle>, mut assists: ResMut<Assists>| {
+            *assists = Assists::default();
+        });
+}
+
+/// 按求解需要的最难技巧划分的难度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Difficulty {
+    #[default]
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    fn base_points(&self) -> i64 {
+        match self {
+            Difficulty::Easy => 1000,
+            Difficulty::Medium => 2000,
+            Difficulty::Hard => 3000,
+        }
+    }
+}
+
+/// 只靠唯余和排除能解出的是简单, 用到数组以内技巧的是中等, 其余是困难
+pub fn rate_difficulty(solver: StrategySolver) -> Difficulty {
+    let (solved, deductions) = match solver.solve(STRATEGIES) {
+        Ok((_, deductions)) => (true, deductions),
+        Err((_, deductions)) => (false, deductions),
+    };
+    if !solved {
+        return Difficulty::Hard;
+    }
+    let hardest = deductions
+        .iter()
+        .filter_map(|deduction| {
+            let strategy = deduction.strategy();
+            STRATEGIES
+                .iter()
+                .position(|s| discriminant(s) == discriminant(&strategy))
+        })
+        .max()
+        .unwrap_or(0);
+    match hardest {
+        0..=1 => Difficulty::Easy,
+        2..=6 => Difficulty::Medium,
+        _ => Difficulty::Hard,
+    }
+}
+
+/// 当前题目的难度
+#[derive(Resource, Debug, Default, Deref)]
+pub struct PuzzleDifficulty(pub Difficulty);
+
+fn rate_puzzle(sudoku_manager: Res<SudokuManager>, mut difficulty: ResMut<PuzzleDifficulty>) {
+    let rated = rate_difficulty(sudoku_manager.solver.clone());
+    if difficulty.0 != rated {
+        difficulty.0 = rated;
+    }
+}
+
+#[derive(Component)]
+struct DifficultyText;
+
+/// 工具栏上的难度
+pub(crate) fn difficulty_label(
+    font_assets: &Res<FontAssets>,
+    builder: &mut ChildSpawnerCommands<'_>,
+) {
+    builder.spawn((
+        DifficultyText,
+        Localized::new(Difficulty::default().label()),
+        TextFont {
+            font_size: 18.0,
+            font: font_assets.franklin_500.clone(),
+            ..default()
+        },
+        ThemedText(ThemeRole::Text),
+        Node {
+            margin: UiRect::horizontal(Val::Px(16.0)),
+            ..default()
+        },
+    ));
+}
+
+fn update_difficulty_text(
+    difficulty: Res<PuzzleDifficulty>,
+    mut q_text: Query<&mut Localized, With<DifficultyText>>,
+) {
+    for mut text in q_text.iter_mut() {
+        *text = Localized::new(difficulty.label());
+    }
+}
+
+/// 本局使用辅助功能的次数
+#[derive(Resource, Debug, Default, Clone)]
+pub struct Assists {
+    pub hints: u32,
+    pub checked_cells: u32,
+    pub checked_puzzles: u32,
+    /// 只报告错了几格, 比检查整题扣分少
+    pub progress_checks: u32,
+    pub revealed_cells: u32,
+    pub revealed_puzzles: u32,
+    /// 讲解会一步步填出整道题, 和揭示整题一样对待
+    pub guided_solves: u32,
+}
+
+impl Assists {
+    /// 揭示过整题或用过讲解的局不进排行榜
+    pub fn ranked(&self) -> bool {
+        self.revealed_puzzles == 0 && self.guided_solves == 0
+    }
+}
+
+/// 得分明细, 每一项是说明和分数
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreBreakdown {
+    pub items: Vec<(String, i64)>,
+}
+
+impl ScoreBreakdown {
+    pub fn new(difficulty: Difficulty, time: Duration, mistakes: u32, assists: &Assists) -> Self {
+        let mut items = vec![(
+            format!("{} puzzle", difficulty.label()),
+            difficulty.base_points(),
+        )];
+        // 每 2 秒扣 1 分
+        let seconds = time.as_secs() as i64;
+        items.push((format!("Time ({}s)", seconds), -seconds / 2));
+        let penalties = [
+            ("Mistakes", mistakes, 100),
+            ("Hints", assists.hints, 150),
+            ("Checked cells", assists.checked_cells, 50),
+            ("Checked puzzle", assists.checked_puzzles, 100),
+            ("Progress checks", assists.progress_checks, 25),
+            ("Revealed cells", assists.revealed_cells, 200),
+            ("Revealed puzzle", assists.revealed_puzzles, 2000),
+            ("Guided solve", assists.guided_solves, 2000),
+        ];
+        for (label, count, points) in penalties {
+            if count > 0 {
+                items.push((format!("{} x{}", label, count), -(count as i64) * points));
+            }
+        }
+        Self { items }
+    }
+
+    /// 总分不低于 0
+    pub fn total(&self) -> i64 {
+        self.items
+            .iter()
+            .map(|(_, points)| points)
+            .sum::<i64>()
+            .max(0)
+    }
+}
+
+/// 每个难度保留的最高分数量
+const LEADERBOARD_SIZE: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderboardEntry {
+    pub score: i64,
+    pub time: Duration,
+}
+
+/// 按难度分开的本地排行榜
+#[derive(Resource, Debug, Default)]
+pub struct Leaderboard {
+    entries: HashMap<Difficulty, Vec<LeaderboardEntry>>,
+}
+
+impl Leaderboard {
+    /// 记录一次得分, 返回名次 (从 0 开始), 没进榜时返回 None
+    pub fn submit(&mut self, difficulty: Difficulty, entry: LeaderboardEntry) -> Option<usize> {
+        let entries = self.entries.entry(difficulty).or_default();
+        let rank = entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(entries.len());
+        if rank >= LEADERBOARD_SIZE {
+            return None;
+        }
+        entries.insert(rank, entry);
+        entries.truncate(LEADERBOARD_SIZE);
+        Some(rank)
+    }
+
+    pub fn entries(&self, difficulty: Difficulty) -> &[LeaderboardEntry] {
+        self.entries
+            .get(&difficulty)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// 每行一条: 难度 分数 用时毫秒
+    pub fn encode(&self) -> String {
+        [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard]
+            .iter()
+            .flat_map(|difficulty| {
+                self.entries(*difficulty).iter().map(move |entry| {
+                    format!(
+                        "{} {} {}\n",
+                        difficulty.label(),
+                        entry.score,
+                        entry.time.as_millis()
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// 跳过读不懂的行
+    pub fn decode(text: &str) -> Self {
+        let mut leaderboard = Self::default();
+        for line in text.lines() {
+            let mut parts = line.split_whitespace();
+            let difficulty = match parts.next() {
+                Some("Easy") => Difficulty::Easy,
+                Some("Medium") => Difficulty::Medium,
+                Some("Hard") => Difficulty::Hard,
+                _ => continue,
+            };
+            let (Some(Ok(score)), Some(Ok(millis))) =
+                (parts.next().map(str::parse), parts.next().map(str::parse))
+            else {
+                continue;
+            };
+            leaderboard.submit(
+                difficulty,
+                LeaderboardEntry {
+                    score,
+                    time: Duration::from_millis(millis),
+                },
+            );
+        }
+        leaderboard
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const LEADERBOARD_PATH: &str = "scores/leaderboard.txt";
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_leaderboard() -> Leaderboard {
+    std::fs::read_to_string(LEADERBOARD_PATH)
+        .map(|text| Leaderboard::decode(&text))
+        .unwrap_or_default()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_leaderboard() -> Leaderboard {
+    Leaderboard::default()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_leaderboard(leaderboard: &Leaderboard) {
+    let result = std::fs::create_dir_all("scores")
+        .and_then(|_| std::fs::write(LEADERBOARD_PATH, leaderboard.encode()));
+    if let Err(err) = result {
+        warn!("failed to save leaderboard: {}", err);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn save_leaderboard(_leaderboard: &Leaderboard) {}
+
+/// 最近一次完成的得分, 供祝贺弹窗显示
+#[derive(Resource, Debug, Clone)]
+pub struct LastScore {
+    pub difficulty: Difficulty,
+    pub breakdown: ScoreBreakdown,
+    pub rank: Option<usize>,
+}
+
+/// 盘面全部填对
+#[derive(Event)]
+pub struct PuzzleSolved;
+
+fn count_guided_solve(mut assists: ResMut<Assists>) {
+    assists.guided_solves += 1;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn on_puzzle_solved(
+    _trigger: Trigger<PuzzleSolved>,
+    difficulty: Res<PuzzleDifficulty>,
+    game_timer: Res<GameTimer>,
+    mistakes: Res<Mistakes>,
+    assists: Res<Assists>,
+    mut leaderboard: ResMut<Leaderboard>,
+    player: Option<Res<ReplayPlayer>>,
+    guided: Option<Res<GuidedSolve>>,
+    lesson: Option<Res<ActiveLesson>>,
+    mut commands: Commands,
+) {
+    // 回放到最后、讲解填满盘面和课程里都不算成绩
+    if player.is_some() || guided.is_some() || lesson.is_some() {
+        return;
+    }
+    let breakdown =
+        ScoreBreakdown::new(difficulty.0, game_timer.elapsed(), mistakes.count, &assists);
+    let rank = assists
+        .ranked()
+        .then(|| {
+            leaderboard.submit(
+                difficulty.0,
+                LeaderboardEntry {
+                    score: breakdown.total(),
+                    time: game_timer.elapsed(),
+                },
+            )
+        })
+        .flatten();
+    if rank.is_some() {
+        save_leaderboard(&leaderboard);
+    }
+    commands.insert_resource(LastScore {
+        difficulty: difficulty.0,
+        breakdown,
+        rank,
+    });
+    commands.trigger(ShowCongrats(true));
+}
+
+#[test]
+fn test_score_breakdown() {
+    let assists = Assists {
+        hints: 1,
+        ..default()
+    };
+    let breakdown = ScoreBreakdown::new(Difficulty::Medium, Duration::from_secs(600), 2, &assists);
+    assert_eq!(breakdown.total(), 2000 - 300 - 200 - 150);
+
+    let slow = ScoreBreakdown::new(Difficulty::Easy, Duration::from_secs(9000), 0, &assists);
+    assert_eq!(slow.total(), 0);
+
+    assert!(assists.ranked());
+    let revealed = Assists {
+        revealed_puzzles: 1,
+        ..default()
+    };
+    assert!(!revealed.ranked());
+}
+
+#[test]
+fn test_leaderboard_keeps_top_scores() {
+    let mut leaderboard = Leaderboard::default();
+    for score in [300, 100, 500, 200, 400, 50] {
+        leaderboard.submit(
+            Difficulty::Easy,
+            LeaderboardEntry {
+                score,
+                time: Duration::ZERO,
+            },
+        );
+    }
+    let scores = leaderboard
+        .entries(Difficulty::Easy)
+        .iter()
+        .map(|entry| entry.score)
+        .collect::<Vec<_>>();
+    assert_eq!(scores, vec![500, 400, 300, 200, 100]);
+    assert!(leaderboard.entries(Difficulty::Hard).is_empty());
+}
+
+#[test]
+fn test_leaderboard_encode_round_trip() {
+    let mut leaderboard = Leaderboard::default();
+    for (difficulty, score) in [(Difficulty::Easy, 900), (Difficulty::Hard, 2500)] {
+        leaderboard.submit(
+            difficulty,
+            LeaderboardEntry {
+                score,
+                time: Duration::from_millis(123_456),
+            },
+        );
+    }
+    let decoded = Leaderboard::decode(&format!("{}garbage line\n", leaderboard.encode()));
+    assert_eq!(
+        decoded.entries(Difficulty::Easy),
+        leaderboard.entries(Difficulty::Easy)
+    );
+    assert_eq!(
+        decoded.entries(Difficulty::Hard),
+        leaderboard.entries(Difficulty::Hard)
+    );
+    assert!(decoded.entries(Difficulty::Medium).is_empty());
+}
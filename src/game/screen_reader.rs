@@ -0,0 +1,254 @@
+use crate::{
+    GameState,
+    game::{
+        NewDigit,
+        cell_state::{
+            AutoCandidates, CellMode, ConflictCell, DigitValueCell, FixedCell, ManualCandidates,
+            SelectedCell,
+        },
+        dialog::{Dialog, DialogBody, DialogStack, ShowCongrats},
+        position::CellPosition,
+    },
+    i18n::{Language, Locale},
+};
+use accesskit::{Live, Node as Accessible, Role};
+use bevy::{
+    a11y::AccessibilityNode,
+    input_focus::{InputDispatchPlugin, InputFocus, tab_navigation::TabNavigationPlugin},
+    prelude::*,
+};
+use sudoku::{bitset::Set, board::Digit};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_plugins((InputDispatchPlugin, TabNavigationPlugin))
+        .add_systems(Startup, spawn_announcer)
+        .add_systems(
+            Update,
+            update_cell_labels.run_if(in_state(GameState::Playing)),
+        )
+        .add_observer(on_announce)
+        .add_observer(on_select_cell)
+        .add_observer(on_new_digit)
+        .add_observer(on_insert_conflict)
+        .add_observer(on_solved)
+        .add_observer(on_open_dialog)
+        .add_observer(on_close_dialog);
+}
+
+/// 让读屏软件朗读一段文字
+#[derive(Event)]
+pub struct Announce(pub String);
+
+/// 朗读区域, 内容变化时读屏软件会自动朗读
+#[derive(Component)]
+struct Announcer;
+
+fn spawn_announcer(mut commands: Commands) {
+    let mut node = Accessible::new(Role::Status);
+    node.set_live(Live::Polite);
+    commands.spawn((Name::new("announcer"), Announcer, AccessibilityNode(node)));
+}
+
+fn on_announce(
+    trigger: Trigger<Announce>,
+    mut q_announcer: Query<&mut AccessibilityNode, With<Announcer>>,
+) {
+    for mut node in q_announcer.iter_mut() {
+        node.set_value(trigger.event().0.clone());
+    }
+}
+
+/// 格子的朗读文字, 如 "Row 3, column 5, empty, candidates 2 4 7"
+pub fn cell_label(
+    language: Language,
+    position: &CellPosition,
+    digit: Option<u8>,
+    given: bool,
+    candidates: &[u8],
+) -> String {
+    let mut label = language.format(
+        "Row {}, column {}",
+        &[&(position.row() + 1), &(position.col() + 1)],
+    );
+    match digit {
+        Some(digit) if given => label.push_str(&language.format(", given {}", &[&digit])),
+        Some(digit) => label.push_str(&language.format(", {}", &[&digit])),
+        None => {
+            label.push_str(language.tr(", empty"));
+            if !candidates.is_empty() {
+                let candidates = candidates
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                label.push_str(&language.format(", candidates {}", &[&candidates]));
+            }
+        }
+    }
+    label
+}
+
+/// 格子里显示的候选数
+fn shown_candidates(mode: &CellMode, auto: &AutoCandidates, manual: &ManualCandidates) -> Vec<u8> {
+    let candidates: Set<Digit> = match mode {
+        CellMode::Digit => Set::NONE,
+        CellMode::AutoCandidates => auto.0,
+        CellMode::ManualCandidates => manual.0,
+    };
+    candidates.into_iter().map(|digit| digit.get()).collect()
+}
+
+type CellLabelQuery<'a> = (
+    Entity,
+    &'a CellPosition,
+    &'a DigitValueCell,
+    &'a CellMode,
+    &'a AutoCandidates,
+    &'a ManualCandidates,
+    Has<FixedCell>,
+    Has<SelectedCell>,
+);
+
+fn update_cell_labels(
+    q_cell: Query<
+        CellLabelQuery,
+        Or<(
+            Changed<DigitValueCell>,
+            Changed<CellMode>,
+            Changed<AutoCandidates>,
+            Changed<ManualCandidates>,
+            Added<SelectedCell>,
+        )>,
+    >,
+    mut removed_selected: RemovedComponents<SelectedCell>,
+    q_all: Query<CellLabelQuery>,
+    locale: Res<Locale>,
+    mut commands: Commands,
+) {
+    // 切换语言时所有格子都要重新生成
+    let changed = if locale.is_changed() {
+        q_all.iter().collect::<Vec<_>>()
+    } else {
+        let unselected = removed_selected
+            .read()
+            .filter_map(|entity| q_all.get(entity).ok());
+        q_cell.iter().chain(unselected).collect()
+    };
+    for (entity, position, digit, mode, auto, manual, is_fixed, is_selected) in changed {
+        let candidates = shown_candidates(mode, auto, manual);
+        let mut node = Accessible::new(Role::Cell);
+        node.set_row_index(position.row() as usize);
+        node.set_column_index(position.col() as usize);
+        node.set_label(cell_label(
+            locale.language,
+            position,
+            digit.0.map(|d| d.get()),
+            is_fixed,
+            &candidates,
+        ));
+        node.set_selected(is_selected);
+        commands.entity(entity).insert(AccessibilityNode(node));
+    }
+}
+
+/// 方向键移动和点击选中格子时, 把焦点交给选中的格子并朗读
+fn on_select_cell(
+    trigger: Trigger<OnInsert, SelectedCell>,
+    q_cell: Query<CellLabelQuery>,
+    mut focus: ResMut<InputFocus>,
+    locale: Res<Locale>,
+    mut commands: Commands,
+) {
+    if let Ok((_, position, digit, mode, auto, manual, is_fixed, _)) = q_cell.get(trigger.target())
+    {
+        focus.set(trigger.target());
+        commands.trigger(Announce(cell_label(
+            locale.language,
+            position,
+            digit.0.map(|d| d.get()),
+            is_fixed,
+            &shown_candidates(mode, auto, manual),
+        )));
+    }
+}
+
+fn on_new_digit(
+    trigger: Trigger<NewDigit>,
+    q_cell: Query<&CellPosition, Without<FixedCell>>,
+    locale: Res<Locale>,
+    mut commands: Commands,
+) {
+    if let Ok(position) = q_cell.get(trigger.target()) {
+        commands.trigger(Announce(locale.format(
+            "{} entered at row {}, column {}",
+            &[
+                &trigger.event().0.get(),
+                &(position.row() + 1),
+                &(position.col() + 1),
+            ],
+        )));
+    }
+}
+
+fn on_insert_conflict(
+    trigger: Trigger<OnInsert, ConflictCell>,
+    q_cell: Query<&CellPosition>,
+    locale: Res<Locale>,
+    mut commands: Commands,
+) {
+    if let Ok(position) = q_cell.get(trigger.target()) {
+        commands.trigger(Announce(locale.format(
+            "Conflict at row {}, column {}",
+            &[&(position.row() + 1), &(position.col() + 1)],
+        )));
+    }
+}
+
+fn on_solved(trigger: Trigger<ShowCongrats>, locale: Res<Locale>, mut commands: Commands) {
+    if trigger.event().0 {
+        commands.trigger(Announce(locale.tr("Puzzle solved").to_string()));
+    }
+}
+
+/// 打开弹窗时焦点移到弹窗上
+fn on_open_dialog(trigger: Trigger<OnAdd, DialogBody>, mut focus: ResMut<InputFocus>) {
+    focus.set(trigger.target());
+}
+
+/// 关闭弹窗后焦点回到下面一层弹窗, 没有弹窗时回到选中的格子
+fn on_close_dialog(
+    trigger: Trigger<OnRemove, DialogBody>,
+    mut focus: ResMut<InputFocus>,
+    stack: Res<DialogStack>,
+    q_dialog: Query<(Entity, &Dialog)>,
+    q_selected: Query<Entity, With<SelectedCell>>,
+) {
+    if focus.get() != Some(trigger.target()) {
+        return;
+    }
+    let below = q_dialog
+        .iter()
+        .find(|(entity, dialog)| *entity != trigger.target() && stack.top() == Some(**dialog));
+    match (below, q_selected.single()) {
+        (Some((dialog, _)), _) => focus.set(dialog),
+        (None, Ok(selected)) => focus.set(selected),
+        (None, Err(_)) => focus.clear(),
+    }
+}
+
+#[test]
+fn test_cell_label() {
+    let position = CellPosition::from_row_col(2, 4);
+    assert_eq!(
+        cell_label(Language::English, &position, None, false, &[2, 4, 7]),
+        "Row 3, column 5, empty, candidates 2 4 7"
+    );
+    assert_eq!(
+        cell_label(Language::English, &position, Some(6), true, &[]),
+        "Row 3, column 5, given 6"
+    );
+    assert_eq!(
+        cell_label(Language::SimplifiedChinese, &position, Some(6), false, &[]),
+        "第 3 行第 5 列，6"
+    );
+}
@@ -0,0 +1,169 @@
+use crate::{
+    GameState,
+    game::{
+        GameTimer, InitPuzzle, SudokuManager,
+        cell_state::{CornerCandidates, DigitValueCell, FixedCell, ManualCandidates, RevealedCell},
+        dialog::Confirm,
+        input::board_input_allowed,
+        position::CellPosition,
+        scoring::PuzzleDifficulty,
+    },
+    share::{
+        SharedPuzzle, ShowToast, copy_to_clipboard, decode_share_code, encode_share_code,
+        page_share_code, read_clipboard, share_card, share_link,
+    },
+};
+use bevy::prelude::*;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<PendingShare>()
+        .add_systems(Startup, read_page_share_code)
+        .add_systems(
+            Update,
+            paste_shortcut.run_if(in_state(GameState::Playing).and(board_input_allowed)),
+        )
+        .add_observer(on_share_puzzle)
+        .add_observer(on_paste_code)
+        .add_observer(on_load_pasted_code)
+        .add_observer(on_share_result);
+}
+
+/// 下一次出题时使用的分享题目
+#[derive(Resource, Debug, Default)]
+pub struct PendingShare(pub Option<SharedPuzzle>);
+
+/// 网页版打开带 `?p=` 的链接时直接玩这道题
+fn read_page_share_code(mut pending: ResMut<PendingShare>) {
+    if let Some(code) = page_share_code() {
+        pending.0 = decode_share_code(&code);
+        if pending.0.is_none() {
+            warn!("Invalid share code in page url: {code}");
+        }
+    }
+}
+
+/// 复制当前题目和进度的分享链接
+#[derive(Event)]
+pub struct SharePuzzle;
+
+fn on_share_puzzle(
+    _trigger: Trigger<SharePuzzle>,
+    sudoku_manager: Res<SudokuManager>,
+    q_cell: Query<
+        (
+            &CellPosition,
+            &DigitValueCell,
+            &ManualCandidates,
+            &CornerCandidates,
+        ),
+        Without<FixedCell>,
+    >,
+    mut commands: Commands,
+) {
+    let mut progress = [0u8; 81];
+    let mut marks = [(0u16, 0u16); 81];
+    for (position, digit, manual, corner) in q_cell.iter() {
+        let index = position.0 as usize;
+        if let Some(digit) = digit.0 {
+            progress[index] = digit.get();
+        } else {
+            marks[index] = (manual.0.bits(), corner.0.bits());
+        }
+    }
+    let puzzle = sudoku_manager.solver.clone().to_sudoku();
+    let link = share_link(&encode_share_code(&puzzle, Some(&progress), Some(&marks)));
+    let message = if copy_to_clipboard(&link) {
+        "Share link copied".to_string()
+    } else {
+        link
+    };
+    commands.trigger(ShowToast(message));
+}
+
+/// 从剪贴板读取分享码, 开始那道题
+#[derive(Event)]
+pub struct PasteCode;
+
+/// 确认替换当前对局后再读一次剪贴板载入
+#[derive(Event)]
+struct LoadPastedCode;
+
+fn pasted_puzzle() -> Option<SharedPuzzle> {
+    read_clipboard()
+        .as_deref()
+        .and_then(decode_share_code)
+        .filter(|shared| shared.puzzle.solution().is_some())
+}
+
+/// 盘面上已经填了数字时先确认, 避免丢掉进度
+fn on_paste_code(
+    _trigger: Trigger<PasteCode>,
+    q_cell: Query<&DigitValueCell, Without<FixedCell>>,
+    mut commands: Commands,
+) {
+    if pasted_puzzle().is_none() {
+        commands.trigger(ShowToast("No puzzle code in clipboard".to_string()));
+    } else if q_cell.iter().any(|digit| digit.0.is_some()) {
+        commands.trigger(Confirm {
+            title: "Replace current game?",
+            message: "Your progress on this puzzle will be lost.",
+            confirm: "Replace",
+            action: |mut commands| commands.trigger(LoadPastedCode),
+        });
+    } else {
+        commands.trigger(LoadPastedCode);
+    }
+}
+
+fn on_load_pasted_code(
+    _trigger: Trigger<LoadPastedCode>,
+    mut pending: ResMut<PendingShare>,
+    mut commands: Commands,
+) {
+    match pasted_puzzle() {
+        Some(shared) => {
+            pending.0 = Some(shared);
+            commands.trigger(InitPuzzle);
+            commands.trigger(ShowToast("Loaded shared puzzle".to_string()));
+        }
+        None => commands.trigger(ShowToast("No puzzle code in clipboard".to_string())),
+    }
+}
+
+fn paste_shortcut(keyboard_input: Res<ButtonInput<KeyCode>>, mut commands: Commands) {
+    let modifier = keyboard_input.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+    if modifier && keyboard_input.just_pressed(KeyCode::KeyV) {
+        commands.trigger(PasteCode);
+    }
+}
+
+/// 复制完成后的成绩卡片
+#[derive(Event)]
+pub struct ShareResult;
+
+fn on_share_result(
+    _trigger: Trigger<ShareResult>,
+    difficulty: Res<PuzzleDifficulty>,
+    game_timer: Res<GameTimer>,
+    q_cell: Query<(&CellPosition, Has<RevealedCell>)>,
+    mut commands: Commands,
+) {
+    let mut clean_boxes = [true; 9];
+    for (position, is_revealed) in q_cell.iter() {
+        if is_revealed {
+            clean_boxes[position.block() as usize] = false;
+        }
+    }
+    let card = share_card(difficulty.label(), &game_timer.to_string(), &clean_boxes);
+    let message = if copy_to_clipboard(&card) {
+        "Result copied"
+    } else {
+        "Could not access the clipboard"
+    };
+    commands.trigger(ShowToast(message.to_string()));
+}
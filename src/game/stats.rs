@@ -0,0 +1,67 @@
+use crate::game::{GameTimer, dialog::ShowCongrats, mistakes::Mistakes};
+use bevy::prelude::*;
+use std::time::Duration;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<GameStats>().add_observer(record_win);
+}
+
+/// 本次运行中所有完成或失败的对局
+#[derive(Resource, Debug, Default)]
+pub struct GameStats {
+    pub records: Vec<GameRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    pub won: bool,
+    pub time: Duration,
+    pub mistakes: u32,
+}
+
+impl GameStats {
+    pub fn won(&self) -> usize {
+        self.records.iter().filter(|record| record.won).count()
+    }
+
+    pub fn lost(&self) -> usize {
+        self.records.len() - self.won()
+    }
+
+    pub fn total_mistakes(&self) -> u32 {
+        self.records.iter().map(|record| record.mistakes).sum()
+    }
+}
+
+fn record_win(
+    trigger: Trigger<ShowCongrats>,
+    mut stats: ResMut<GameStats>,
+    game_timer: Res<GameTimer>,
+    mistakes: Res<Mistakes>,
+) {
+    if trigger.event().0 {
+        stats.records.push(GameRecord {
+            won: true,
+            time: game_timer.elapsed(),
+            mistakes: mistakes.count,
+        });
+    }
+}
+
+#[test]
+fn test_game_stats() {
+    let mut stats = GameStats::default();
+    stats.records.push(GameRecord {
+        won: true,
+        time: Duration::from_secs(300),
+        mistakes: 2,
+    });
+    stats.records.push(GameRecord {
+        won: false,
+        time: Duration::from_secs(100),
+        mistakes: 3,
+    });
+    assert_eq!(stats.won(), 1);
+    assert_eq!(stats.lost(), 1);
+    assert_eq!(stats.total_mistakes(), 5);
+}
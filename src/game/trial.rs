@@ -0,0 +1,407 @@
+use crate::{
+    GameState,
+    game::{
+        PuzzleStarted, ResetPuzzle, SudokuManager,
+        auto_candidates::{CandidateElimination, CandidateGrid},
+        cell_state::{
+            AutoCandidates, CellMode, CellValueBundle, ConflictCell, CornerCandidates,
+            CorrectionCell, DigitValueCell, FixedCell, ManualCandidates, RevealedCell, TrialCell,
+        },
+        guided_solve::panel_button,
+        input::board_input_allowed,
+        mistakes::MistakesMade,
+        position::CellPosition,
+        scoring::PuzzleSolved,
+    },
+    i18n::Localized,
+    loading::FontAssets,
+    share::ShowToast,
+    theme::{ThemeRole, ThemedText},
+};
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+};
+use sudoku::{bitset::Set, board::Digit};
+
+/// 试填: 先记下盘面再大胆猜, 猜错了一键退回
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<TrialBranches>()
+        .add_systems(
+            Update,
+            (
+                trial_shortcut.run_if(board_input_allowed),
+                mark_trial_cells,
+                flag_contradiction.run_if(trial_active),
+                update_trial_bar.run_if(
+                    resource_changed::<TrialBranches>.or(any_match_filter::<Added<TrialBar>>),
+                ),
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(OnExit(GameState::Playing), clear_branches)
+        .add_observer(on_trial_control)
+        .add_observer(
+            |_: Trigger<PuzzleStarted>, mut trial: ResMut<TrialBranches>| {
+                *trial = TrialBranches::default();
+            },
+        )
+        .add_observer(
+            |_: Trigger<ResetPuzzle>, mut trial: ResMut<TrialBranches>| {
+                *trial = TrialBranches::default();
+            },
+        )
+        .add_observer(
+            |_: Trigger<PuzzleSolved>, mut trial: ResMut<TrialBranches>| {
+                *trial = TrialBranches::default();
+            },
+        );
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrialControl {
+    /// 记下当前盘面, 在试填中可以继续嵌套
+    Start,
+    /// 保留最近一层试填的结果
+    Commit,
+    /// 退回最近一层试填开始时的盘面
+    Revert,
+}
+
+/// 一个格子的全部内容
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CellSnapshot {
+    pub digit: Option<Digit>,
+    pub manual: Set<Digit>,
+    pub auto: Set<Digit>,
+    pub corner: Set<Digit>,
+    pub mode: CellMode,
+}
+
+pub(crate) type CellValues = (
+    &'static DigitValueCell,
+    &'static ManualCandidates,
+    &'static AutoCandidates,
+    &'static CornerCandidates,
+    &'static CellMode,
+);
+
+impl CellSnapshot {
+    pub(crate) fn new(
+        (digit, manual, auto, corner, mode): (
+            &DigitValueCell,
+            &ManualCandidates,
+            &AutoCandidates,
+            &CornerCandidates,
+            &CellMode,
+        ),
+    ) -> Self {
+        Self {
+            digit: digit.0,
+            manual: manual.0,
+            auto: auto.0,
+            corner: corner.0,
+            mode: *mode,
+        }
+    }
+
+    /// 格子里显示的中心候选数
+    fn marks(&self) -> Set<Digit> {
+        match self.mode {
+            CellMode::Digit => Set::NONE,
+            CellMode::AutoCandidates => self.auto,
+            CellMode::ManualCandidates => self.manual,
+        }
+    }
+
+    pub(crate) fn bundle(&self) -> CellValueBundle {
+        CellValueBundle {
+            digit_value: DigitValueCell(self.digit),
+            auto_candidates: AutoCandidates(self.auto),
+            manual_candidates: ManualCandidates(self.manual),
+            corner_candidates: CornerCandidates(self.corner),
+            cell_mode: self.mode,
+        }
+    }
+}
+
+/// 每层试填开始时可编辑格子的快照, 最后一层是最近开始的
+#[derive(Resource, Debug, Default)]
+pub struct TrialBranches {
+    branches: Vec<HashMap<Entity, CellSnapshot>>,
+    /// 当前盘面已经出现矛盾
+    pub contradiction: bool,
+}
+
+impl TrialBranches {
+    pub fn depth(&self) -> usize {
+        self.branches.len()
+    }
+}
+
+pub fn trial_active(trial: Res<TrialBranches>) -> bool {
+    trial.depth() > 0
+}
+
+fn clear_branches(mut trial: ResMut<TrialBranches>) {
+    *trial = TrialBranches::default();
+}
+
+fn trial_shortcut(keyboard_input: Res<ButtonInput<KeyCode>>, mut commands: Commands) {
+    if keyboard_input.just_pressed(KeyCode::KeyT) {
+        commands.trigger(TrialControl::Start);
+    }
+}
+
+type EditableCell = (Without<FixedCell>, Without<RevealedCell>);
+
+fn on_trial_control(
+    trigger: Trigger<TrialControl>,
+    q_cell: Query<(Entity, CellValues), EditableCell>,
+    q_digit: Query<(Entity, &CellPosition, &DigitValueCell)>,
+    sudoku_manager: Res<SudokuManager>,
+    mut trial: ResMut<TrialBranches>,
+    mut commands: Commands,
+) {
+    match trigger.event() {
+        TrialControl::Start => {
+            let snapshot = q_cell
+                .iter()
+                .map(|(entity, values)| (entity, CellSnapshot::new(values)))
+                .collect();
+            trial.branches.push(snapshot);
+        }
+        TrialControl::Commit => {
+            let Some(snapshot) = trial.branches.pop() else {
+                return;
+            };
+            trial.contradiction = false;
+            if trial.depth() > 0 {
+                return;
+            }
+            // 保留最外层试填时, 把新填错的数字算作错误
+            let solution = sudoku_manager.solution.to_bytes();
+            let wrong = q_digit
+                .iter()
+                .filter(|(entity, position, digit)| {
+                    snapshot
+                        .get(entity)
+                        .is_some_and(|cell| cell.digit != digit.0)
+                        && digit
+                            .0
+                            .is_some_and(|digit| digit.get() != solution[position.0 as usize])
+                })
+                .count();
+            if wrong > 0 {
+                commands.trigger(MistakesMade(wrong as u32));
+            }
+        }
+        TrialControl::Revert => {
+            let Some(snapshot) = trial.branches.pop() else {
+                return;
+            };
+            trial.contradiction = false;
+            for (entity, values) in q_cell.iter() {
+                if let Some(cell) = snapshot.get(&entity)
+                    && *cell != CellSnapshot::new(values)
+                {
+                    commands
+                        .entity(entity)
+                        .insert(cell.bundle())
+                        .remove::<CorrectionCell>();
+                }
+            }
+            // 冲突标记只跟着选中格子更新, 退回后按整个盘面重新算一遍
+            let digits = q_digit
+                .iter()
+                .map(|(entity, position, digit)| {
+                    let digit = snapshot.get(&entity).map_or(digit.0, |cell| cell.digit);
+                    (entity, *position, digit)
+                })
+                .collect::<Vec<_>>();
+            for (entity, conflict) in conflicts(&digits) {
+                if conflict.is_empty() {
+                    commands.entity(entity).remove::<ConflictCell>();
+                } else {
+                    commands.entity(entity).insert(ConflictCell(conflict));
+                }
+            }
+        }
+    }
+}
+
+/// 每个格子和哪些同行、同列、同宫的格子填了相同的数字
+pub(crate) fn conflicts(
+    digits: &[(Entity, CellPosition, Option<Digit>)],
+) -> Vec<(Entity, HashSet<Entity>)> {
+    digits
+        .iter()
+        .map(|(entity, position, digit)| {
+            let conflict = digits
+                .iter()
+                .filter(|(other, other_position, other_digit)| {
+                    other != entity
+                        && digit.is_some()
+                        && other_digit == digit
+                        && position.in_range(other_position)
+                })
+                .map(|(other, _, _)| *other)
+                .collect();
+            (*entity, conflict)
+        })
+        .collect()
+}
+
+/// 和最外层试填开始时不同的格子换一种颜色
+fn mark_trial_cells(
+    trial: Res<TrialBranches>,
+    q_cell: Query<(Entity, CellValues, Has<TrialCell>), EditableCell>,
+    mut commands: Commands,
+) {
+    let base = trial.branches.first();
+    for (entity, values, is_trial) in q_cell.iter() {
+        let changed = base
+            .and_then(|base| base.get(&entity))
+            .is_some_and(|cell| *cell != CellSnapshot::new(values));
+        if changed && !is_trial {
+            commands.entity(entity).insert(TrialCell);
+        } else if !changed && is_trial {
+            commands.entity(entity).remove::<TrialCell>();
+        }
+    }
+}
+
+/// 有重复的数字, 或者某个空格已经没有能填的数字
+pub(crate) fn has_contradiction(cells: &[(CellPosition, CellSnapshot)]) -> bool {
+    let mut grid = CandidateGrid::default();
+    for (position, cell) in cells {
+        grid.set_digit(position.0 as usize, cell.digit);
+    }
+    let possible = grid.candidates(CandidateElimination::Singles);
+    cells.iter().any(|(position, cell)| match cell.digit {
+        Some(digit) => cells.iter().any(|(other, other_cell)| {
+            other != position && other_cell.digit == Some(digit) && position.in_range(other)
+        }),
+        None => {
+            let possible = possible[position.0 as usize];
+            let marks = cell.marks();
+            if marks.is_empty() {
+                possible.is_empty()
+            } else {
+                (marks & possible).is_empty()
+            }
+        }
+    })
+}
+
+fn flag_contradiction(
+    q_cell: Query<(&CellPosition, CellValues)>,
+    mut trial: ResMut<TrialBranches>,
+    mut commands: Commands,
+) {
+    let cells = q_cell
+        .iter()
+        .map(|(position, values)| (*position, CellSnapshot::new(values)))
+        .collect::<Vec<_>>();
+    let contradiction = has_contradiction(&cells);
+    if trial.contradiction != contradiction {
+        trial.contradiction = contradiction;
+        if contradiction {
+            commands.trigger(ShowToast("This trial has a contradiction".to_string()));
+        }
+    }
+}
+
+#[derive(Component)]
+struct TrialBar;
+
+#[derive(Component)]
+struct TrialText;
+
+/// 工具栏上的试填层数, 以及保留和退回按钮
+pub(crate) fn trial_bar(font_assets: &Res<FontAssets>, builder: &mut ChildSpawnerCommands<'_>) {
+    builder
+        .spawn((
+            TrialBar,
+            Node {
+                display: Display::None,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(6.0),
+                margin: UiRect::right(Val::Px(16.0)),
+                ..default()
+            },
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                TrialText,
+                Localized::new(""),
+                TextFont {
+                    font_size: 16.0,
+                    font: font_assets.franklin_500.clone(),
+                    ..default()
+                },
+                ThemedText(ThemeRole::Text),
+            ));
+            panel_button(font_assets, builder, "Commit", TrialControl::Commit, ());
+            panel_button(font_assets, builder, "Revert", TrialControl::Revert, ());
+        });
+}
+
+fn update_trial_bar(
+    trial: Res<TrialBranches>,
+    mut q_bar: Query<&mut Node, With<TrialBar>>,
+    mut q_text: Query<(&mut Localized, &mut ThemedText), With<TrialText>>,
+) {
+    for mut node in q_bar.iter_mut() {
+        node.display = if trial.depth() > 0 {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    for (mut text, mut themed) in q_text.iter_mut() {
+        if trial.contradiction {
+            *text = Localized::with_args("Trial {}: contradiction", [trial.depth()]);
+            themed.set_if_neq(ThemedText(ThemeRole::Conflict));
+        } else {
+            *text = Localized::with_args("Trial {}", [trial.depth()]);
+            themed.set_if_neq(ThemedText(ThemeRole::Text));
+        }
+    }
+}
+
+#[test]
+fn test_has_contradiction() {
+    let empty = CellSnapshot {
+        digit: None,
+        manual: Set::NONE,
+        auto: Set::NONE,
+        corner: Set::NONE,
+        mode: CellMode::ManualCandidates,
+    };
+    let digit = |digit: u8| CellSnapshot {
+        digit: Some(Digit::new(digit)),
+        mode: CellMode::Digit,
+        ..empty
+    };
+    let mut cells = (0..81)
+        .map(|index| (CellPosition::new(index), empty))
+        .collect::<Vec<_>>();
+    assert!(!has_contradiction(&cells));
+
+    // 第一行填了 1 到 8, 最后一格只剩 9
+    for (col, (_, cell)) in cells.iter_mut().take(8).enumerate() {
+        *cell = digit(col as u8 + 1);
+    }
+    assert!(!has_contradiction(&cells));
+
+    // 只标了 5 的格子已经没有能填的数字
+    cells[8].1.manual = Digit::new(5).as_set();
+    assert!(has_contradiction(&cells));
+    cells[8].1.manual = Set::NONE;
+
+    // 同一列出现两个 1
+    cells[9 * 4].1 = digit(1);
+    assert!(has_contradiction(&cells));
+}
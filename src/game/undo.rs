@@ -0,0 +1,255 @@
+use crate::{
+    GameState,
+    game::{
+        AutoCandidateMode, PuzzleStarted, ResetPuzzle,
+        cell_state::{
+            CellMode, ConflictCell, CornerCandidates, CorrectionCell, DigitValueCell, FixedCell,
+            ManualCandidates, RevealedCell,
+        },
+        coop::RemoteEdits,
+        guided_solve::GuidedSolve,
+        input::board_input_allowed,
+        lessons::ActiveLesson,
+        position::CellPosition,
+        trial::{TrialControl, conflicts},
+    },
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+use sudoku::{bitset::Set, board::Digit};
+
+/// 撤销: 数字、中心候选数和角标三层分别记录, 撤销时只还原那一步改过的层
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<UndoHistory>()
+        .add_systems(
+            Update,
+            undo_shortcut.run_if(in_state(GameState::Playing).and(board_input_allowed)),
+        )
+        .add_systems(PostUpdate, track_edits.run_if(in_state(GameState::Playing)))
+        .add_systems(OnExit(GameState::Playing), clear_history)
+        .add_observer(on_undo)
+        .add_observer(on_trial_control)
+        .add_observer(
+            |_: Trigger<PuzzleStarted>, mut history: ResMut<UndoHistory>| {
+                history.restart();
+            },
+        )
+        .add_observer(
+            |_: Trigger<ResetPuzzle>, mut history: ResMut<UndoHistory>| {
+                history.restart();
+            },
+        );
+}
+
+/// 撤销最近一步, 快捷键 Ctrl/Cmd + Z
+#[derive(Event)]
+pub struct Undo;
+
+/// 一个格子里玩家能编辑的内容
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CellLayers {
+    digit: Option<Digit>,
+    centre: Set<Digit>,
+    corner: Set<Digit>,
+}
+
+/// 一步操作之前的内容, 没改动的层是 None
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct LayerEdit {
+    digit: Option<Option<Digit>>,
+    centre: Option<Set<Digit>>,
+    corner: Option<Set<Digit>>,
+}
+
+impl LayerEdit {
+    fn between(before: &CellLayers, after: &CellLayers) -> Self {
+        Self {
+            digit: (before.digit != after.digit).then_some(before.digit),
+            centre: (before.centre != after.centre).then_some(before.centre),
+            corner: (before.corner != after.corner).then_some(before.corner),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 每一步改动过的格子, 最后一步是最近的
+#[derive(Resource, Debug, Default)]
+pub struct UndoHistory {
+    steps: Vec<Vec<(Entity, LayerEdit)>>,
+    /// 上一次看到的格子内容
+    known: HashMap<Entity, CellLayers>,
+    /// 每层试填开始时的步数, 退回试填时一起丢掉之后的步骤
+    trial_marks: Vec<usize>,
+    /// 下一次只更新已知内容, 不算作一步
+    skip: bool,
+    /// 讲解和课程时不记录也不能撤销, 结束时还原盘面也不算
+    paused: bool,
+}
+
+impl UndoHistory {
+    fn restart(&mut self) {
+        self.steps.clear();
+        self.trial_marks.clear();
+        self.skip = true;
+    }
+}
+
+fn clear_history(mut history: ResMut<UndoHistory>) {
+    *history = UndoHistory::default();
+}
+
+type EditableCell = (Without<FixedCell>, Without<RevealedCell>);
+
+/// 每帧结束时把格子的变化合成一步
+#[allow(clippy::type_complexity)]
+fn track_edits(
+    q_cell: Query<
+        (
+            Entity,
+            &DigitValueCell,
+            &ManualCandidates,
+            &CornerCandidates,
+        ),
+        (
+            EditableCell,
+            Or<(
+                Changed<DigitValueCell>,
+                Changed<ManualCandidates>,
+                Changed<CornerCandidates>,
+            )>,
+        ),
+    >,
+    mut history: ResMut<UndoHistory>,
+    guided: Option<Res<GuidedSolve>>,
+    lesson: Option<Res<ActiveLesson>>,
+    remote_edits: Res<RemoteEdits>,
+) {
+    let paused = guided.is_some() || lesson.is_some();
+    let record = !paused && !history.paused && !history.skip;
+    history.paused = paused;
+    history.skip = false;
+
+    let mut step = vec![];
+    for (entity, digit, centre, corner) in q_cell.iter() {
+        let after = CellLayers {
+            digit: digit.0,
+            centre: centre.0,
+            corner: corner.0,
+        };
+        // 合作时对方改的格子只记下内容, 不能在本机撤销
+        if let Some(before) = history.known.insert(entity, after)
+            && record
+            && !remote_edits.contains(&entity)
+        {
+            let edit = LayerEdit::between(&before, &after);
+            if !edit.is_empty() {
+                step.push((entity, edit));
+            }
+        }
+    }
+    if !step.is_empty() {
+        history.steps.push(step);
+    }
+}
+
+fn on_undo(
+    _trigger: Trigger<Undo>,
+    mut history: ResMut<UndoHistory>,
+    q_cell: Query<(Entity, &CellPosition, &DigitValueCell)>,
+    auto_mode: Res<AutoCandidateMode>,
+    mut commands: Commands,
+) {
+    if history.paused {
+        return;
+    }
+    let Some(step) = history.steps.pop() else {
+        return;
+    };
+    history.skip = true;
+
+    let mut digits = q_cell
+        .iter()
+        .map(|(entity, position, digit)| (entity, *position, digit.0))
+        .collect::<Vec<_>>();
+    for (entity, edit) in step {
+        let mut cell = commands.entity(entity);
+        if let Some(digit) = edit.digit {
+            // 空格按现在的自动候选数模式显示, 期间可能切换过
+            let mode = match digit {
+                Some(_) => CellMode::Digit,
+                None if **auto_mode => CellMode::AutoCandidates,
+                None => CellMode::ManualCandidates,
+            };
+            cell.insert((DigitValueCell(digit), mode))
+                .remove::<CorrectionCell>();
+            if let Some(index) = digits.iter().position(|(other, ..)| *other == entity) {
+                digits[index].2 = digit;
+            }
+        }
+        if let Some(centre) = edit.centre {
+            cell.insert(ManualCandidates(centre));
+        }
+        if let Some(corner) = edit.corner {
+            cell.insert(CornerCandidates(corner));
+        }
+    }
+
+    // 和退回试填一样按整个盘面重新算冲突
+    for (entity, conflict) in conflicts(&digits) {
+        if conflict.is_empty() {
+            commands.entity(entity).remove::<ConflictCell>();
+        } else {
+            commands.entity(entity).insert(ConflictCell(conflict));
+        }
+    }
+}
+
+fn on_trial_control(trigger: Trigger<TrialControl>, mut history: ResMut<UndoHistory>) {
+    match trigger.event() {
+        TrialControl::Start => {
+            let steps = history.steps.len();
+            history.trial_marks.push(steps);
+        }
+        TrialControl::Commit => {
+            history.trial_marks.pop();
+        }
+        TrialControl::Revert => {
+            if let Some(steps) = history.trial_marks.pop() {
+                history.steps.truncate(steps);
+                history.skip = true;
+            }
+        }
+    }
+}
+
+fn undo_shortcut(keyboard_input: Res<ButtonInput<KeyCode>>, mut commands: Commands) {
+    let modifier = keyboard_input.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+    if modifier && keyboard_input.just_pressed(KeyCode::KeyZ) {
+        commands.trigger(Undo);
+    }
+}
+
+#[test]
+fn test_layer_edit_keeps_only_changed_layers() {
+    let before = CellLayers {
+        digit: None,
+        centre: Digit::new(1).as_set() | Digit::new(2).as_set(),
+        corner: Set::NONE,
+    };
+    let after = CellLayers {
+        corner: Digit::new(5).as_set(),
+        ..before
+    };
+    let edit = LayerEdit::between(&before, &after);
+    assert_eq!(edit.digit, None);
+    assert_eq!(edit.centre, None);
+    assert_eq!(edit.corner, Some(Set::NONE));
+    assert!(LayerEdit::between(&before, &before).is_empty());
+}
@@ -0,0 +1,524 @@
+use crate::game::Settings;
+use bevy::{prelude::*, ui::UiSystem};
+use std::fmt::Display;
+
+pub struct I18nPlugin;
+
+/// 界面文字按 [`Locale`] 翻译, 切换语言时带 [`Localized`] 的文字会一起刷新
+impl Plugin for I18nPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Locale>()
+            .add_systems(Startup, load_cjk_font)
+            .add_systems(Update, sync_locale.run_if(resource_changed::<Settings>))
+            .add_systems(
+                PostUpdate,
+                (apply_localized_text, apply_localized_font).before(UiSystem::Prepare),
+            );
+    }
+}
+
+/// 中文字形的后备字体, 自带的 Franklin 和 Karnak 字体没有中文
+///
+/// 字体文件不随仓库分发, 桌面端没有这个文件时改用系统自带的中文字体,
+/// 都加载不到时设置里不显示语言选项
+pub const CJK_FONT_PATH: &str = "fonts/NotoSansSC-Regular.otf";
+
+/// 桌面端常见的系统中文字体, 按顺序找第一个存在的
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+const SYSTEM_CJK_FONTS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/STHeiti Medium.ttc",
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    SimplifiedChinese,
+}
+
+impl Language {
+    pub fn next(self) -> Self {
+        match self {
+            Language::English => Language::SimplifiedChinese,
+            Language::SimplifiedChinese => Language::English,
+        }
+    }
+
+    /// 用该语言自己的写法显示名字
+    pub fn label(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::SimplifiedChinese => "简体中文",
+        }
+    }
+
+    /// 查不到翻译时原样返回英文
+    pub fn tr<'a>(&self, key: &'a str) -> &'a str {
+        self.catalog()
+            .iter()
+            .find(|(english, _)| *english == key)
+            .map_or(key, |(_, translated)| translated)
+    }
+
+    /// 翻译带 `{}` 占位符的文字, 参数按顺序填入
+    pub fn format(&self, key: &str, args: &[&dyn Display]) -> String {
+        fill_placeholders(self.tr(key), args)
+    }
+
+    fn catalog(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::English => &[],
+            Language::SimplifiedChinese => ZH_HANS,
+        }
+    }
+
+    fn needs_cjk_font(&self) -> bool {
+        matches!(self, Language::SimplifiedChinese)
+    }
+
+    /// 按该语言的习惯写日期
+    pub fn format_date(&self, date: chrono::NaiveDate) -> String {
+        match self {
+            Language::English => date.format("%B %d, %Y").to_string(),
+            Language::SimplifiedChinese => date.format("%Y年%-m月%-d日").to_string(),
+        }
+    }
+}
+
+/// 当前界面语言
+#[derive(Resource, Debug, Default)]
+pub struct Locale {
+    pub language: Language,
+    cjk_font: Option<Handle<Font>>,
+}
+
+impl Locale {
+    pub fn tr<'a>(&self, key: &'a str) -> &'a str {
+        self.language.tr(key)
+    }
+
+    pub fn format(&self, key: &str, args: &[&dyn Display]) -> String {
+        self.language.format(key, args)
+    }
+
+    /// 中文字体加载好以后才能切换语言
+    pub fn can_switch_language(&self, fonts: &Assets<Font>) -> bool {
+        self.cjk_font
+            .as_ref()
+            .is_some_and(|font| fonts.contains(font))
+    }
+}
+
+fn fill_placeholders(template: &str, args: &[&dyn Display]) -> String {
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    let mut result = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            result.push_str(&arg.to_string());
+        }
+        result.push_str(part);
+    }
+    result
+}
+
+/// 按英文原文翻译的文字, `args` 依次填入 `{}` 占位符, 本身也会被翻译
+#[derive(Component, Debug, Clone, Default)]
+#[require(Text, LocalizedFont)]
+pub struct Localized {
+    pub key: &'static str,
+    pub args: Vec<String>,
+}
+
+impl Localized {
+    pub fn new(key: &'static str) -> Self {
+        Self { key, args: vec![] }
+    }
+
+    pub fn with_args(key: &'static str, args: impl IntoIterator<Item = impl ToString>) -> Self {
+        Self {
+            key,
+            args: args.into_iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+}
+
+/// 需要中文字形的文字, 记住原来的字体以便切回英文
+#[derive(Component, Debug, Default)]
+pub struct LocalizedFont {
+    latin: Option<Handle<Font>>,
+}
+
+/// 启动时就试着加载中文字体, 以便知道能不能提供中文
+#[cfg(any(target_arch = "wasm32", target_os = "android", target_os = "ios"))]
+fn load_cjk_font(asset_server: Res<AssetServer>, mut locale: ResMut<Locale>) {
+    locale.cjk_font = Some(asset_server.load(CJK_FONT_PATH));
+}
+
+/// 启动时就试着加载中文字体, 以便知道能不能提供中文
+///
+/// 没有自带的字体文件时从系统字体里找, 免得资源加载报错
+#[cfg(not(any(target_arch = "wasm32", target_os = "android", target_os = "ios")))]
+fn load_cjk_font(
+    asset_server: Res<AssetServer>,
+    mut fonts: ResMut<Assets<Font>>,
+    mut locale: ResMut<Locale>,
+) {
+    use bevy::asset::io::file::FileAssetReader;
+
+    let bundled = FileAssetReader::get_base_path()
+        .join("assets")
+        .join(CJK_FONT_PATH);
+    if bundled.exists() {
+        locale.cjk_font = Some(asset_server.load(CJK_FONT_PATH));
+        return;
+    }
+    let system_font = SYSTEM_CJK_FONTS
+        .iter()
+        .filter_map(|path| std::fs::read(path).ok())
+        .find_map(|bytes| Font::try_from_bytes(bytes).ok());
+    match system_font {
+        Some(font) => locale.cjk_font = Some(fonts.add(font)),
+        None => info!("no CJK font found, language switching is disabled"),
+    }
+}
+
+fn sync_locale(settings: Res<Settings>, mut locale: ResMut<Locale>) {
+    if locale.language == settings.language {
+        return;
+    }
+    locale.language = settings.language;
+}
+
+fn apply_localized_text(locale: Res<Locale>, mut q_text: Query<(Ref<Localized>, &mut Text)>) {
+    for (localized, mut text) in q_text.iter_mut() {
+        if locale.is_changed() || localized.is_changed() {
+            let args = localized
+                .args
+                .iter()
+                .map(|arg| locale.tr(arg))
+                .collect::<Vec<_>>();
+            let args = args
+                .iter()
+                .map(|arg| arg as &dyn Display)
+                .collect::<Vec<_>>();
+            text.0 = locale.format(localized.key, &args);
+        }
+    }
+}
+
+fn apply_localized_font(
+    locale: Res<Locale>,
+    fonts: Res<Assets<Font>>,
+    mut cjk_active: Local<bool>,
+    mut q_text: Query<(&mut LocalizedFont, &mut TextFont)>,
+) {
+    // 字体文件缺失时保持原来的字体, 至少英文部分能正常显示
+    let cjk_font = locale
+        .cjk_font
+        .as_ref()
+        .filter(|font| locale.language.needs_cjk_font() && fonts.contains(*font));
+    let refresh = *cjk_active != cjk_font.is_some();
+    *cjk_active = cjk_font.is_some();
+
+    for (mut localized_font, mut text_font) in q_text.iter_mut() {
+        if !refresh && !localized_font.is_added() {
+            continue;
+        }
+        match cjk_font {
+            Some(cjk_font) => {
+                if localized_font.latin.is_none() {
+                    localized_font.latin = Some(text_font.font.clone());
+                }
+                text_font.font = cjk_font.clone();
+            }
+            None => {
+                if let Some(latin) = localized_font.latin.take() {
+                    text_font.font = latin;
+                }
+            }
+        }
+    }
+}
+
+/// 简体中文, 按英文原文查找
+#[rustfmt::skip]
+const ZH_HANS: &[(&str, &str)] = &[
+    // 菜单
+    ("Sudoku", "数独"),
+    ("Try this numbers game,", "试试这个数字游戏，"),
+    ("minus the math.", "不用做数学题。"),
+    ("Choose Your Puzzle:", "选择你的谜题："),
+    ("Easy", "简单"),
+    ("Medium", "中等"),
+    ("Hard", "困难"),
+    ("Versus", "对战"),
+    ("Made with Bevy", "使用 Bevy 制作"),
+    ("Open source", "开源"),
+    // 工具栏和更多菜单
+    ("Back", "返回"),
+    ("Reset Puzzle", "重置谜题"),
+    ("Watch Replay", "观看回放"),
+    ("Share Result", "分享成绩"),
+    ("Hint", "提示"),
+    ("Check Cell", "检查格子"),
+    ("Check Puzzle", "检查谜题"),
+    ("Am I on Track?", "我做对了吗？"),
+    ("Undo", "撤销"),
+    ("Try a Guess", "试填"),
+    ("Reveal Cell", "揭示格子"),
+    ("Fill Candidates", "填满候选数"),
+    ("Remove Impossible", "删除不可能的候选数"),
+    ("Guided Solve", "引导解题"),
+    ("Reveal Puzzle", "揭示谜题"),
+    ("Share", "分享"),
+    ("Paste Code", "粘贴分享码"),
+    ("Ping Partner", "提醒队友"),
+    ("Mistakes: {}/{}", "错误：{}/{}"),
+    ("Mistakes: {}", "错误：{}"),
+    // 控制面板
+    ("Normal", "普通"),
+    ("Candidate", "候选"),
+    ("Corner", "角标"),
+    ("Auto Candidate Mode", "自动候选模式"),
+    // 暂停和玩法说明
+    ("Your game has been paused", "游戏已暂停"),
+    ("Resume", "继续"),
+    ("How to play Sudoku", "数独玩法"),
+    ("Fill each 3 x 3 set with numbers 1–9.", "在每个 3 x 3 宫格中填入数字 1–9。"),
+    ("Tap a cell in any set, then select a number.", "点选任意宫格中的一个格子，然后选择一个数字。"),
+    ("Fill cells until the board is complete. Numbers in sets, rows or columns cannot repeat.", "填满所有格子即完成。同一宫、行或列中的数字不能重复。"),
+    ("Note: Each number can only appear on the board 9 times.", "注意：每个数字在棋盘上只能出现 9 次。"),
+    ("Start lessons", "开始教程"),
+    ("Play modes and tips", "玩法模式与提示"),
+    ("Normal mode: Add 1 number to a cell.", "普通模式：在格子中填入 1 个数字。"),
+    ("Candidate mode: Add several numbers to a cell (for multiple options).", "候选模式：在格子中填入多个数字（表示多种可能）。"),
+    ("Need a clue? Tap -> \"Hint\" to see the next logical cell to solve.", "需要线索？点击 ->「提示」查看下一个可以推理出的格子。"),
+    ("Choose from 3 levels — easy, medium and hard. To change levels, tap \"Back\" in the toolbar.", "共有简单、中等、困难 3 个难度。要更换难度，请点击工具栏中的「返回」。"),
+    ("New puzzles for each level are released daily: Sunday–Thursday at 10 p.m. E.T.; Friday–Saturday at 6 p.m. E.T.", "每个难度每天发布新题：周日至周四美东时间晚上 10 点；周五至周六美东时间下午 6 点。"),
+    ("Have feedback? ", "有意见反馈？"),
+    ("Email us", "给我们发邮件"),
+    // 设置
+    ("Settings", "设置"),
+    ("Check guesses when entered", "输入时检查答案"),
+    ("Start in automatic mode", "以自动模式开始"),
+    ("Highlight conflicts", "高亮冲突"),
+    ("Play sound on solve", "完成时播放音效"),
+    ("Show clock", "显示计时"),
+    ("Dark mode", "深色模式"),
+    ("High contrast", "高对比度"),
+    ("Accessibility cues", "无障碍标记"),
+    ("Count mistakes", "统计错误"),
+    ("Strict mode", "严格模式"),
+    ("Mistakes allowed: {}", "允许错误次数：{}"),
+    ("Colour palette: {}", "配色：{}"),
+    ("Text size: {}", "文字大小：{}"),
+    ("Auto candidates: {}", "自动候选：{}"),
+    ("Language: {}", "语言：{}"),
+    ("Auto pause: {}", "自动暂停：{}"),
+    ("New puzzles: {}", "新题目难度：{}"),
+    ("Any", "不限"),
+    ("Generating puzzle...", "正在生成题目…"),
+    ("{} min", "{} 分钟"),
+    ("Off", "关闭"),
+    ("Standard", "标准"),
+    ("Deuteranopia", "绿色弱"),
+    ("Protanopia", "红色弱"),
+    ("Tritanopia", "蓝黄色弱"),
+    ("Singles", "唯一数"),
+    ("Locked candidates", "区块排除"),
+    ("Pairs", "数对"),
+    // 完成和失败
+    ("You finished a puzzle in {}", "你用 {} 完成了谜题"),
+    ("You finished an Easy puzzle in {}", "你用 {} 完成了简单谜题"),
+    ("You finished a Medium puzzle in {}", "你用 {} 完成了中等谜题"),
+    ("You finished a Hard puzzle in {}", "你用 {} 完成了困难谜题"),
+    ("Play another Sudoku", "再玩一局数独"),
+    ("Watch replay", "观看回放"),
+    ("Share result", "分享成绩"),
+    ("Score", "得分"),
+    ("{} leaderboard", "{}排行榜"),
+    ("Game over", "游戏结束"),
+    ("You made {} mistakes. This session: {} won, {} lost, {} mistakes.", "你犯了 {} 次错误。本次游戏：赢 {} 局，输 {} 局，共 {} 次错误。"),
+    ("Retry", "重试"),
+    ("Cancel", "取消"),
+    ("Custom Puzzle", "自定义题目"),
+    ("Start", "开始"),
+    ("Symmetry: {}", "对称：{}"),
+    ("Clues: {}", "提示数：{}"),
+    ("Minimal: {}", "最简：{}"),
+    ("None", "无"),
+    ("Rotational", "旋转对称"),
+    ("Mirror", "镜像对称"),
+    ("Diagonal", "对角线对称"),
+    ("On", "开启"),
+    ("Practice: {}", "练习：{}"),
+    ("Other steps up to: {}", "其余步骤最难：{}"),
+    ("No puzzle matched the custom settings", "没有找到符合自定义条件的题目"),
+    // 出题器
+    ("Puzzle Editor", "出题器"),
+    ("Givens: {}", "提示数：{}"),
+    ("Solutions: {}", "解的个数：{}"),
+    ("Many", "多个"),
+    ("Rating: {}", "评级：{}"),
+    ("Erase", "擦除"),
+    ("Clear", "清空"),
+    ("Open saved", "打开收藏"),
+    ("Save", "保存"),
+    ("Export", "导出"),
+    ("Start solving", "开始解题"),
+    ("No saved puzzles", "还没有收藏的题目"),
+    ("The puzzle needs exactly one solution", "题目必须有且只有一个解"),
+    ("Saved to collection", "已保存到收藏"),
+    ("Already in collection", "收藏里已经有这道题"),
+    ("Could not save puzzle", "无法保存题目"),
+    ("Reveal puzzle?", "揭示整道谜题？"),
+    ("This fills in every remaining cell and ends the game.", "这会填好剩下的所有格子并结束本局。"),
+    ("Reveal", "揭示"),
+    ("Reset puzzle?", "重置谜题？"),
+    ("This clears all your progress on this puzzle.", "这会清除你在这道题上的所有进度。"),
+    ("Reset", "重置"),
+    ("New puzzle", "新谜题"),
+    // 分享
+    ("Share link copied", "分享链接已复制"),
+    // 试填
+    ("Trial {}", "试填第 {} 层"),
+    ("Trial {}: contradiction", "试填第 {} 层：出现矛盾"),
+    ("Commit", "保留"),
+    ("Revert", "退回"),
+    ("This trial has a contradiction", "这次试填出现了矛盾"),
+    // 进度检查
+    ("You're on track", "目前都对"),
+    ("1 cell doesn't match the solution", "有 1 个格子和答案不符"),
+    ("{} cells don't match the solution", "有 {} 个格子和答案不符"),
+    ("Loaded shared puzzle", "已载入分享的谜题"),
+    ("No puzzle code in clipboard", "剪贴板里没有谜题分享码"),
+    ("Replace current game?", "替换当前对局？"),
+    ("Your progress on this puzzle will be lost.", "你在这道题上的进度将会丢失。"),
+    ("Replace", "替换"),
+    ("Result copied", "成绩已复制"),
+    ("Could not access the clipboard", "无法访问剪贴板"),
+    // 教程、引导解题和回放
+    ("Prev", "上一课"),
+    ("Another", "换一题"),
+    ("Next", "下一步"),
+    ("Exit", "退出"),
+    ("Play", "播放"),
+    ("Pause", "暂停"),
+    ("Speed {}x", "速度 {}x"),
+    ("Step {}/{}: {}", "第 {}/{} 步：{}"),
+    ("{} steps, press Play or Next", "共 {} 步，按播放或下一步"),
+    ("No logical steps found", "找不到可以推理的步骤"),
+    ("Replay {} ({}/{} moves)", "回放 {}（{}/{} 步）"),
+    ("Lesson {}: {}{}", "第 {} 课：{}{}"),
+    (" (completed)", "（已完成）"),
+    ("Completed {}/{} lessons", "已完成 {}/{} 课"),
+    ("Enter the digit this technique reveals.", "填入这个技巧找到的数字。"),
+    ("Tap candidates to remove the ones this technique rules out.", "点击候选数，删除这个技巧排除掉的数字。"),
+    ("Well done! Move on to the next lesson or try another puzzle.", "做得好！继续下一课，或换一道题。"),
+    ("That move doesn't follow from {}. Try again.", "这一步不能由{}推出，再试一次。"),
+    ("Good. {} more to remove.", "很好，还要删除 {} 个。"),
+    ("Full House", "满格唯一数"),
+    ("Naked Single", "显性唯一数"),
+    ("Hidden Single", "隐性唯一数"),
+    ("Locked Candidates", "区块排除"),
+    ("Pointing Pair", "宫内区块"),
+    ("Box/Line Reduction", "行列区块"),
+    ("Naked Pair", "显性数对"),
+    ("Hidden Pair", "隐性数对"),
+    ("Naked Triple", "显性三数组"),
+    ("Hidden Triple", "隐性三数组"),
+    ("Naked Quad", "显性四数组"),
+    ("Hidden Quad", "隐性四数组"),
+    ("X-Wing", "X 翼"),
+    ("Swordfish", "剑鱼"),
+    ("Jellyfish", "水母"),
+    ("XY-Wing", "XY 翼"),
+    ("XYZ-Wing", "XYZ 翼"),
+    ("Mutant Swordfish", "变异剑鱼"),
+    ("Mutant Jellyfish", "变异水母"),
+    ("Avoidable Rectangle", "可规避矩形"),
+    ("Unknown", "未知"),
+    ("A row, column or box has only one empty cell left. Fill in the missing digit.", "某行、列或宫只剩一个空格，填入缺少的数字。"),
+    ("A cell has only one candidate left. Find it and enter the digit.", "某个格子只剩一个候选数，找到它并填入。"),
+    ("A digit fits in only one cell of a row, column or box. Find it and enter the digit.", "某个数字在一行、一列或一宫中只能放在一个格子里，找到它并填入。"),
+    ("Inside a box a digit is confined to one row or column. Remove it from the rest of that line.", "在一宫内某个数字只出现在同一行或同一列，把它从这一行或列的其他格子中删除。"),
+    ("Inside a row or column a digit is confined to one box. Remove it from the rest of that box.", "在一行或一列内某个数字只出现在同一宫，把它从这一宫的其他格子中删除。"),
+    ("Two cells in a house share the same two candidates. Remove those digits from the other cells.", "同一区域中两个格子的候选数是相同的两个数字，把它们从其他格子中删除。"),
+    ("Two digits appear in only two cells of a house. Remove every other candidate from those cells.", "两个数字在同一区域中只出现在两个格子里，删除这两个格子的其他候选数。"),
+    ("Three cells in a house hold only three digits between them. Remove those digits elsewhere.", "同一区域中三个格子的候选数只有三个数字，把它们从其他格子中删除。"),
+    ("Three digits appear in only three cells of a house. Remove the other candidates from them.", "三个数字在同一区域中只出现在三个格子里，删除这些格子的其他候选数。"),
+    ("A digit sits in the same two columns in two rows. Remove it from the rest of those columns.", "某个数字在两行中都只出现在相同的两列，把它从这两列的其他格子中删除。"),
+    ("A digit is limited to three columns across three rows. Remove it from the rest of those columns.", "某个数字在三行中都只出现在相同的三列，把它从这三列的其他格子中删除。"),
+    ("A pivot cell XY sees pincers XZ and YZ. Remove Z from cells that see both pincers.", "枢纽格 XY 能看到钳子格 XZ 和 YZ，从同时能看到两个钳子格的格子中删除 Z。"),
+    // 分屏对战
+    ("Player 1", "玩家 1"),
+    ("Player 2", "玩家 2"),
+    ("WASD move · 1-9 digit · hold Shift for candidates · Q clear", "WASD 移动 · 1-9 填数 · 按住 Shift 填候选 · Q 清除"),
+    ("Arrows move · numpad digit · hold Enter for candidates · 0 clear · or gamepad", "方向键移动 · 小键盘填数 · 按住 Enter 填候选 · 0 清除 · 或使用手柄"),
+    ("{}  {}/81", "{}  {}/81"),
+    ("{}  {}/81  (pad digit {})", "{}  {}/81  （手柄数字 {}）"),
+    ("{} wins!", "{}获胜！"),
+    ("Solved in {}. {} had {}/81 cells.", "用时 {} 完成。{}填对了 {}/81 格。"),
+    ("Rematch", "再来一局"),
+    ("Menu", "菜单"),
+    // 联机合作
+    ("Co-op: hosting on {}", "合作：在 {} 等待队友"),
+    ("Co-op: joining {}", "合作：正在加入 {}"),
+    ("Co-op: connected", "合作：已连接"),
+    ("Co-op: partner left", "合作：队友已离开"),
+    ("Co-op: {}", "合作：{}"),
+    ("Partner: {}", "队友：{}"),
+    ("Partner connected", "队友已连接"),
+    ("Partner says {}", "队友说：{}"),
+    // 读屏朗读
+    ("Row {}, column {}", "第 {} 行第 {} 列"),
+    (", given {}", "，题目给出 {}"),
+    (", {}", "，{}"),
+    (", empty", "，空格"),
+    (", candidates {}", "，候选数 {}"),
+    ("{} entered at row {}, column {}", "填入 {}，第 {} 行第 {} 列"),
+    ("Conflict at row {}, column {}", "第 {} 行第 {} 列有冲突"),
+    ("Puzzle solved", "谜题已解出"),
+];
+
+#[test]
+fn test_catalog_placeholders_match() {
+    for (index, (english, translated)) in ZH_HANS.iter().enumerate() {
+        assert_eq!(
+            english.matches("{}").count(),
+            translated.matches("{}").count(),
+            "{english}"
+        );
+        assert!(
+            ZH_HANS[..index].iter().all(|(other, _)| other != english),
+            "duplicate key {english}"
+        );
+    }
+    assert_eq!(
+        Language::SimplifiedChinese.format("Mistakes: {}/{}", &[&1, &3]),
+        "错误：1/3"
+    );
+    assert_eq!(
+        Language::SimplifiedChinese.tr("Not translated"),
+        "Not translated"
+    );
+}
+
+#[test]
+fn test_format_date() {
+    let date = chrono::NaiveDate::from_ymd_opt(2025, 3, 7).unwrap();
+    assert_eq!(Language::English.format_date(date), "March 07, 2025");
+    assert_eq!(
+        Language::SimplifiedChinese.format_date(date),
+        "2025年3月7日"
+    );
+}
@@ -2,11 +2,16 @@
 
 pub mod color;
 mod game;
+mod i18n;
 mod loading;
 mod menu;
 mod share;
+mod theme;
 
-use crate::{game::SudokuPlugin, loading::LoadingPlugin, menu::MenuPlugin};
+use crate::{
+    game::SudokuPlugin, i18n::I18nPlugin, loading::LoadingPlugin, menu::MenuPlugin,
+    share::SharePlugin, theme::ThemePlugin,
+};
 
 use crate::color::WHITE_COLOR;
 use bevy::{
@@ -26,6 +31,10 @@ enum GameState {
     Loading,
     // During this State the actual game is executed
     Playing,
+    // 两个玩家在同一台电脑上分屏比赛
+    Race,
+    // 出题器, 自己填提示数编题
+    Editor,
     // Here the menu is drawn and waiting for player interaction
     Menu,
 }
@@ -36,7 +45,15 @@ impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<GameState>()
             .insert_resource(ClearColor(WHITE_COLOR))
-            .add_plugins((LoadingPlugin, MenuPlugin, SudokuPlugin, AudioPlugin));
+            .add_plugins((
+                LoadingPlugin,
+                MenuPlugin,
+                SudokuPlugin,
+                SharePlugin,
+                ThemePlugin,
+                I18nPlugin,
+                AudioPlugin,
+            ));
 
         #[cfg(debug_assertions)]
         {
@@ -22,6 +22,11 @@ fn main() {
                         // Tells wasm not to override default event handling, like F5 and Ctrl+R
                         prevent_default_event_handling: false,
                         resolution: bevy::window::WindowResolution::new(1400., 1000.),
+                        resize_constraints: bevy::window::WindowResizeConstraints {
+                            min_width: 320.0,
+                            min_height: 480.0,
+                            ..default()
+                        },
                         ..default()
                     }),
                     ..default()
@@ -1,6 +1,11 @@
 use crate::{
     GameState,
     color::DARK_BLACK,
+    game::{
+        CustomPuzzle, Difficulty, Settings, next_clue_range, next_hardest_other, next_technique,
+        technique_name,
+    },
+    i18n::{Locale, Localized, LocalizedFont},
     loading::{FontAssets, TextureAssets},
     share::title_bar,
 };
@@ -13,7 +18,17 @@ pub struct MenuPlugin;
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(OnEnter(GameState::Menu), setup_menu)
-            .add_systems(Update, click_play_button.run_if(in_state(GameState::Menu)))
+            .add_systems(
+                Update,
+                (
+                    click_play_button,
+                    update_custom_labels.run_if(
+                        resource_changed::<CustomPuzzle>
+                            .or(any_match_filter::<Added<CustomOption>>),
+                    ),
+                )
+                    .run_if(in_state(GameState::Menu)),
+            )
             .add_systems(OnExit(GameState::Menu), cleanup_menu);
     }
 }
@@ -36,7 +51,12 @@ impl Default for ButtonColors {
 #[derive(Component)]
 struct Menu;
 
-fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>, font_assets: Res<FontAssets>) {
+fn setup_menu(
+    mut commands: Commands,
+    textures: Res<TextureAssets>,
+    font_assets: Res<FontAssets>,
+    locale: Res<Locale>,
+) {
     commands.spawn((Camera2d, Msaa::Off, Menu));
     commands
         .spawn((
@@ -51,6 +71,7 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>, font_assets:
         ))
         .with_children(|children| {
             title_bar(&font_assets, children);
+            custom_panel(&font_assets, children);
             children
                 .spawn((
                     Name::new("menu-container"),
@@ -99,7 +120,7 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>, font_assets:
                             ));
 
                             children.spawn((
-                                Text::new("Sudoku"),
+                                Localized::new("Sudoku"),
                                 TextFont {
                                     font_size: 48.0,
                                     font: font_assets.karnak.clone(),
@@ -109,7 +130,7 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>, font_assets:
                             ));
 
                             children.spawn((
-                                Text::new("Try this numbers game,"),
+                                Localized::new("Try this numbers game,"),
                                 TextFont {
                                     font_size: 36.0,
                                     font: font_assets.karnak_500.clone(),
@@ -118,7 +139,7 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>, font_assets:
                                 TextColor::BLACK,
                             ));
                             children.spawn((
-                                Text::new("minus the math."),
+                                Localized::new("minus the math."),
                                 TextFont {
                                     font_size: 36.0,
                                     font: font_assets.karnak_500.clone(),
@@ -135,7 +156,7 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>, font_assets:
                             ));
 
                             children.spawn((
-                                Text::new("Choose Your Puzzle:"),
+                                Localized::new("Choose Your Puzzle:"),
                                 TextFont {
                                     font_size: 16.0,
                                     font: font_assets.franklin_700.clone(),
@@ -151,13 +172,29 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>, font_assets:
                                 },
                             ));
 
-                            button_item(&font_assets, children, "Easy");
-                            button_item(&font_assets, children, "Medium");
-                            button_item(&font_assets, children, "Hard");
+                            for difficulty in
+                                [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard]
+                            {
+                                button_item(
+                                    &font_assets,
+                                    children,
+                                    difficulty.label(),
+                                    GameState::Playing,
+                                )
+                                .insert(PuzzleChoice::Difficulty(difficulty));
+                            }
+                            button_item(&font_assets, children, "Versus", GameState::Race);
+                            button_item(&font_assets, children, "Puzzle Editor", GameState::Editor);
+                            button_item(&font_assets, children, "Custom Puzzle", GameState::Menu)
+                                .remove::<ChangeState>()
+                                .observe(show_custom_panel::<true>);
 
-                            let date_str = chrono::Local::now().format("%B %d, %Y").to_string();
+                            let date_str = locale
+                                .language
+                                .format_date(chrono::Local::now().date_naive());
                             children.spawn((
                                 Text::new(date_str),
+                                LocalizedFont::default(),
                                 TextFont {
                                     font_size: 16.0,
                                     font: font_assets.franklin_700.clone(),
@@ -209,7 +246,7 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>, font_assets:
                 ))
                 .with_children(|parent| {
                     parent.spawn((
-                        Text::new("Made with Bevy"),
+                        Localized::new("Made with Bevy"),
                         TextFont {
                             font_size: 15.0,
                             ..default()
@@ -247,7 +284,7 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>, font_assets:
                 ))
                 .with_children(|parent| {
                     parent.spawn((
-                        Text::new("Open source"),
+                        Localized::new("Open source"),
                         TextFont {
                             font_size: 15.0,
                             ..default()
@@ -265,52 +302,198 @@ fn setup_menu(mut commands: Commands, textures: Res<TextureAssets>, font_assets:
         });
 }
 
-fn button_item(font_assets: &Res<FontAssets>, children: &mut ChildSpawnerCommands<'_>, text: &str) {
+fn button_item<'a>(
+    font_assets: &Res<FontAssets>,
+    children: &'a mut ChildSpawnerCommands<'_>,
+    text: &'static str,
+    state: GameState,
+) -> EntityCommands<'a> {
     let button_colors = ButtonColors {
         normal: *DARK_BLACK,
         hovered: *DARK_BLACK,
     };
+    let mut button = children.spawn((
+        Button,
+        Node {
+            height: Val::Px(44.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            margin: UiRect {
+                left: Val::Px(10.0),
+                right: Val::Px(10.0),
+                bottom: Val::Px(8.0),
+                ..default()
+            },
+            padding: UiRect::axes(Val::Px(16.0), Val::Px(1.0)),
+            min_width: Val::Px(150.0),
+            ..Default::default()
+        },
+        BorderRadius::all(Val::Px(24.0)),
+        // BackgroundColor(button_colors.normal),
+        button_colors,
+        ChangeState(state),
+    ));
+    button.with_child((
+        Localized::new(text),
+        TextFont {
+            font_size: 16.0,
+            font: font_assets.franklin_600.clone(),
+            ..default()
+        },
+        TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
+    ));
+    button
+}
+
+#[derive(Component)]
+struct ChangeState(GameState);
+
+/// 开始游戏时新题目从哪里来
+#[derive(Component)]
+enum PuzzleChoice {
+    /// 这个难度的题目池
+    Difficulty(Difficulty),
+    /// 按自定义条件生成
+    Custom,
+}
+
+/// 自定义题目的设置面板
+#[derive(Component)]
+struct CustomPanel;
+
+#[derive(Component, Debug, Clone, Copy)]
+enum CustomOption {
+    Symmetry,
+    Clues,
+    Minimal,
+    Technique,
+    HardestOther,
+}
+
+fn custom_panel(font_assets: &Res<FontAssets>, children: &mut ChildSpawnerCommands<'_>) {
     children
         .spawn((
-            Button,
+            CustomPanel,
             Node {
-                height: Val::Px(44.0),
-                justify_content: JustifyContent::Center,
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                display: Display::None,
+                flex_direction: FlexDirection::Column,
                 align_items: AlignItems::Center,
-                margin: UiRect {
-                    left: Val::Px(10.0),
-                    right: Val::Px(10.0),
-                    bottom: Val::Px(8.0),
-                    ..default()
-                },
-                padding: UiRect::axes(Val::Px(16.0), Val::Px(1.0)),
-                min_width: Val::Px(150.0),
-                ..Default::default()
-            },
-            BorderRadius::all(Val::Px(24.0)),
-            // BackgroundColor(button_colors.normal),
-            button_colors,
-            ChangeState(GameState::Playing),
-        ))
-        .with_child((
-            Text::new(text),
-            TextFont {
-                font_size: 16.0,
-                font: font_assets.franklin_600.clone(),
+                justify_content: JustifyContent::Center,
                 ..default()
             },
-            TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
-        ));
+            BackgroundColor(Color::srgb_u8(251, 155, 0)),
+            GlobalZIndex(10),
+        ))
+        .with_children(|children| {
+            children.spawn((
+                Localized::new("Custom Puzzle"),
+                TextFont {
+                    font_size: 36.0,
+                    font: font_assets.karnak_500.clone(),
+                    ..default()
+                },
+                TextColor::BLACK,
+                Node {
+                    margin: UiRect::bottom(Val::Px(24.0)),
+                    ..default()
+                },
+            ));
+            for option in [
+                CustomOption::Symmetry,
+                CustomOption::Clues,
+                CustomOption::Minimal,
+                CustomOption::Technique,
+                CustomOption::HardestOther,
+            ] {
+                let mut button = button_item(font_assets, children, "", GameState::Menu);
+                button
+                    .remove::<ChangeState>()
+                    .insert(option)
+                    .observe(cycle_custom_option);
+            }
+            button_item(font_assets, children, "Start", GameState::Playing)
+                .insert(PuzzleChoice::Custom);
+            button_item(font_assets, children, "Cancel", GameState::Menu)
+                .remove::<ChangeState>()
+                .observe(show_custom_panel::<false>);
+        });
 }
 
-#[derive(Component)]
-struct ChangeState(GameState);
+fn show_custom_panel<const SHOW: bool>(
+    _trigger: Trigger<Pointer<Click>>,
+    mut q_panel: Query<&mut Node, With<CustomPanel>>,
+) {
+    for mut node in q_panel.iter_mut() {
+        node.display = if SHOW { Display::Flex } else { Display::None };
+    }
+}
+
+fn cycle_custom_option(
+    trigger: Trigger<Pointer<Click>>,
+    q_option: Query<&CustomOption>,
+    mut custom: ResMut<CustomPuzzle>,
+) {
+    let Ok(option) = q_option.get(trigger.target()) else {
+        return;
+    };
+    let options = &mut custom.options;
+    match option {
+        CustomOption::Symmetry => options.symmetry = options.symmetry.next(),
+        CustomOption::Clues => options.clue_range = next_clue_range(options.clue_range),
+        CustomOption::Minimal => options.minimal = !options.minimal,
+        CustomOption::Technique => options.technique = next_technique(options.technique),
+        CustomOption::HardestOther => {
+            options.hardest_other = next_hardest_other(options.hardest_other)
+        }
+    }
+}
+
+fn update_custom_labels(
+    custom: Res<CustomPuzzle>,
+    q_option: Query<(&CustomOption, &Children)>,
+    mut q_text: Query<&mut Localized>,
+) {
+    let options = &custom.options;
+    for (option, children) in q_option.iter() {
+        let label = match option {
+            CustomOption::Symmetry => {
+                Localized::with_args("Symmetry: {}", [options.symmetry.label()])
+            }
+            CustomOption::Clues => match options.clue_range {
+                Some((min, max)) => Localized::with_args("Clues: {}", [format!("{min}-{max}")]),
+                None => Localized::with_args("Clues: {}", ["Any"]),
+            },
+            CustomOption::Minimal => {
+                Localized::with_args("Minimal: {}", [if options.minimal { "On" } else { "Off" }])
+            }
+            CustomOption::Technique => Localized::with_args(
+                "Practice: {}",
+                [options.technique.map_or("Off", technique_name)],
+            ),
+            CustomOption::HardestOther => Localized::with_args(
+                "Other steps up to: {}",
+                [technique_name(options.hardest_other)],
+            ),
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = q_text.get_mut(child) {
+                *text = label.clone();
+            }
+        }
+    }
+}
 
 #[derive(Component)]
 struct OpenLink(&'static str);
 
+#[allow(clippy::collapsible_if)]
 fn click_play_button(
     mut next_state: ResMut<NextState<GameState>>,
+    mut settings: ResMut<Settings>,
+    mut custom: ResMut<CustomPuzzle>,
     mut interaction_query: Query<
         (
             &Interaction,
@@ -318,13 +501,24 @@ fn click_play_button(
             &ButtonColors,
             Option<&ChangeState>,
             Option<&OpenLink>,
+            Option<&PuzzleChoice>,
         ),
         (Changed<Interaction>, With<Button>),
     >,
 ) {
-    for (interaction, mut color, button_colors, change_state, open_link) in &mut interaction_query {
+    for (interaction, mut color, button_colors, change_state, open_link, choice) in
+        &mut interaction_query
+    {
         match *interaction {
             Interaction::Pressed => {
+                match choice {
+                    Some(PuzzleChoice::Difficulty(difficulty)) => {
+                        settings.puzzle_difficulty = Some(*difficulty);
+                        custom.active = false;
+                    }
+                    Some(PuzzleChoice::Custom) => custom.active = true,
+                    None => {}
+                }
                 if let Some(state) = change_state {
                     next_state.set(state.0.clone());
                 } else if let Some(link) = open_link {
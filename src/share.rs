@@ -1,5 +1,30 @@
-use crate::loading::FontAssets;
+use crate::{
+    i18n::{Locale, Localized, LocalizedFont},
+    loading::FontAssets,
+    theme::{ThemeRole, ThemedBackground, ThemedText},
+};
 use bevy::prelude::*;
+use sudoku::Sudoku;
+
+pub struct SharePlugin;
+
+/// 分享题目和成绩用到的编码、剪贴板和提示
+impl Plugin for SharePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(on_show_toast).add_systems(
+            Update,
+            (
+                expire_toast,
+                update_title_date
+                    .run_if(resource_changed::<Locale>.or(any_match_filter::<Added<TitleDate>>)),
+            ),
+        );
+    }
+}
+
+/// 顶部标题栏
+#[derive(Component)]
+pub struct TitleBar;
 
 /// 顶部标题栏
 pub fn title_bar(font_assets: &Res<FontAssets>, builder: &mut ChildSpawnerCommands<'_>) {
@@ -17,10 +42,11 @@ pub fn title_bar(font_assets: &Res<FontAssets>, builder: &mut ChildSpawnerComman
                     bottom: Val::Px(20.0),
                 },
                 max_width: Val::Px(1280.0),
-                width: Val::Px(1280.0),
+                width: Val::Percent(100.0),
                 align_items: AlignItems::Baseline,
                 ..default()
             },
+            TitleBar,
             // BackgroundColor(GAME_YELLOW),
         ))
         .with_children(|builder| {
@@ -40,13 +66,13 @@ pub fn title_bar(font_assets: &Res<FontAssets>, builder: &mut ChildSpawnerComman
                 ))
                 .with_children(|p| {
                     p.spawn((
-                        Text::new("Sudoku"),
+                        Localized::new("Sudoku"),
                         TextFont {
                             font_size: 42.0,
                             font: font_assets.karnak.clone(),
                             ..default()
                         },
-                        TextColor::BLACK,
+                        ThemedText(ThemeRole::Text),
                     ));
                 });
 
@@ -61,16 +87,414 @@ pub fn title_bar(font_assets: &Res<FontAssets>, builder: &mut ChildSpawnerComman
                     // BackgroundColor(GRAY),
                 ))
                 .with_children(|p| {
-                    let date_str = chrono::Local::now().format("%B %d, %Y").to_string();
                     p.spawn((
-                        Text::new(date_str),
+                        TitleDate,
                         TextFont {
                             font_size: 28.0,
                             font: font_assets.franklin_500.clone(),
                             ..default()
                         },
-                        TextColor::BLACK,
+                        ThemedText(ThemeRole::Text),
                     ));
                 });
         });
 }
+
+/// 标题栏上今天的日期, 按界面语言显示
+#[derive(Component)]
+#[require(Text, LocalizedFont)]
+struct TitleDate;
+
+fn update_title_date(locale: Res<Locale>, mut q_date: Query<&mut Text, With<TitleDate>>) {
+    let date = locale
+        .language
+        .format_date(chrono::Local::now().date_naive());
+    for mut text in q_date.iter_mut() {
+        text.0 = date.clone();
+    }
+}
+
+/// 分享码的版本号, 改格式时递增
+const SHARE_CODE_VERSION: char = '1';
+
+/// 网页地址里分享码的参数名
+const SHARE_QUERY: &str = "p=";
+
+/// 链接里查询参数 `p` 的值, 不会误认 `step=` 之类的参数
+fn share_query_value(link: &str) -> Option<&str> {
+    let query = link.split_once('?')?.1;
+    let query = query.split('#').next().unwrap_or(query);
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix(SHARE_QUERY))
+}
+
+/// 从分享码还原的题目, 可以带上已经填好的格子
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedPuzzle {
+    pub puzzle: Sudoku,
+    /// 玩家填的数字, 0 表示空格, 题目给出的格子总是 0
+    pub progress: Option<[u8; 81]>,
+    /// 每格的中心和角标候选数, 第 n 位是数字 n + 1
+    pub marks: Option<[(u16, u16); 81]>,
+}
+
+/// 编码成 URL 安全的分享码: 版本号 + 题目 [+ "." + 进度 [+ "." + 候选数]]
+pub fn encode_share_code(
+    puzzle: &Sudoku,
+    progress: Option<&[u8; 81]>,
+    marks: Option<&[(u16, u16); 81]>,
+) -> String {
+    let mut code = SHARE_CODE_VERSION.to_string();
+    code.push_str(&base64url_encode(&pack_cells(&puzzle.to_bytes())));
+    let progress = progress.filter(|progress| progress.iter().any(|digit| *digit != 0));
+    let marks = marks.filter(|marks| marks.iter().any(|mark| *mark != (0, 0)));
+    if progress.is_some() || marks.is_some() {
+        code.push('.');
+        if let Some(progress) = progress {
+            code.push_str(&base64url_encode(&pack_cells(progress)));
+        }
+    }
+    if let Some(marks) = marks {
+        code.push('.');
+        code.push_str(&base64url_encode(&pack_marks(marks)));
+    }
+    code
+}
+
+/// 解析分享码, 也接受带 `?p=` 的完整链接
+pub fn decode_share_code(text: &str) -> Option<SharedPuzzle> {
+    let text = text.trim();
+    let code = if text.contains('?') {
+        share_query_value(text)?
+    } else {
+        text
+    };
+    let code = code.strip_prefix(SHARE_CODE_VERSION)?;
+    let mut parts = code.split('.');
+    let givens = parts.next()?;
+    let progress = parts.next().filter(|progress| !progress.is_empty());
+    let marks = match parts.next() {
+        Some(marks) => Some(unpack_marks(&base64url_decode(marks)?)?),
+        None => None,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    let givens = unpack_cells(&base64url_decode(givens)?)?;
+    let puzzle = Sudoku::from_bytes(givens).ok()?;
+    let progress = match progress {
+        Some(progress) => {
+            let mut progress = unpack_cells(&base64url_decode(progress)?)?;
+            for (digit, given) in progress.iter_mut().zip(givens) {
+                if given != 0 {
+                    *digit = 0;
+                }
+            }
+            Some(progress)
+        }
+        None => None,
+    };
+    let marks = marks.map(|mut marks| {
+        for (mark, given) in marks.iter_mut().zip(givens) {
+            if given != 0 {
+                *mark = (0, 0);
+            }
+        }
+        marks
+    });
+    Some(SharedPuzzle {
+        puzzle,
+        progress,
+        marks,
+    })
+}
+
+/// 81 位的有无数字位图, 后面每个数字占半个字节
+fn pack_cells(cells: &[u8; 81]) -> Vec<u8> {
+    let mut bytes = vec![0u8; 11];
+    let mut digits = vec![];
+    for (index, digit) in cells.iter().enumerate() {
+        if *digit != 0 {
+            bytes[index / 8] |= 1 << (index % 8);
+            digits.push(*digit);
+        }
+    }
+    bytes.extend(
+        digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0)),
+    );
+    bytes
+}
+
+fn unpack_cells(bytes: &[u8]) -> Option<[u8; 81]> {
+    let (bitmap, packed) = bytes.split_at_checked(11)?;
+    let mut digits = packed.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]);
+    let mut cells = [0u8; 81];
+    for (index, cell) in cells.iter_mut().enumerate() {
+        if bitmap[index / 8] & (1 << (index % 8)) != 0 {
+            *cell = digits.next().filter(|digit| (1..=9).contains(digit))?;
+        }
+    }
+    // 最多只允许补齐用的半个字节
+    if digits.any(|digit| digit != 0) {
+        return None;
+    }
+    Some(cells)
+}
+
+/// 81 位的有无候选数位图, 后面每格 3 个字节: 中心候选数在高 9 位, 角标在低 9 位
+fn pack_marks(marks: &[(u16, u16); 81]) -> Vec<u8> {
+    let mut bytes = vec![0u8; 11];
+    let mut packed = vec![];
+    for (index, (centre, corner)) in marks.iter().enumerate() {
+        if (*centre, *corner) != (0, 0) {
+            bytes[index / 8] |= 1 << (index % 8);
+            let bits = ((*centre as u32) & 0x1ff) << 9 | (*corner as u32) & 0x1ff;
+            packed.extend([(bits >> 16) as u8, (bits >> 8) as u8, bits as u8]);
+        }
+    }
+    bytes.extend(packed);
+    bytes
+}
+
+fn unpack_marks(bytes: &[u8]) -> Option<[(u16, u16); 81]> {
+    let (bitmap, packed) = bytes.split_at_checked(11)?;
+    let mut cells = packed.chunks(3);
+    let mut marks = [(0u16, 0u16); 81];
+    for (index, mark) in marks.iter_mut().enumerate() {
+        if bitmap[index / 8] & (1 << (index % 8)) != 0 {
+            let [high, middle, low] = *cells.next()? else {
+                return None;
+            };
+            let bits = (high as u32) << 16 | (middle as u32) << 8 | low as u32;
+            if bits >= 1 << 18 {
+                return None;
+            }
+            *mark = ((bits >> 9) as u16, (bits & 0x1ff) as u16);
+        }
+    }
+    if cells.next().is_some() {
+        return None;
+    }
+    Some(marks)
+}
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn base64url_encode(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            text.push(BASE64URL[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    text
+}
+
+fn base64url_decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    for chunk in text.as_bytes().chunks(4) {
+        if chunk.len() < 2 {
+            return None;
+        }
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = BASE64URL.iter().position(|b| b == c)? as u32;
+            n |= value << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// 类似 NYT 的成绩卡片, 每个宫一个方块, 用过揭示的宫是黄色
+pub fn share_card(difficulty: &str, time: &str, clean_boxes: &[bool; 9]) -> String {
+    let mut card = format!("Sudoku · {} · {}", difficulty, time);
+    for row in clean_boxes.chunks(3) {
+        card.push('\n');
+        for clean in row {
+            card.push(if *clean { '🟩' } else { '🟨' });
+        }
+    }
+    card
+}
+
+/// 网页版分享完整链接, 桌面版只分享分享码
+pub fn share_link(code: &str) -> String {
+    #[cfg(target_arch = "wasm32")]
+    if let Some(location) = web_sys::window().map(|window| window.location())
+        && let (Ok(origin), Ok(path)) = (location.origin(), location.pathname())
+    {
+        return format!("{}{}?{}{}", origin, path, SHARE_QUERY, code);
+    }
+    code.to_string()
+}
+
+/// 网页地址里带的分享码
+pub fn page_share_code() -> Option<String> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let search = web_sys::window()?.location().search().ok()?;
+        share_query_value(&search).map(str::to_string)
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    None
+}
+
+pub fn copy_to_clipboard(text: &str) -> bool {
+    #[cfg(target_arch = "wasm32")]
+    {
+        web_sys::window()
+            .map(|window| {
+                let _ = window.navigator().clipboard().write_text(text);
+            })
+            .is_some()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text))
+            .inspect_err(|error| warn!("Failed to copy to clipboard: {error}"))
+            .is_ok()
+    }
+}
+
+/// 网页版读剪贴板需要异步授权, 只在桌面版支持粘贴
+pub fn read_clipboard() -> Option<String> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        None
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.get_text())
+            .ok()
+    }
+}
+
+/// 在屏幕底部短暂显示一行提示
+#[derive(Event)]
+pub struct ShowToast(pub String);
+
+#[derive(Component)]
+struct Toast(Timer);
+
+fn on_show_toast(
+    trigger: Trigger<ShowToast>,
+    font_assets: Res<FontAssets>,
+    locale: Res<Locale>,
+    q_toast: Query<Entity, With<Toast>>,
+    mut commands: Commands,
+) {
+    for entity in q_toast.iter() {
+        commands.entity(entity).despawn();
+    }
+    commands
+        .spawn((
+            Toast(Timer::from_seconds(2.5, TimerMode::Once)),
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(40.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            GlobalZIndex(1000),
+            Pickable::IGNORE,
+        ))
+        .with_children(|builder| {
+            builder
+                .spawn((
+                    Node {
+                        padding: UiRect::axes(Val::Px(20.0), Val::Px(10.0)),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(4.0)),
+                    ThemedBackground(ThemeRole::Button),
+                ))
+                .with_child((
+                    Text::new(locale.tr(&trigger.event().0)),
+                    LocalizedFont::default(),
+                    TextFont {
+                        font_size: 16.0,
+                        font: font_assets.franklin_500.clone(),
+                        ..default()
+                    },
+                    ThemedText(ThemeRole::ButtonText),
+                ));
+        });
+}
+
+fn expire_toast(time: Res<Time>, mut q_toast: Query<(Entity, &mut Toast)>, mut commands: Commands) {
+    for (entity, mut toast) in q_toast.iter_mut() {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[test]
+fn test_share_code_round_trip() {
+    let puzzle = Sudoku::generate();
+    let solution = puzzle.solution().unwrap().to_bytes();
+    let givens = puzzle.to_bytes();
+    let mut progress = [0u8; 81];
+    for index in (0..81).filter(|index| givens[*index] == 0).take(5) {
+        progress[index] = solution[index];
+    }
+
+    let code = encode_share_code(&puzzle, Some(&progress), None);
+    assert!(code.len() < 80);
+    assert!(
+        code.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+    );
+    let shared = decode_share_code(&format!("https://example.com/?{}{}", SHARE_QUERY, code));
+    assert_eq!(
+        shared,
+        Some(SharedPuzzle {
+            puzzle,
+            progress: Some(progress),
+            marks: None,
+        })
+    );
+
+    let link = format!("https://example.com/?step=2&{}{}#top", SHARE_QUERY, code);
+    assert_eq!(decode_share_code(&link).unwrap().puzzle, puzzle);
+
+    let mut marks = [(0u16, 0u16); 81];
+    let empty = (0..81)
+        .filter(|index| givens[*index] == 0)
+        .collect::<Vec<_>>();
+    marks[empty[5]] = (0b1_0000_0011, 0);
+    marks[empty[empty.len() - 1]] = (0b110, 0b1_1000_0000);
+    for progress in [Some(&progress), None] {
+        let code = encode_share_code(&puzzle, progress, Some(&marks));
+        let shared = decode_share_code(&code).unwrap();
+        assert_eq!(shared.progress.as_ref(), progress);
+        assert_eq!(shared.marks, Some(marks));
+    }
+
+    let code = encode_share_code(&puzzle, None, None);
+    assert_eq!(decode_share_code(&code).unwrap().progress, None);
+    assert_eq!(decode_share_code("2abc"), None);
+}
+
+#[test]
+fn test_share_card() {
+    let mut boxes = [true; 9];
+    boxes[4] = false;
+    assert_eq!(
+        share_card("Easy", "00:05:00", &boxes),
+        "Sudoku · Easy · 00:05:00\n🟩🟩🟩\n🟩🟨🟩\n🟩🟩🟩"
+    );
+}
@@ -0,0 +1,367 @@
+use crate::{color::*, game::Settings};
+use bevy::prelude::*;
+
+pub struct ThemePlugin;
+
+/// 主题相关的颜色都从 [`Theme`] 里取, 切换主题时所有带 `Themed*` 组件的节点会一起刷新
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Theme>()
+            .add_systems(Update, sync_theme.run_if(resource_changed::<Settings>))
+            .add_systems(
+                PostUpdate,
+                (
+                    apply_clear_color.run_if(resource_changed::<Theme>),
+                    apply_themed_background,
+                    apply_themed_text,
+                    apply_themed_border,
+                    apply_themed_image,
+                ),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ThemeKind {
+    #[default]
+    Light,
+    Dark,
+    HighContrast,
+}
+
+/// 色盲友好配色, 在当前主题上替换选中、冲突、提示等依赖颜色区分的部分
+///
+/// 颜色取自 Okabe-Ito 配色, 在对应的色觉缺陷下仍能互相区分
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorBlindPalette {
+    #[default]
+    Standard,
+    /// 绿色弱
+    Deuteranopia,
+    /// 红色弱
+    Protanopia,
+    /// 蓝黄色弱
+    Tritanopia,
+}
+
+impl ColorBlindPalette {
+    pub fn next(self) -> Self {
+        match self {
+            ColorBlindPalette::Standard => ColorBlindPalette::Deuteranopia,
+            ColorBlindPalette::Deuteranopia => ColorBlindPalette::Protanopia,
+            ColorBlindPalette::Protanopia => ColorBlindPalette::Tritanopia,
+            ColorBlindPalette::Tritanopia => ColorBlindPalette::Standard,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColorBlindPalette::Standard => "Standard",
+            ColorBlindPalette::Deuteranopia => "Deuteranopia",
+            ColorBlindPalette::Protanopia => "Protanopia",
+            ColorBlindPalette::Tritanopia => "Tritanopia",
+        }
+    }
+}
+
+/// 界面上颜色的语义
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ThemeRole {
+    /// 窗口背景
+    Background,
+    /// 弹窗、菜单、工具栏的底色
+    Surface,
+    /// 鼠标悬停时的底色
+    SurfaceHover,
+    Text,
+    SecondaryText,
+    /// 分割线
+    Border,
+    /// 宫格之间的边框
+    BoardFrame,
+    CellBorder,
+    CellBackground,
+    /// 题目给出的格子
+    GivenCell,
+    SelectedCell,
+    /// 与选中格子同行、同列、同宫的格子
+    PeerHighlight,
+    Digit,
+    RevealedDigit,
+    /// 试填时改过的格子
+    TrialDigit,
+    CandidateText,
+    PreviewCandidate,
+    Conflict,
+    Button,
+    ButtonText,
+    KeyBackground,
+    KeyBorder,
+}
+
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub kind: ThemeKind,
+    pub palette: ColorBlindPalette,
+    pub background: Color,
+    pub surface: Color,
+    pub surface_hover: Color,
+    pub text: Color,
+    pub secondary_text: Color,
+    pub border: Color,
+    pub board_frame: Color,
+    pub cell_border: Color,
+    pub cell_background: Color,
+    pub given_cell: Color,
+    pub selected_cell: Color,
+    pub peer_highlight: Color,
+    pub digit: Color,
+    pub revealed_digit: Color,
+    pub trial_digit: Color,
+    pub candidate_text: Color,
+    pub preview_candidate: Color,
+    pub conflict: Color,
+    pub button: Color,
+    pub button_text: Color,
+    pub key_background: Color,
+    pub key_border: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::light()
+    }
+}
+
+impl Theme {
+    pub fn from_kind(kind: ThemeKind) -> Self {
+        match kind {
+            ThemeKind::Light => Self::light(),
+            ThemeKind::Dark => Self::dark(),
+            ThemeKind::HighContrast => Self::high_contrast(),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            kind: ThemeKind::Light,
+            palette: ColorBlindPalette::Standard,
+            background: WHITE_COLOR,
+            surface: WHITE_COLOR,
+            surface_hover: *EXTRA_LIGHT_GRAY,
+            text: *DARK_BLACK,
+            secondary_text: *DARK_GRAY,
+            border: *EXTRA_LIGHT_GRAY,
+            board_frame: *GRAY,
+            cell_border: *LIGHT_GRAY,
+            cell_background: WHITE_COLOR,
+            given_cell: *EXTRA_LIGHT_GRAY,
+            selected_cell: *STRANDS_YELLOW,
+            peer_highlight: *PALE_YELLOW,
+            digit: *DARK_BLACK,
+            revealed_digit: *ACCENT_BLUE,
+            trial_digit: *OKABE_GREEN,
+            candidate_text: *GRAY2,
+            preview_candidate: *LIGHTER_GRAY,
+            conflict: *CONFLICT_RED,
+            button: *DARK_BLACK,
+            button_text: WHITE_COLOR,
+            key_background: *EXTRA_LIGHT_GRAY,
+            key_border: *GRAY,
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            kind: ThemeKind::Dark,
+            palette: ColorBlindPalette::Standard,
+            background: *DARK_BLACK,
+            surface: *MED_BLACK,
+            surface_hover: *DARKEST_GRAY,
+            text: *LIGHTEST_GRAY,
+            secondary_text: *GRAY,
+            border: *DARKEST_GRAY,
+            board_frame: *DARK_GRAY,
+            cell_border: *EXTRA_DARK_GRAY,
+            cell_background: *BLACK,
+            given_cell: *DARKEST_GRAY,
+            selected_cell: *DARK_YELLOW,
+            peer_highlight: *EXTRA_DARK_GRAY,
+            digit: *LIGHTEST_GRAY,
+            revealed_digit: *ACCENT_LIGHT_BLUE,
+            trial_digit: *OKABE_GREEN,
+            candidate_text: *GRAY,
+            preview_candidate: *DARKER_GRAY,
+            conflict: *CONFLICT_RED,
+            button: *LIGHTEST_GRAY,
+            button_text: *DARK_BLACK,
+            key_background: *DARKEST_GRAY,
+            key_border: *DARK_GRAY,
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            kind: ThemeKind::HighContrast,
+            palette: ColorBlindPalette::Standard,
+            background: WHITE_COLOR,
+            surface: WHITE_COLOR,
+            surface_hover: *LIGHTER_GRAY,
+            text: Color::BLACK,
+            secondary_text: Color::BLACK,
+            border: Color::BLACK,
+            board_frame: Color::BLACK,
+            cell_border: *DARK_GRAY,
+            cell_background: WHITE_COLOR,
+            given_cell: *LIGHTER_GRAY,
+            selected_cell: *GAME_YELLOW,
+            peer_highlight: *PALE_YELLOW,
+            digit: Color::BLACK,
+            revealed_digit: *ACCENT_XD_BLUE,
+            trial_digit: *OKABE_GREEN,
+            candidate_text: *BLACK,
+            preview_candidate: *DARK_GRAY,
+            conflict: *CONFLICT_RED,
+            button: Color::BLACK,
+            button_text: WHITE_COLOR,
+            key_background: WHITE_COLOR,
+            key_border: Color::BLACK,
+        }
+    }
+
+    pub fn with_palette(mut self, palette: ColorBlindPalette) -> Self {
+        let dark = self.kind == ThemeKind::Dark;
+        match palette {
+            ColorBlindPalette::Standard => {}
+            ColorBlindPalette::Deuteranopia | ColorBlindPalette::Protanopia => {
+                // 红色弱看红色偏暗, 用橙色代替
+                self.conflict = if palette == ColorBlindPalette::Protanopia {
+                    *OKABE_ORANGE
+                } else {
+                    *OKABE_VERMILLION
+                };
+                self.revealed_digit = if dark { *OKABE_SKY_BLUE } else { *OKABE_BLUE };
+                self.trial_digit = *OKABE_PURPLE;
+                if !dark {
+                    self.selected_cell = *OKABE_YELLOW;
+                    self.peer_highlight = *PALE_SKY_BLUE;
+                }
+            }
+            ColorBlindPalette::Tritanopia => {
+                self.conflict = *OKABE_VERMILLION;
+                self.revealed_digit = *OKABE_GREEN;
+                self.trial_digit = *OKABE_BLUE;
+                if !dark {
+                    self.selected_cell = *OKABE_PURPLE;
+                    self.peer_highlight = *PALE_PURPLE;
+                }
+            }
+        }
+        self.palette = palette;
+        self
+    }
+
+    pub fn color(&self, role: ThemeRole) -> Color {
+        match role {
+            ThemeRole::Background => self.background,
+            ThemeRole::Surface => self.surface,
+            ThemeRole::SurfaceHover => self.surface_hover,
+            ThemeRole::Text => self.text,
+            ThemeRole::SecondaryText => self.secondary_text,
+            ThemeRole::Border => self.border,
+            ThemeRole::BoardFrame => self.board_frame,
+            ThemeRole::CellBorder => self.cell_border,
+            ThemeRole::CellBackground => self.cell_background,
+            ThemeRole::GivenCell => self.given_cell,
+            ThemeRole::SelectedCell => self.selected_cell,
+            ThemeRole::PeerHighlight => self.peer_highlight,
+            ThemeRole::Digit => self.digit,
+            ThemeRole::RevealedDigit => self.revealed_digit,
+            ThemeRole::TrialDigit => self.trial_digit,
+            ThemeRole::CandidateText => self.candidate_text,
+            ThemeRole::PreviewCandidate => self.preview_candidate,
+            ThemeRole::Conflict => self.conflict,
+            ThemeRole::Button => self.button,
+            ThemeRole::ButtonText => self.button_text,
+            ThemeRole::KeyBackground => self.key_background,
+            ThemeRole::KeyBorder => self.key_border,
+        }
+    }
+}
+
+/// 背景色跟随主题
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+#[require(BackgroundColor)]
+pub struct ThemedBackground(pub ThemeRole);
+
+/// 文字颜色跟随主题
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+#[require(TextColor)]
+pub struct ThemedText(pub ThemeRole);
+
+/// 边框颜色跟随主题
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+#[require(BorderColor)]
+pub struct ThemedBorder(pub ThemeRole);
+
+/// 图片着色跟随主题
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+#[require(ImageNode)]
+pub struct ThemedImage(pub ThemeRole);
+
+fn sync_theme(settings: Res<Settings>, mut theme: ResMut<Theme>) {
+    let kind = if settings.high_contrast {
+        ThemeKind::HighContrast
+    } else if settings.dark_mode {
+        ThemeKind::Dark
+    } else {
+        ThemeKind::Light
+    };
+
+    if theme.kind != kind || theme.palette != settings.palette {
+        *theme = Theme::from_kind(kind).with_palette(settings.palette);
+    }
+}
+
+fn apply_clear_color(theme: Res<Theme>, mut clear_color: ResMut<ClearColor>) {
+    clear_color.0 = theme.color(ThemeRole::Background);
+}
+
+fn apply_themed_background(
+    theme: Res<Theme>,
+    mut q_bg: Query<(Ref<ThemedBackground>, &mut BackgroundColor)>,
+) {
+    for (themed, mut bg) in q_bg.iter_mut() {
+        if theme.is_changed() || themed.is_changed() {
+            bg.set_if_neq(BackgroundColor(theme.color(themed.0)));
+        }
+    }
+}
+
+fn apply_themed_text(theme: Res<Theme>, mut q_text: Query<(Ref<ThemedText>, &mut TextColor)>) {
+    for (themed, mut text_color) in q_text.iter_mut() {
+        if theme.is_changed() || themed.is_changed() {
+            text_color.set_if_neq(TextColor(theme.color(themed.0)));
+        }
+    }
+}
+
+fn apply_themed_border(
+    theme: Res<Theme>,
+    mut q_border: Query<(Ref<ThemedBorder>, &mut BorderColor)>,
+) {
+    for (themed, mut border) in q_border.iter_mut() {
+        if theme.is_changed() || themed.is_changed() {
+            border.set_if_neq(BorderColor(theme.color(themed.0)));
+        }
+    }
+}
+
+fn apply_themed_image(theme: Res<Theme>, mut q_image: Query<(Ref<ThemedImage>, &mut ImageNode)>) {
+    for (themed, mut image) in q_image.iter_mut() {
+        let color = theme.color(themed.0);
+        if (theme.is_changed() || themed.is_changed()) && image.color != color {
+            image.color = color;
+        }
+    }
+}